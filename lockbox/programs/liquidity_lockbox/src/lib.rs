@@ -1,6 +1,7 @@
 pub mod state;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, CloseAccount};
 use anchor_spl::associated_token::AssociatedToken;
 use whirlpool::{
   self,
@@ -10,7 +11,7 @@ use whirlpool::{
   cpi::accounts::CollectFees,
   cpi::accounts::ClosePosition
 };
-use solana_program::{pubkey::Pubkey, program::invoke_signed};
+use solana_program::{pubkey::Pubkey, program::invoke_signed, program_option::COption};
 use spl_token::instruction::{burn_checked, close_account, mint_to};
 pub use state::*;
 
@@ -23,12 +24,8 @@ pub mod liquidity_lockbox {
 
   // Orca Whirlpool program address
   const ORCA: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
-  // OLAS-SOL Whirlpool address
-  const WHIRLPOOL: Pubkey = pubkey!("5dMKUYJDsjZkAD3wiV3ViQkuq9pSmWQ5eAzcQLtDnUT3");
-  // SOL address
-  const SOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
-  // OLAS address
-  const OLAS: Pubkey = pubkey!("Ez3nzG9ofodYCvEmw73XhQ87LWNYVRM2s7diB5tBZPyM");
+  // Lockbox v2 program address
+  const LOCKBOX2: Pubkey = pubkey!("1BoXeb8hobfLCHNsyCoG1jpEv41ez4w4eDrJ48N1jY3");
   // Position account discriminator
   const POSITION_HEADER: [u8; 8] = [0xaa, 0xbc, 0x8f, 0xe4, 0x7a, 0x40, 0xf7, 0xd0];
   // Full range lower and upper indexes
@@ -36,19 +33,41 @@ pub mod liquidity_lockbox {
   const TICK_UPPER_INDEX: i32 = 443584;
   // Bridged token decimals
   const BRIDGED_TOKEN_DECIMALS: u8 = 8;
+  // Number of remaining accounts supplied per lockbox position
+  const POSITION_ACCOUNTS_LEN: usize = 5;
 
 
   /// Initializes a Lockbox account that stores state data.
   pub fn initialize(
     ctx: Context<InitializeLiquidityLockbox>
   ) -> Result<()> {
-    // Check that the first token mint is SOL
-    if ctx.accounts.fee_collector_token_owner_account_a.mint != SOL {
+    // Check the bridged token mint authority
+    if ctx.accounts.bridged_token_mint.mint_authority != COption::Some(ctx.accounts.lockbox.key()) {
+      return Err(ErrorCode::WrongMintAuthority.into());
+    }
+
+    // Check that the bridged token mint has no freeze authority
+    if ctx.accounts.bridged_token_mint.freeze_authority.is_some() {
+      return Err(ErrorCode::FreezeAuthoritySet.into());
+    }
+
+    // Check the bridged token decimals
+    if ctx.accounts.bridged_token_mint.decimals != BRIDGED_TOKEN_DECIMALS {
+      return Err(ErrorCode::WrongMintDecimals.into());
+    }
+
+    // Check that no bridged tokens are minted yet
+    if ctx.accounts.bridged_token_mint.supply != 0 {
+      return Err(ErrorCode::BridgedSupplyNotZero.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    if ctx.accounts.fee_collector_token_owner_account_a.mint != ctx.accounts.whirlpool.token_mint_a {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
-    // Check that the second token mint is OLAS
-    if ctx.accounts.fee_collector_token_owner_account_b.mint != OLAS {
+    // Check that the second token mint is the whirlpool token B
+    if ctx.accounts.fee_collector_token_owner_account_b.mint != ctx.accounts.whirlpool.token_mint_b {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
@@ -63,9 +82,14 @@ pub mod liquidity_lockbox {
       bump,
       ctx.accounts.bridged_token_mint.key(),
       ctx.accounts.fee_collector_token_owner_account_a.key(),
-      ctx.accounts.fee_collector_token_owner_account_b.key()
+      ctx.accounts.fee_collector_token_owner_account_b.key(),
+      ctx.accounts.whirlpool.key()
     )?;
 
+    // Initialize the index of active lockbox positions
+    let mut position_index = ctx.accounts.position_index.load_init()?;
+    position_index.initialize(*ctx.bumps.get("position_index").unwrap())?;
+
     Ok(())
   }
 
@@ -73,7 +97,8 @@ pub mod liquidity_lockbox {
   ///
   /// ### Parameters
   /// - `id` - Lockbox position ID. Must be equal to the current total number of lockbox positions.
-  pub fn deposit(ctx: Context<DepositPositionForLiquidity>, id: u32) -> Result<()> {
+  /// - `proof` - Allowlist Merkle proof of the signer, empty if the allowlist mode is disabled.
+  pub fn deposit(ctx: Context<DepositPositionForLiquidity>, id: u32, proof: Vec<[u8; 32]>) -> Result<()> {
     let whirlpool = ctx.accounts.position.whirlpool;
     let position_mint = ctx.accounts.position.position_mint;
     let liquidity = ctx.accounts.position.liquidity;
@@ -81,7 +106,7 @@ pub mod liquidity_lockbox {
     let tick_upper_index = ctx.accounts.position.tick_upper_index;
 
     // Check the whirlpool
-    if whirlpool != WHIRLPOOL {
+    if whirlpool != ctx.accounts.lockbox.whirlpool {
         return Err(ErrorCode::WrongWhirlpool.into());
     }
 
@@ -131,6 +156,15 @@ pub mod liquidity_lockbox {
       return Err(ErrorCode::WrongLockboxPDA.into());
     }
 
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check the deposit limits
+    ctx.accounts.lockbox.check_deposit_limits(position_liquidity)?;
+
+    // Check that the signer is allowlisted
+    ctx.accounts.lockbox.check_allowlist(&ctx.accounts.signer.key(), &proof)?;
+
     // Check the id that has to match the number of lockbox positions in order to create a correct account
     // The position needs to be provided as an argument since it's passed into the instruction field
     let num_positions = ctx.accounts.lockbox.num_positions;
@@ -188,20 +222,33 @@ pub mod liquidity_lockbox {
       &[&ctx.accounts.lockbox.seeds()],
     )?;
 
-    // Record position liquidity amount and its correspondent account address
+    // Record position liquidity amount, its correspondent account address and the deposit details
+    let clock = Clock::get()?;
     let pda_lockbox_position = &mut ctx.accounts.pda_lockbox_position;
     pda_lockbox_position.initialize(
       id,
       *ctx.bumps.get("pda_lockbox_position").unwrap(),
       position_liquidity,
       ctx.accounts.position.key(),
-      ctx.accounts.pda_position_account.key()
+      ctx.accounts.pda_position_account.key(),
+      ctx.accounts.signer.key(),
+      clock.slot,
+      clock.unix_timestamp
     )?;
 
     // Increase the amount of total bridged token liquidity and the number of position accounts
     ctx.accounts.lockbox.total_liquidity += position_liquidity;
     ctx.accounts.lockbox.num_positions += 1;
 
+    // Record the deposit in the user state and check it against the user cap
+    let lockbox = ctx.accounts.lockbox.key();
+    let user = ctx.accounts.signer.key();
+    ctx.accounts.user_state.initialize_if_needed(*ctx.bumps.get("user_state").unwrap(), lockbox, user)?;
+    ctx.accounts.user_state.record_deposit(position_liquidity, ctx.accounts.lockbox.max_user_deposit)?;
+
+    // Add the position to the index of active positions
+    ctx.accounts.position_index.load_mut()?.add(id, position_liquidity)?;
+
     emit!(DepositEvent {
       signer: ctx.accounts.signer.key(),
       pda_lockbox_position: ctx.accounts.pda_lockbox_position.key(),
@@ -218,8 +265,8 @@ pub mod liquidity_lockbox {
   /// ### Parameters
   /// - `id` - Lockbox position ID. Must be smaller than the total number of lockbox positions.
   /// - `amount` - Amount of bridged tokens corresponding to the position liquidity part to withdraw.
-  /// - `token_min_a` - The minimum amount of SOL the signer is willing to withdraw.
-  /// - `token_min_b` - The minimum amount of OLAS the signer is willing to withdraw.
+  /// - `token_min_a` - The minimum amount of token A the signer is willing to withdraw.
+  /// - `token_min_b` - The minimum amount of token B the signer is willing to withdraw.
   pub fn withdraw(
     ctx: Context<WithdrawLiquidityForTokens>,
     id: u32,
@@ -248,19 +295,27 @@ pub mod liquidity_lockbox {
       return Err(ErrorCode::WrongPDAPositionAccount.into());
     }
 
+    // Check the lockbox position state layout version
+    ctx.accounts.pda_lockbox_position.check_version()?;
+
     // Check the lockbox PDA address correctness
     let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
     if lockbox_pda.0 != ctx.accounts.lockbox.key() {
       return Err(ErrorCode::WrongLockboxPDA.into());
     }
 
-    // Check that the first token mint is SOL
-    if ctx.accounts.token_owner_account_a.mint != SOL || ctx.accounts.token_vault_a.mint != SOL {
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
-    // Check that the second token mint is OLAS
-    if ctx.accounts.token_owner_account_b.mint != OLAS || ctx.accounts.token_vault_b.mint != OLAS {
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
@@ -380,7 +435,7 @@ pub mod liquidity_lockbox {
       let cpi_program_close_position = ctx.accounts.whirlpool_program.to_account_info();
       let cpi_accounts_close_position = ClosePosition {
         position_authority: ctx.accounts.lockbox.to_account_info(),
-        receiver: ctx.accounts.signer.to_account_info(),
+        receiver: ctx.accounts.depositor.to_account_info(),
         position: ctx.accounts.position.to_account_info(),
         position_mint: ctx.accounts.position_mint.to_account_info(),
         position_token_account: ctx.accounts.pda_position_account.to_account_info(),
@@ -394,10 +449,10 @@ pub mod liquidity_lockbox {
       );
       whirlpool::cpi::close_position(cpi_ctx_close_position)?;
 
-      // Close the pda_lockbox_position account and send all lamports to the receiver
+      // Close the pda_lockbox_position account and send all lamports to the original depositor
       // Secure reference: https://github.com/coral-xyz/sealevel-attacks/blob/master/programs/9-closing-accounts/secure/src/lib.rs
       // which was previously implemented here: https://github.com/valory-xyz/lockbox-solana/blob/04781fa3968a14347ee33f1c4df6184d902e4c6f/lockbox/programs/liquidity_lockbox/src/lib.rs#L402-L419
-      ctx.accounts.pda_lockbox_position.close(ctx.accounts.signer.to_account_info())?;
+      ctx.accounts.pda_lockbox_position.close(ctx.accounts.depositor.to_account_info())?;
     } else {
       // Update position liquidity
       ctx.accounts.pda_lockbox_position.position_liquidity = remainder;
@@ -406,6 +461,15 @@ pub mod liquidity_lockbox {
     // Decrease the total liquidity amount
     ctx.accounts.lockbox.total_liquidity -= amount;
 
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(amount, Clock::get()?.slot)?;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), amount)?;
+
+    // Update the position liquidity in the index of active positions, or remove the position if it is closed
+    ctx.accounts.position_index.load_mut()?.update(id, remainder)?;
+
     emit!(WithdrawEvent {
       signer: ctx.accounts.signer.key(),
       pda_lockbox_position: ctx.accounts.pda_lockbox_position.key(),
@@ -419,202 +483,1903 @@ pub mod liquidity_lockbox {
 
     Ok(())
   }
-}
 
-#[derive(Accounts)]
-pub struct InitializeLiquidityLockbox<'info> {
-  #[account(mut)]
-  pub signer: Signer<'info>,
+  /// Withdraws liquidity for supplied bridged tokens proportionally from every active position.
+  /// Each position gives up its share of the specified amount against the total liquidity.
+  ///
+  /// ### Parameters
+  /// - `amount` - Amount of bridged tokens corresponding to the total liquidity part to withdraw pro-rata.
+  /// - `token_mins_a` - The minimum amounts of token A the signer is willing to withdraw from each supplied position.
+  /// - `token_mins_b` - The minimum amounts of token B the signer is willing to withdraw from each supplied position.
+  ///
+  /// ### Remaining accounts
+  /// For each active lockbox position, in this order: `pda_lockbox_position`, `position`, `pda_position_account`,
+  /// `position_mint` and `depositor`.
+  pub fn withdraw_pro_rata<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, WithdrawLiquidityProRata<'info>>,
+    amount: u64,
+    token_mins_a: Vec<u64>,
+    token_mins_b: Vec<u64>
+  ) -> Result<()> {
+    // Check if there is any liquidity left in the Lockbox
+    let total_liquidity = ctx.accounts.lockbox.total_liquidity;
+    if total_liquidity == 0 {
+      return Err(ErrorCode::TotalLiquidityZero.into());
+    }
 
-  #[account(init,
-    seeds = [
-      b"liquidity_lockbox".as_ref()
-    ],
-    bump,
-    payer = signer,
-    space = LiquidityLockbox::LEN)]
-  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+    // Check the token amount
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
 
-  #[account(constraint = bridged_token_mint.mint_authority.unwrap() == lockbox.key())]
-  pub bridged_token_mint: Box<Account<'info, Mint>>,
+    // Check the requested amount to be smaller or equal than the total liquidity
+    if amount > total_liquidity {
+      return Err(ErrorCode::AmountExceedsTotalLiquidity.into());
+    }
 
-  #[account(constraint = signer.key == &fee_collector_token_owner_account_a.owner)]
-  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
-  #[account(constraint = signer.key == &fee_collector_token_owner_account_b.owner)]
-  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
 
-  #[account(address = token::ID)]
-  pub token_program: Program<'info, Token>,
-  pub system_program: Program<'info, System>,
-  pub rent: Sysvar<'info, Rent>
-}
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
 
-#[derive(Accounts)]
-#[instruction(id: u32)]
-pub struct DepositPositionForLiquidity<'info> {
-  #[account(mut)]
-  pub signer: Signer<'info>,
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
 
-  pub position: Box<Account<'info, Position>>,
-  #[account(mut,
-    constraint = signer.key == &position_token_account.owner,
-    constraint = position_token_account.mint == position.position_mint,
-    constraint = position_token_account.amount == 1
-  )]
-  pub position_token_account: Box<Account<'info, TokenAccount>>,
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
 
-  #[account(address = position_token_account.mint,
-    constraint = position_mint.supply == 1
-  )]
-  pub position_mint: Account<'info, Mint>,
+    // Check tick arrays owner
+    if ctx.accounts.tick_array_lower.to_account_info().owner != &ORCA ||
+      ctx.accounts.tick_array_upper.to_account_info().owner != &ORCA {
+      return Err(ErrorCode::WrongOwner.into());
+    }
 
-  #[account(init,
-    associated_token::authority = lockbox,
-    associated_token::mint = position_mint,
-    payer = signer)]
-  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
 
-  #[account(init,
-    seeds = [
-      b"lockbox_position".as_ref(),
-      id.to_be_bytes().as_ref()
-    ],
-    bump,
-    space = LockboxPosition::LEN,
-    payer = signer)]
-  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+    // Check that at least one position is supplied along with its minimum token amounts
+    let remaining_accounts = ctx.remaining_accounts;
+    if remaining_accounts.is_empty() || remaining_accounts.len() % POSITION_ACCOUNTS_LEN != 0 {
+      return Err(ErrorCode::WrongNumberOfPositions.into());
+    }
+    let num_positions = remaining_accounts.len() / POSITION_ACCOUNTS_LEN;
+    if token_mins_a.len() != num_positions || token_mins_b.len() != num_positions {
+      return Err(ErrorCode::WrongNumberOfPositions.into());
+    }
 
-  #[account(mut, address = lockbox.bridged_token_mint)]
-  pub bridged_token_mint: Box<Account<'info, Mint>>,
-  #[account(mut,
-    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
-    constraint = bridged_token_account.mint == bridged_token_mint.key(),
-    constraint = signer.key == &bridged_token_account.owner,
-  )]
-  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+    // Load and validate all the supplied positions
+    let mut pda_lockbox_positions: Vec<Account<'info, LockboxPosition>> = Vec::with_capacity(num_positions);
+    let mut positions: Vec<Account<'info, Position>> = Vec::with_capacity(num_positions);
+    let mut ids: Vec<u32> = Vec::with_capacity(num_positions);
+    for accounts in remaining_accounts.chunks(POSITION_ACCOUNTS_LEN) {
+      let pda_lockbox_position = Account::<LockboxPosition>::try_from(&accounts[0])?;
+      let position = Account::<Position>::try_from(&accounts[1])?;
+      let pda_position_account = Account::<TokenAccount>::try_from(&accounts[2])?;
+      let position_mint = &accounts[3];
+      let depositor = &accounts[4];
+
+      // Check the lockbox position PDA address correctness
+      let id = u32::from_be_bytes(pda_lockbox_position.id);
+      let lockbox_position = Pubkey::find_program_address(&[b"lockbox_position", id.to_be_bytes().as_ref()], &ID);
+      if lockbox_position.0 != pda_lockbox_position.key() {
+        return Err(ErrorCode::WrongPDAPositionAccount.into());
+      }
+
+      // Check the lockbox position state layout version
+      pda_lockbox_position.check_version()?;
+
+      // Check that each position is supplied only once
+      if ids.contains(&id) {
+        return Err(ErrorCode::WrongPositionId.into());
+      }
+
+      // Check the position and its PDA ATA against the recorded ones
+      if pda_lockbox_position.position_account != position.key() || position.whirlpool != ctx.accounts.whirlpool.key() {
+        return Err(ErrorCode::WrongPositionPDA.into());
+      }
+      if pda_lockbox_position.position_pda_ata != pda_position_account.key() ||
+        pda_position_account.owner != ctx.accounts.lockbox.key() ||
+        pda_position_account.mint != position.position_mint ||
+        pda_position_account.amount != 1 {
+        return Err(ErrorCode::WrongPositionAccount.into());
+      }
+      if position_mint.key() != position.position_mint {
+        return Err(ErrorCode::WrongPositionAccount.into());
+      }
+      if depositor.key() != pda_lockbox_position.depositor {
+        return Err(ErrorCode::WrongDepositor.into());
+      }
+
+      ids.push(id);
+      pda_lockbox_positions.push(pda_lockbox_position);
+      positions.push(position);
+    }
 
-  #[account(mut)]
-  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
-  #[account(address = token::ID)]
-  pub token_program: Program<'info, Token>,
+    // Check that every active position is supplied, as distinct positions add up to the total liquidity only then
+    let supplied_liquidity: u128 = pda_lockbox_positions.iter()
+      .map(|pda_lockbox_position| pda_lockbox_position.position_liquidity as u128)
+      .sum();
+    if supplied_liquidity != total_liquidity as u128 {
+      return Err(ErrorCode::WrongNumberOfPositions.into());
+    }
 
-  pub system_program: Program<'info, System>,
-  pub rent: Sysvar<'info, Rent>,
-  pub associated_token_program: Program<'info, AssociatedToken>
-}
+    // Calculate the liquidity share of the amount to withdraw from each position against the total liquidity
+    let position_amounts: Vec<u64> = pda_lockbox_positions.iter()
+      .map(|pda_lockbox_position| {
+        (amount as u128 * pda_lockbox_position.position_liquidity as u128 / total_liquidity as u128) as u64
+      })
+      .collect();
 
-#[derive(Accounts)]
-pub struct WithdrawLiquidityForTokens<'info> {
-  #[account(mut)]
-  pub whirlpool: Box<Account<'info, Whirlpool>>,
+    // The withdrawn amount is the sum of the supplied position shares
+    let withdraw_amount: u64 = position_amounts.iter().sum();
+    if withdraw_amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
 
-  #[account(mut)]
-  pub signer: Signer<'info>,
+    // Burn the withdrawn amount of bridged tokens
+    invoke_signed(
+      &burn_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.bridged_token_account.to_account_info().key,
+        ctx.accounts.bridged_token_mint.to_account_info().key,
+        ctx.accounts.signer.to_account_info().key,
+        &[],
+        withdraw_amount,
+        BRIDGED_TOKEN_DECIMALS,
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.bridged_token_account.to_account_info(),
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+      ],
+      &[]
+    )?;
 
-  #[account(mut, address = lockbox.bridged_token_mint)]
-  pub bridged_token_mint: Box<Account<'info, Mint>>,
-  #[account(mut,
-    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
-    constraint = bridged_token_account.mint == bridged_token_mint.key(),
-    constraint = signer.key == &bridged_token_account.owner,
-  )]
-  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
 
-  #[account(mut, has_one = whirlpool, has_one = position_mint)]
-  pub position: Box<Account<'info, Position>>,
-  #[account(mut,
-    constraint = pda_position_account.mint == position.position_mint,
-    constraint = pda_position_account.amount == 1,
-    constraint = lockbox.key() == pda_position_account.owner
-  )]
-  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+    for (i, accounts) in remaining_accounts.chunks(POSITION_ACCOUNTS_LEN).enumerate() {
+      let position_amount = position_amounts[i];
+      // Positions with a zero pro-rata amount are left intact
+      if position_amount == 0 {
+        continue;
+      }
+
+      let position = &positions[i];
+      let pda_position_account = &accounts[2];
+      let position_mint = &accounts[3];
+      let depositor = &accounts[4];
+
+      // Update fees for the position
+      let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_update_fees = UpdateFeesAndRewards {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: position.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
+      };
 
-  #[account(mut,
-    address = position.position_mint,
-    constraint = position_mint.supply == 1
-  )]
-  pub position_mint: Box<Account<'info, Mint>>,
+      let cpi_ctx_update_fees = CpiContext::new_with_signer(
+        cpi_program_update_fees,
+        cpi_accounts_update_fees,
+        signer_seeds
+      );
+      whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
 
-  #[account(mut,
-    constraint = pda_lockbox_position.position_account == position.key(),
-    constraint = pda_lockbox_position.position_pda_ata == pda_position_account.key(),
-    constraint = pda_lockbox_position.to_account_info().owner == lockbox.to_account_info().owner
-  )]
-  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+      // Collect fees from the position
+      let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_collect_fees = CollectFees {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position: position.to_account_info(),
+        position_token_account: pda_position_account.clone(),
+        token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
 
-  #[account(mut,
-    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
-    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
-    constraint = signer.key == &token_owner_account_a.owner
-  )]
-  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
-  #[account(mut,
-    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
-    constraint = signer.key == &token_owner_account_b.owner
-  )]
-  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+      let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+        cpi_program_collect_fees,
+        cpi_accounts_collect_fees,
+        signer_seeds
+      );
+      whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
 
-  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
-  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
-  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
-  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+      // CPI to decrease liquidity
+      let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_modify_liquidity = ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: pda_position_account.clone(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
 
-  #[account(mut,
-    constraint = token_vault_a.key() == whirlpool.token_vault_a,
-    constraint = token_vault_a.key() != token_vault_b.key()
-  )]
-  pub token_vault_a: Box<Account<'info, TokenAccount>>,
-  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
-  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+      let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+        cpi_program_modify_liquidity,
+        cpi_accounts_modify_liquidity,
+        signer_seeds
+      );
+      whirlpool::cpi::decrease_liquidity(
+        cpi_ctx_modify_liquidity,
+        position_amount as u128,
+        token_mins_a[i],
+        token_mins_b[i]
+      )?;
+
+      // Get the post-withdraw position remainder
+      let pda_lockbox_position = &mut pda_lockbox_positions[i];
+      let id = u32::from_be_bytes(pda_lockbox_position.id);
+      let remainder: u64 = pda_lockbox_position.position_liquidity - position_amount;
+
+      if remainder == 0 {
+        // Close the position
+        let cpi_program_close_position = ctx.accounts.whirlpool_program.to_account_info();
+        let cpi_accounts_close_position = ClosePosition {
+          position_authority: ctx.accounts.lockbox.to_account_info(),
+          receiver: depositor.clone(),
+          position: position.to_account_info(),
+          position_mint: position_mint.clone(),
+          position_token_account: pda_position_account.clone(),
+          token_program: ctx.accounts.token_program.to_account_info()
+        };
+
+        let cpi_ctx_close_position = CpiContext::new_with_signer(
+          cpi_program_close_position,
+          cpi_accounts_close_position,
+          signer_seeds
+        );
+        whirlpool::cpi::close_position(cpi_ctx_close_position)?;
+
+        // Close the pda_lockbox_position account and send all lamports to the original depositor
+        pda_lockbox_position.close(depositor.clone())?;
+      } else {
+        // Update position liquidity and persist the account data
+        pda_lockbox_position.position_liquidity = remainder;
+        pda_lockbox_position.exit(&ID)?;
+      }
+
+      // Update the position liquidity in the index of active positions, or remove the position if it is closed
+      ctx.accounts.position_index.load_mut()?.update(id, remainder)?;
+    }
 
-  #[account(mut, has_one = whirlpool, constraint = tick_array_lower.key() != tick_array_upper.key())]
-  pub tick_array_lower: AccountLoader<'info, TickArray>,
-  #[account(mut, has_one = whirlpool)]
-  pub tick_array_upper: AccountLoader<'info, TickArray>,
+    // Decrease the total liquidity amount
+    ctx.accounts.lockbox.total_liquidity -= withdraw_amount;
 
-  #[account(mut)]
-  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
-  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(withdraw_amount, Clock::get()?.slot)?;
 
-  #[account(address = token::ID)]
-  pub token_program: Program<'info, Token>
-}
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), withdraw_amount)?;
 
+    emit!(WithdrawProRataEvent {
+      signer: ctx.accounts.signer.key(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.key(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.key(),
+      amount: withdraw_amount,
+      num_positions: num_positions as u32,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
 
-#[error_code]
-pub enum ErrorCode {
-  #[msg("Liquidity value overflow")]
-  LiquidityOverflow,
-  #[msg("Wrong whirlpool address")]
-  WrongWhirlpool,
-  #[msg("Wrong position PDA header")]
-  WrongPositionHeader,
-  #[msg("Wrong position ID")]
-  WrongPositionId,
-  #[msg("Liquidity is zero")]
-  LiquidityZero,
-  #[msg("Total liquidity is zero")]
-  TotalLiquidityZero,
-  #[msg("Requested amount exceeds a position liquidity")]
-  AmountExceedsPositionLiquidity,
-  #[msg("Requested amount exceeds total liquidity")]
-  AmountExceedsTotalLiquidity,
-  #[msg("Tick out of range")]
-  OutOfRange,
-  #[msg("Wrong account owner")]
-  WrongOwner,
-  #[msg("Provided wrong position PDA")]
-  WrongPositionPDA,
-  #[msg("Provided wrong lockbox PDA")]
-  WrongLockboxPDA,
-  #[msg("Provided wrong position ATA")]
-  WrongPositionAccount,
+    Ok(())
+  }
+
+  /// Consolidates liquidity of several lockbox positions into a primary lockbox position.
+  ///
+  /// ### Parameters
+  /// - `token_max_a` - The maximum amount of token A the signer is willing to add to cover liquidity rounding.
+  /// - `token_max_b` - The maximum amount of token B the signer is willing to add to cover liquidity rounding.
+  ///
+  /// ### Remaining accounts
+  /// For each lockbox position to consolidate, in this order: `pda_lockbox_position`, `position`,
+  /// `pda_position_account`, `position_mint` and `depositor`.
+  pub fn consolidate<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ConsolidatePositions<'info>>,
+    token_max_a: u64,
+    token_max_b: u64
+  ) -> Result<()> {
+    // Check that at least one position to consolidate is supplied
+    let remaining_accounts = ctx.remaining_accounts;
+    if remaining_accounts.is_empty() || remaining_accounts.len() % POSITION_ACCOUNTS_LEN != 0 {
+      return Err(ErrorCode::WrongNumberOfPositions.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check the primary lockbox position PDA address correctness
+    let primary_id = u32::from_be_bytes(ctx.accounts.pda_lockbox_position.id);
+    let lockbox_position = Pubkey::find_program_address(&[b"lockbox_position", primary_id.to_be_bytes().as_ref()], &ID);
+    if lockbox_position.0 != ctx.accounts.pda_lockbox_position.key() {
+      return Err(ErrorCode::WrongPDAPositionAccount.into());
+    }
+
+    // Check the primary lockbox position state layout version
+    ctx.accounts.pda_lockbox_position.check_version()?;
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check tick arrays owner
+    if ctx.accounts.tick_array_lower.to_account_info().owner != &ORCA ||
+      ctx.accounts.tick_array_upper.to_account_info().owner != &ORCA {
+      return Err(ErrorCode::WrongOwner.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Record lockbox token balances before the consolidation
+    let lockbox_amount_a_before = ctx.accounts.lockbox_token_account_a.amount;
+    let lockbox_amount_b_before = ctx.accounts.lockbox_token_account_b.amount;
+
+    // Transfer the rounding cover of A tokens to the lockbox
+    if token_max_a > 0 {
+      token::transfer(
+        CpiContext::new(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.token_owner_account_a.to_account_info(),
+            to: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+          },
+        ),
+        token_max_a,
+      )?;
+    }
+
+    // Transfer the rounding cover of B tokens to the lockbox
+    if token_max_b > 0 {
+      token::transfer(
+        CpiContext::new(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.token_owner_account_b.to_account_info(),
+            to: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+          },
+        ),
+        token_max_b,
+      )?;
+    }
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    let mut consolidated_liquidity: u64 = 0;
+    let mut ids: Vec<u32> = Vec::with_capacity(remaining_accounts.len() / POSITION_ACCOUNTS_LEN);
+    for accounts in remaining_accounts.chunks(POSITION_ACCOUNTS_LEN) {
+      let pda_lockbox_position = Account::<LockboxPosition>::try_from(&accounts[0])?;
+      let position = Account::<Position>::try_from(&accounts[1])?;
+      let pda_position_account = Account::<TokenAccount>::try_from(&accounts[2])?;
+      let position_mint = &accounts[3];
+      let depositor = &accounts[4];
+
+      // Check the lockbox position PDA address correctness
+      let id = u32::from_be_bytes(pda_lockbox_position.id);
+      let lockbox_position = Pubkey::find_program_address(&[b"lockbox_position", id.to_be_bytes().as_ref()], &ID);
+      if lockbox_position.0 != pda_lockbox_position.key() {
+        return Err(ErrorCode::WrongPDAPositionAccount.into());
+      }
+
+      // Check the lockbox position state layout version
+      pda_lockbox_position.check_version()?;
+
+      // Check that each position is supplied only once and is not the primary one
+      if id == primary_id || ids.contains(&id) {
+        return Err(ErrorCode::WrongPositionId.into());
+      }
+
+      // Check the position and its PDA ATA against the recorded ones
+      if pda_lockbox_position.position_account != position.key() || position.whirlpool != ctx.accounts.whirlpool.key() {
+        return Err(ErrorCode::WrongPositionPDA.into());
+      }
+      if pda_lockbox_position.position_pda_ata != pda_position_account.key() ||
+        pda_position_account.owner != ctx.accounts.lockbox.key() ||
+        pda_position_account.mint != position.position_mint ||
+        pda_position_account.amount != 1 {
+        return Err(ErrorCode::WrongPositionAccount.into());
+      }
+      if position_mint.key() != position.position_mint {
+        return Err(ErrorCode::WrongPositionAccount.into());
+      }
+      if depositor.key() != pda_lockbox_position.depositor {
+        return Err(ErrorCode::WrongDepositor.into());
+      }
+
+      let position_liquidity = pda_lockbox_position.position_liquidity;
+
+      // Update fees for the position
+      let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_update_fees = UpdateFeesAndRewards {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: position.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
+      };
+
+      let cpi_ctx_update_fees = CpiContext::new_with_signer(
+        cpi_program_update_fees,
+        cpi_accounts_update_fees,
+        signer_seeds
+      );
+      whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+
+      // Collect fees from the position
+      let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_collect_fees = CollectFees {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position: position.to_account_info(),
+        position_token_account: pda_position_account.to_account_info(),
+        token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
+
+      let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+        cpi_program_collect_fees,
+        cpi_accounts_collect_fees,
+        signer_seeds
+      );
+      whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+
+      // CPI to decrease the whole position liquidity into the lockbox token accounts
+      let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_modify_liquidity = ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
+
+      let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+        cpi_program_modify_liquidity,
+        cpi_accounts_modify_liquidity,
+        signer_seeds
+      );
+      whirlpool::cpi::decrease_liquidity(cpi_ctx_modify_liquidity, position_liquidity as u128, 0, 0)?;
+
+      // Close the position
+      let cpi_program_close_position = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_close_position = ClosePosition {
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        receiver: depositor.clone(),
+        position: position.to_account_info(),
+        position_mint: position_mint.clone(),
+        position_token_account: pda_position_account.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
+
+      let cpi_ctx_close_position = CpiContext::new_with_signer(
+        cpi_program_close_position,
+        cpi_accounts_close_position,
+        signer_seeds
+      );
+      whirlpool::cpi::close_position(cpi_ctx_close_position)?;
+
+      // Close the pda_lockbox_position account and send all lamports to the original depositor
+      pda_lockbox_position.close(depositor.clone())?;
+
+      // Remove the position from the index of active positions
+      ctx.accounts.position_index.load_mut()?.update(id, 0)?;
+
+      consolidated_liquidity = match consolidated_liquidity.checked_add(position_liquidity) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityOverflow.into()),
+      };
+      ids.push(id);
+    }
+
+    // Get the tokens available for increasing the primary position liquidity
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+    let token_available_a = ctx.accounts.lockbox_token_account_a.amount - lockbox_amount_a_before;
+    let token_available_b = ctx.accounts.lockbox_token_account_b.amount - lockbox_amount_b_before;
+
+    // CPI to increase the primary position liquidity by the consolidated amount
+    let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_modify_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+      cpi_program_modify_liquidity,
+      cpi_accounts_modify_liquidity,
+      signer_seeds
+    );
+    whirlpool::cpi::increase_liquidity(cpi_ctx_modify_liquidity, consolidated_liquidity as u128,
+      token_available_a, token_available_b)?;
+
+    // Return unused rounding cover tokens to the signer
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+    let refund_a = ctx.accounts.lockbox_token_account_a.amount - lockbox_amount_a_before;
+    let refund_b = ctx.accounts.lockbox_token_account_b.amount - lockbox_amount_b_before;
+
+    if refund_a > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            to: ctx.accounts.token_owner_account_a.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        refund_a,
+      )?;
+    }
+
+    if refund_b > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            to: ctx.accounts.token_owner_account_b.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        refund_b,
+      )?;
+    }
+
+    // Update the primary position liquidity, the total liquidity stays unchanged
+    let position_liquidity = match ctx.accounts.pda_lockbox_position
+      .position_liquidity
+      .checked_add(consolidated_liquidity) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityOverflow.into()),
+      };
+    ctx.accounts.pda_lockbox_position.position_liquidity = position_liquidity;
+    ctx.accounts.position_index.load_mut()?.update(primary_id, position_liquidity)?;
+
+    emit!(ConsolidateEvent {
+      signer: ctx.accounts.signer.key(),
+      pda_lockbox_position: ctx.accounts.pda_lockbox_position.key(),
+      position: ctx.accounts.position.key(),
+      num_positions: ids.len() as u32,
+      consolidated_liquidity,
+      position_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Redeems a whole lockbox position NFT for supplied bridged tokens.
+  ///
+  /// ### Parameters
+  /// - `id` - Lockbox position ID. Must be smaller than the total number of lockbox positions.
+  pub fn redeem_position(ctx: Context<RedeemPosition>, id: u32) -> Result<()> {
+    // Get the lockbox position PDA ATA
+    if id >= ctx.accounts.lockbox.num_positions {
+      return Err(ErrorCode::WrongPositionId.into());
+    }
+    let lockbox_position = Pubkey::find_program_address(&[b"lockbox_position", id.to_be_bytes().as_ref()], &ID);
+
+    // Check that the calculated address matches the provided PDA lockbox position
+    if lockbox_position.0 != ctx.accounts.pda_lockbox_position.key() {
+      return Err(ErrorCode::WrongPDAPositionAccount.into());
+    }
+
+    // Check the lockbox position state layout version
+    ctx.accounts.pda_lockbox_position.check_version()?;
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the first token mint is the whirlpool token A
+    if ctx.accounts.token_vault_a.mint != ctx.accounts.whirlpool.token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    if ctx.accounts.token_vault_b.mint != ctx.accounts.whirlpool.token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check tick arrays owner
+    if ctx.accounts.tick_array_lower.to_account_info().owner != &ORCA ||
+      ctx.accounts.tick_array_upper.to_account_info().owner != &ORCA {
+      return Err(ErrorCode::WrongOwner.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Get the position liquidity
+    let position_liquidity = ctx.accounts.pda_lockbox_position.position_liquidity;
+
+    // Check that the liquidity is not zero - must never happen for an active position
+    if position_liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Burn bridged tokens in the amount of the whole position liquidity
+    invoke_signed(
+      &burn_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.bridged_token_account.to_account_info().key,
+        ctx.accounts.bridged_token_mint.to_account_info().key,
+        ctx.accounts.signer.to_account_info().key,
+        &[],
+        position_liquidity,
+        BRIDGED_TOKEN_DECIMALS,
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.bridged_token_account.to_account_info(),
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+      ],
+      &[]
+    )?;
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    // Update fees for the position
+    let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_update_fees = UpdateFeesAndRewards {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
+    };
+
+    let cpi_ctx_update_fees = CpiContext::new_with_signer(
+      cpi_program_update_fees,
+      cpi_accounts_update_fees,
+      signer_seeds
+    );
+    whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+
+    // Collect fees accrued while the position was locked
+    let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_collect_fees = CollectFees {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+      cpi_program_collect_fees,
+      cpi_accounts_collect_fees,
+      signer_seeds
+    );
+    whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+
+    // Transfer the position NFT from the program PDA ATA to the signer
+    token::transfer(
+      CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+          from: ctx.accounts.pda_position_account.to_account_info(),
+          to: ctx.accounts.position_token_account.to_account_info(),
+          authority: ctx.accounts.lockbox.to_account_info(),
+        },
+        signer_seeds
+      ),
+      1,
+    )?;
+
+    // Close the program PDA ATA and send its rent to the original depositor
+    invoke_signed(
+      &close_account(
+        ctx.accounts.token_program.key,
+        ctx.accounts.pda_position_account.to_account_info().key,
+        ctx.accounts.depositor.to_account_info().key,
+        ctx.accounts.lockbox.to_account_info().key,
+        &[],
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.pda_position_account.to_account_info(),
+        ctx.accounts.depositor.to_account_info(),
+        ctx.accounts.lockbox.to_account_info(),
+      ],
+      signer_seeds,
+    )?;
+
+    // Close the pda_lockbox_position account and send all lamports to the original depositor
+    ctx.accounts.pda_lockbox_position.close(ctx.accounts.depositor.to_account_info())?;
+
+    // Decrease the total liquidity amount
+    ctx.accounts.lockbox.total_liquidity -= position_liquidity;
+
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(position_liquidity, Clock::get()?.slot)?;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), position_liquidity)?;
+
+    // Remove the position from the index of active positions
+    ctx.accounts.position_index.load_mut()?.update(id, 0)?;
+
+    emit!(RedeemPositionEvent {
+      signer: ctx.accounts.signer.key(),
+      pda_lockbox_position: ctx.accounts.pda_lockbox_position.key(),
+      position: ctx.accounts.position.key(),
+      position_token_account: ctx.accounts.position_token_account.key(),
+      position_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Migrates a whole lockbox position NFT to the lockbox v2 program.
+  /// Can only be called by the lockbox v2 program that adds the position liquidity to its own position and swaps
+  /// v1 bridged tokens for v2 bridged tokens.
+  ///
+  /// ### Parameters
+  /// - `id` - Lockbox position ID. Must be smaller than the total number of lockbox positions.
+  pub fn migrate_position(ctx: Context<MigratePosition>, id: u32) -> Result<()> {
+    // Check that the migrator is the lockbox v2 PDA
+    let lockbox2_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &LOCKBOX2);
+    if lockbox2_pda.0 != ctx.accounts.migrator.key() {
+      return Err(ErrorCode::WrongMigrator.into());
+    }
+
+    // Get the lockbox position PDA ATA
+    if id >= ctx.accounts.lockbox.num_positions {
+      return Err(ErrorCode::WrongPositionId.into());
+    }
+    let lockbox_position = Pubkey::find_program_address(&[b"lockbox_position", id.to_be_bytes().as_ref()], &ID);
+
+    // Check that the calculated address matches the provided PDA lockbox position
+    if lockbox_position.0 != ctx.accounts.pda_lockbox_position.key() {
+      return Err(ErrorCode::WrongPDAPositionAccount.into());
+    }
+
+    // Check the lockbox position state layout version
+    ctx.accounts.pda_lockbox_position.check_version()?;
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Get the position liquidity
+    let position_liquidity = ctx.accounts.pda_lockbox_position.position_liquidity;
+
+    // Check that the liquidity is not zero - must never happen for an active position
+    if position_liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    // Transfer the position NFT from the program PDA ATA to the migrator
+    token::transfer(
+      CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+          from: ctx.accounts.pda_position_account.to_account_info(),
+          to: ctx.accounts.migrator_position_account.to_account_info(),
+          authority: ctx.accounts.lockbox.to_account_info(),
+        },
+        signer_seeds
+      ),
+      1,
+    )?;
+
+    // Close the program PDA ATA and send its rent to the original depositor
+    invoke_signed(
+      &close_account(
+        ctx.accounts.token_program.key,
+        ctx.accounts.pda_position_account.to_account_info().key,
+        ctx.accounts.depositor.to_account_info().key,
+        ctx.accounts.lockbox.to_account_info().key,
+        &[],
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.pda_position_account.to_account_info(),
+        ctx.accounts.depositor.to_account_info(),
+        ctx.accounts.lockbox.to_account_info(),
+      ],
+      signer_seeds,
+    )?;
+
+    // Close the pda_lockbox_position account and send all lamports to the original depositor
+    ctx.accounts.pda_lockbox_position.close(ctx.accounts.depositor.to_account_info())?;
+
+    // Decrease the total liquidity amount
+    ctx.accounts.lockbox.total_liquidity -= position_liquidity;
+
+    // Remove the position from the index of active positions
+    ctx.accounts.position_index.load_mut()?.update(id, 0)?;
+
+    emit!(MigratePositionEvent {
+      migrator: ctx.accounts.migrator.key(),
+      pda_lockbox_position: ctx.accounts.pda_lockbox_position.key(),
+      position: ctx.accounts.position.key(),
+      position_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Migrates the lockbox and lockbox position accounts to the current state layout version.
+  /// Accounts are reallocated to the current size with the rent difference paid by the admin, and the supplied
+  /// whirlpool of the lockbox positions is recorded in the lockbox.
+  ///
+  /// ### Parameters
+  /// - `depositors` - Depositors of the supplied lockbox positions taken from their deposit transactions, in the same
+  ///   order. Legacy positions did not record the depositor that receives the position rent when it is closed.
+  ///
+  /// ### Remaining accounts
+  /// Lockbox position accounts to migrate. Active positions are added to the position index, which is created on the
+  /// first migration, such that all the active positions must be supplied over one or several migrations.
+  pub fn migrate_state<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, MigrateState<'info>>,
+    depositors: Vec<Pubkey>
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check that a depositor is supplied for each lockbox position
+    if depositors.len() != ctx.remaining_accounts.len() {
+      return Err(ErrorCode::WrongNumberOfPositions.into());
+    }
+
+    let signer = ctx.accounts.signer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+    let mut num_migrated: u32 = 0;
+
+    // Migrate the lockbox account according to its stored layout version
+    let lockbox = ctx.accounts.lockbox.to_account_info();
+    let lockbox_version = get_state_version(
+      &lockbox,
+      LiquidityLockbox::discriminator(),
+      LiquidityLockbox::LEN_V0,
+      LiquidityLockbox::VERSION_OFFSET
+    )?;
+    match lockbox_version {
+      0 => {
+        let legacy_lockbox = {
+          let data = lockbox.try_borrow_data()?;
+          LiquidityLockboxV0::deserialize(&mut &data[8..])?
+        };
+
+        realloc_account(&lockbox, &signer, &system_program, LiquidityLockbox::LEN)?;
+        let mut lockbox_state = LiquidityLockbox::from(legacy_lockbox);
+        lockbox_state.whirlpool = ctx.accounts.whirlpool.key();
+
+        let mut data = lockbox.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        lockbox_state.try_serialize(&mut writer)?;
+        num_migrated += 1;
+      },
+      LiquidityLockbox::VERSION => {},
+      _ => return Err(ErrorCode::UnsupportedVersion.into())
+    }
+
+    // Get the position index, initializing it if it was created by this instruction
+    let position_index_account = ctx.accounts.position_index.to_account_info();
+    let is_new_position_index = position_index_account.try_borrow_data()?[..8] == [0u8; 8];
+    let mut position_index = if is_new_position_index {
+      let mut position_index = ctx.accounts.position_index.load_init()?;
+      position_index.initialize(*ctx.bumps.get("position_index").unwrap())?;
+      position_index
+    } else {
+      ctx.accounts.position_index.load_mut()?
+    };
+
+    // Migrate the supplied lockbox position accounts according to their stored layout versions
+    for (account, depositor) in ctx.remaining_accounts.iter().zip(depositors) {
+      let position_version = get_state_version(
+        account,
+        LockboxPosition::discriminator(),
+        LockboxPosition::LEN_V0,
+        LockboxPosition::VERSION_OFFSET
+      )?;
+
+      // Get the lockbox position id that is stored right after the discriminator in all the layouts
+      let id: [u8; 4] = account.try_borrow_data()?[8..12].try_into().unwrap();
+
+      // Check the lockbox position PDA address correctness
+      let lockbox_position = Pubkey::find_program_address(&[b"lockbox_position", id.as_ref()], &ID);
+      if lockbox_position.0 != account.key() {
+        return Err(ErrorCode::WrongPDAPositionAccount.into());
+      }
+
+      let position_state = match position_version {
+        0 => {
+          let legacy_position = {
+            let data = account.try_borrow_data()?;
+            LockboxPositionV0::deserialize(&mut &data[8..])?
+          };
+          realloc_account(account, &signer, &system_program, LockboxPosition::LEN)?;
+          LockboxPosition::from_v0(legacy_position, depositor)
+        },
+        LockboxPosition::VERSION => {
+          let data = account.try_borrow_data()?;
+          LockboxPosition::try_deserialize(&mut &data[..])?
+        },
+        _ => return Err(ErrorCode::UnsupportedVersion.into())
+      };
+
+      if position_version != LockboxPosition::VERSION {
+        let mut data = account.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        position_state.try_serialize(&mut writer)?;
+        num_migrated += 1;
+      }
+
+      // Backfill the position index with the active positions deposited before it was created
+      let id = u32::from_be_bytes(id);
+      if position_state.position_liquidity > 0 && position_index.find(id).is_none() {
+        position_index.add(id, position_state.position_liquidity)?;
+      }
+    }
+
+    emit!(MigrateStateEvent {
+      admin: signer.key(),
+      num_accounts: num_migrated,
+      version: LiquidityLockbox::VERSION
+    });
+
+    Ok(())
+  }
+
+  /// Decommissions the Lockbox by closing its accounts and reclaiming all the rent to the admin.
+  ///
+  /// ### Remaining accounts
+  /// Lockbox-owned token accounts to close. All of them must have a zero balance.
+  pub fn decommission<'c: 'info, 'info>(ctx: Context<'_, '_, 'c, 'info, DecommissionLockbox<'info>>) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that there is no liquidity left in the Lockbox
+    if ctx.accounts.lockbox.total_liquidity != 0 || ctx.accounts.position_index.load()?.num_active != 0 {
+      return Err(ErrorCode::LiquidityNotZero.into());
+    }
+
+    // Check that there are no bridged tokens left
+    if ctx.accounts.bridged_token_mint.supply != 0 {
+      return Err(ErrorCode::BridgedSupplyNotZero.into());
+    }
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    // Close lockbox-owned token accounts
+    for token_account in ctx.remaining_accounts {
+      let lockbox_token_account = Account::<TokenAccount>::try_from(token_account)?;
+      if lockbox_token_account.owner != ctx.accounts.lockbox.key() {
+        return Err(ErrorCode::WrongOwner.into());
+      }
+
+      // Check that no tokens are left in the token account, as closing it would strand them
+      if lockbox_token_account.amount != 0 {
+        return Err(ErrorCode::TokenBalanceNotZero.into());
+      }
+
+      token::close_account(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          CloseAccount {
+            account: token_account.clone(),
+            destination: ctx.accounts.signer.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        )
+      )?;
+    }
+
+    emit!(DecommissionEvent {
+      admin: ctx.accounts.signer.key(),
+      lockbox: ctx.accounts.lockbox.key(),
+      num_token_accounts: ctx.remaining_accounts.len() as u32
+    });
+
+    // The lockbox and position index accounts are closed on exit
+    Ok(())
+  }
+
+  /// Sets the deposit limits of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `max_total_liquidity` - Maximum total liquidity, zero to disable the cap.
+  /// - `max_deposit_per_tx` - Maximum liquidity per deposit, zero to disable the limit.
+  /// - `min_deposit` - Minimum liquidity per deposit.
+  pub fn set_deposit_limits(
+    ctx: Context<SetDepositLimits>,
+    max_total_liquidity: u64,
+    max_deposit_per_tx: u64,
+    min_deposit: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.set_deposit_limits(max_total_liquidity, max_deposit_per_tx, min_deposit)?;
+
+    emit!(DepositLimitsEvent {
+      admin: ctx.accounts.signer.key(),
+      max_total_liquidity,
+      max_deposit_per_tx,
+      min_deposit
+    });
+
+    Ok(())
+  }
+
+  /// Sets the maximum cumulative liquidity deposited per user of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `max_user_deposit` - Maximum cumulative liquidity deposited per user, zero to disable the cap.
+  pub fn set_user_deposit_cap(ctx: Context<SetDepositLimits>, max_user_deposit: u64) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.max_user_deposit = max_user_deposit;
+
+    emit!(UserDepositCapEvent {
+      admin: ctx.accounts.signer.key(),
+      max_user_deposit
+    });
+
+    Ok(())
+  }
+
+  /// Sets the depositor allowlist Merkle root of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `allowlist_root` - Allowlist Merkle root, zero to disable the allowlist mode.
+  pub fn set_allowlist_root(ctx: Context<SetDepositLimits>, allowlist_root: [u8; 32]) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.allowlist_root = allowlist_root;
+
+    emit!(AllowlistRootEvent {
+      admin: ctx.accounts.signer.key(),
+      allowlist_root
+    });
+
+    Ok(())
+  }
+
+  /// Sets the withdrawal rate limit of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `withdraw_window_slots` - Window length in slots, zero to disable the rate limit.
+  /// - `max_withdraw_per_window` - Maximum liquidity withdrawn per window.
+  pub fn set_withdraw_rate_limit(
+    ctx: Context<SetWithdrawRateLimit>,
+    withdraw_window_slots: u64,
+    max_withdraw_per_window: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.set_withdraw_rate_limit(withdraw_window_slots, max_withdraw_per_window)?;
+
+    emit!(WithdrawRateLimitEvent {
+      admin: ctx.accounts.signer.key(),
+      withdraw_window_slots,
+      max_withdraw_per_window
+    });
+
+    Ok(())
+  }
+}
+
+// Gets the state layout version of the program account, zero for the legacy layout without the version field
+fn get_state_version(
+  account: &AccountInfo,
+  discriminator: [u8; 8],
+  len_v0: usize,
+  version_offset: usize
+) -> Result<u8> {
+  if account.owner != &ID {
+    return Err(ErrorCode::WrongOwner.into());
+  }
+
+  let data = account.try_borrow_data()?;
+  if data.len() <= version_offset || data[..8] != discriminator {
+    return Err(ErrorCode::UnsupportedVersion.into());
+  }
+
+  if data.len() == len_v0 {
+    Ok(0)
+  } else {
+    Ok(data[version_offset])
+  }
+}
+
+// Records the withdrawn liquidity in the user state if the user has one, as withdrawals never create it
+fn record_user_withdraw<'info>(user_state: &AccountInfo<'info>, liquidity: u64) -> Result<()> {
+  if user_state.owner != &ID || user_state.data_is_empty() {
+    return Ok(());
+  }
+
+  let mut user_state = Account::<UserState>::try_from(user_state)?;
+  user_state.record_withdraw(liquidity)?;
+  user_state.exit(&ID)?;
+
+  Ok(())
+}
+
+// Reallocates the program account to a new length, topping up its rent from the payer
+fn realloc_account<'info>(
+  account: &AccountInfo<'info>,
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+  new_len: usize
+) -> Result<()> {
+  let rent_exempt_lamports = Rent::get()?.minimum_balance(new_len);
+  let lamports = account.lamports();
+  if rent_exempt_lamports > lamports {
+    anchor_lang::system_program::transfer(
+      CpiContext::new(
+        system_program.clone(),
+        anchor_lang::system_program::Transfer {
+          from: payer.clone(),
+          to: account.clone(),
+        },
+      ),
+      rent_exempt_lamports - lamports,
+    )?;
+  }
+  account.realloc(new_len, false)?;
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeLiquidityLockbox<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(init,
+    seeds = [
+      b"liquidity_lockbox".as_ref()
+    ],
+    bump,
+    payer = signer,
+    space = LiquidityLockbox::LEN)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+
+  #[account(init,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump,
+    payer = signer,
+    space = PositionIndex::LEN)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+
+  #[account(constraint = signer.key == &fee_collector_token_owner_account_a.owner)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(constraint = signer.key == &fee_collector_token_owner_account_b.owner)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+  pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+#[instruction(id: u32)]
+pub struct DepositPositionForLiquidity<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    constraint = signer.key == &position_token_account.owner,
+    constraint = position_token_account.mint == position.position_mint,
+    constraint = position_token_account.amount == 1
+  )]
+  pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(address = position_token_account.mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Account<'info, Mint>,
+
+  #[account(init,
+    associated_token::authority = lockbox,
+    associated_token::mint = position_mint,
+    payer = signer)]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(init,
+    seeds = [
+      b"lockbox_position".as_ref(),
+      id.to_be_bytes().as_ref()
+    ],
+    bump,
+    space = LockboxPosition::LEN,
+    payer = signer)]
+  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(init_if_needed,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump,
+    space = UserState::LEN,
+    payer = signer)]
+  pub user_state: Box<Account<'info, UserState>>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+
+  pub system_program: Program<'info, System>,
+  pub rent: Sysvar<'info, Rent>,
+  pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidityForTokens<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    constraint = pda_position_account.mint == position.position_mint,
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    address = position.position_mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = pda_lockbox_position.position_account == position.key(),
+    constraint = pda_lockbox_position.position_pda_ata == pda_position_account.key(),
+    constraint = pda_lockbox_position.to_account_info().owner == lockbox.to_account_info().owner
+  )]
+  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+
+  /// CHECK: Original depositor receiving the closed position rent, checked against the recorded one
+  #[account(mut, address = pda_lockbox_position.depositor)]
+  pub depositor: UncheckedAccount<'info>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool, constraint = tick_array_lower.key() != tick_array_upper.key())]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool)]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidityProRata<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool, constraint = tick_array_lower.key() != tick_array_upper.key())]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool)]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct ConsolidatePositions<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, has_one = whirlpool)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(
+    constraint = pda_position_account.mint == position.position_mint,
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = pda_lockbox_position.position_account == position.key(),
+    constraint = pda_lockbox_position.position_pda_ata == pda_position_account.key(),
+    constraint = pda_lockbox_position.to_account_info().owner == lockbox.to_account_info().owner
+  )]
+  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = lockbox_token_account_a.mint == whirlpool.token_mint_a,
+    constraint = lockbox.key() == lockbox_token_account_a.owner
+  )]
+  pub lockbox_token_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = lockbox_token_account_b.mint == whirlpool.token_mint_b,
+    constraint = lockbox.key() == lockbox_token_account_b.owner
+  )]
+  pub lockbox_token_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool, constraint = tick_array_lower.key() != tick_array_upper.key())]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool)]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct RedeemPosition<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    constraint = pda_position_account.mint == position.position_mint,
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = position_token_account.mint == position.position_mint,
+    constraint = signer.key == &position_token_account.owner
+  )]
+  pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = pda_lockbox_position.position_account == position.key(),
+    constraint = pda_lockbox_position.position_pda_ata == pda_position_account.key(),
+    constraint = pda_lockbox_position.to_account_info().owner == lockbox.to_account_info().owner
+  )]
+  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+
+  /// CHECK: Original depositor receiving the closed position rent, checked against the recorded one
+  #[account(mut, address = pda_lockbox_position.depositor)]
+  pub depositor: UncheckedAccount<'info>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool, constraint = tick_array_lower.key() != tick_array_upper.key())]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool)]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct MigratePosition<'info> {
+  #[account(mut)]
+  pub migrator: Signer<'info>,
+
+  #[account(mut,
+    constraint = migrator_position_account.mint == position.position_mint,
+    constraint = migrator.key == &migrator_position_account.owner
+  )]
+  pub migrator_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    constraint = pda_position_account.mint == position.position_mint,
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = pda_lockbox_position.position_account == position.key(),
+    constraint = pda_lockbox_position.position_pda_ata == pda_position_account.key(),
+    constraint = pda_lockbox_position.to_account_info().owner == lockbox.to_account_info().owner
+  )]
+  pub pda_lockbox_position: Box<Account<'info, LockboxPosition>>,
+
+  /// CHECK: Original depositor receiving the closed position rent, checked against the recorded one
+  #[account(mut, address = pda_lockbox_position.depositor)]
+  pub depositor: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  /// CHECK: Lockbox account at any supported state layout version, checked against the lockbox PDA
+  #[account(mut)]
+  pub lockbox: UncheckedAccount<'info>,
+
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(init_if_needed,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump,
+    payer = signer,
+    space = PositionIndex::LEN)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DecommissionLockbox<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut, close = signer)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(mut,
+    seeds = [
+      b"position_index".as_ref()
+    ],
+    bump,
+    close = signer)]
+  pub position_index: AccountLoader<'info, PositionIndex>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct SetDepositLimits<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawRateLimit<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>
+}
+
+
+#[error_code]
+pub enum ErrorCode {
+  #[msg("Liquidity value overflow")]
+  LiquidityOverflow,
+  #[msg("Wrong whirlpool address")]
+  WrongWhirlpool,
+  #[msg("Wrong position PDA header")]
+  WrongPositionHeader,
+  #[msg("Wrong position ID")]
+  WrongPositionId,
+  #[msg("Liquidity is zero")]
+  LiquidityZero,
+  #[msg("Total liquidity is zero")]
+  TotalLiquidityZero,
+  #[msg("Requested amount exceeds a position liquidity")]
+  AmountExceedsPositionLiquidity,
+  #[msg("Requested amount exceeds total liquidity")]
+  AmountExceedsTotalLiquidity,
+  #[msg("Tick out of range")]
+  OutOfRange,
+  #[msg("Wrong account owner")]
+  WrongOwner,
+  #[msg("Provided wrong position PDA")]
+  WrongPositionPDA,
+  #[msg("Provided wrong lockbox PDA")]
+  WrongLockboxPDA,
+  #[msg("Provided wrong position ATA")]
+  WrongPositionAccount,
   #[msg("Provided wrong PDA position ATA")]
   WrongPDAPositionAccount,
   #[msg("Provided wrong Orca program account")]
   WrongOrcaAccount,
   #[msg("Wrong token mint")]
-  WrongTokenMint
+  WrongTokenMint,
+  #[msg("Wrong number of supplied positions")]
+  WrongNumberOfPositions,
+  #[msg("Provided wrong position depositor")]
+  WrongDepositor,
+  #[msg("Migrator is not the lockbox v2 PDA")]
+  WrongMigrator,
+  #[msg("Unsupported state layout version")]
+  UnsupportedVersion,
+  #[msg("Signer is not the program upgrade authority")]
+  WrongAdmin,
+  #[msg("Liquidity is not zero")]
+  LiquidityNotZero,
+  #[msg("Bridged token supply is not zero")]
+  BridgedSupplyNotZero,
+  #[msg("Deposit liquidity is below the minimum deposit")]
+  DepositBelowMinimum,
+  #[msg("Deposit liquidity exceeds the per-transaction limit")]
+  DepositExceedsLimit,
+  #[msg("Total liquidity cap exceeded")]
+  TotalLiquidityCapExceeded,
+  #[msg("Maximum deposit is below the minimum deposit")]
+  WrongDepositLimits,
+  #[msg("User deposit cap exceeded")]
+  UserDepositCapExceeded,
+  #[msg("Signer is not allowlisted")]
+  NotAllowlisted,
+  #[msg("Withdrawal rate limit exceeded")]
+  WithdrawRateLimitExceeded,
+  #[msg("Wrong bridged token mint authority")]
+  WrongMintAuthority,
+  #[msg("Bridged token mint has a freeze authority")]
+  FreezeAuthoritySet,
+  #[msg("Wrong bridged token decimals")]
+  WrongMintDecimals,
+  #[msg("Token account balance is not zero")]
+  TokenBalanceNotZero
 }
 
 
@@ -659,3 +2424,143 @@ pub struct WithdrawEvent {
     // Position liquidity remainder
     pub remainder: u64
 }
+
+#[event]
+pub struct WithdrawProRataEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+
+    // User ATA token A
+    token_owner_account_a: Pubkey,
+    // User ATA token B
+    token_owner_account_b: Pubkey,
+    // Withdraw amount
+    pub amount: u64,
+    // Number of positions the amount was withdrawn from
+    pub num_positions: u32,
+    // Total liquidity remainder
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct ConsolidateEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Primary PDA lockbox position account
+    #[index]
+    pub pda_lockbox_position: Pubkey,
+
+    // Primary position account
+    pub position: Pubkey,
+    // Number of consolidated positions
+    pub num_positions: u32,
+    // Consolidated liquidity
+    pub consolidated_liquidity: u64,
+    // Primary position liquidity after the consolidation
+    pub position_liquidity: u64
+}
+
+#[event]
+pub struct RedeemPositionEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Closed PDA lockbox position account
+    #[index]
+    pub pda_lockbox_position: Pubkey,
+
+    // Position account
+    pub position: Pubkey,
+    // User ATA receiving the position NFT
+    pub position_token_account: Pubkey,
+    // Redeemed position liquidity
+    pub position_liquidity: u64
+}
+
+#[event]
+pub struct MigratePositionEvent {
+    // Migrator (lockbox v2 PDA)
+    #[index]
+    pub migrator: Pubkey,
+    // Closed PDA lockbox position account
+    #[index]
+    pub pda_lockbox_position: Pubkey,
+
+    // Position account
+    pub position: Pubkey,
+    // Migrated position liquidity
+    pub position_liquidity: u64
+}
+
+#[event]
+pub struct MigrateStateEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Number of migrated accounts
+    pub num_accounts: u32,
+    // State layout version
+    pub version: u8
+}
+
+#[event]
+pub struct DecommissionEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+    // Closed lockbox account
+    #[index]
+    pub lockbox: Pubkey,
+
+    // Number of closed lockbox-owned token accounts
+    pub num_token_accounts: u32
+}
+
+#[event]
+pub struct DepositLimitsEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Maximum total liquidity
+    pub max_total_liquidity: u64,
+    // Maximum liquidity per deposit
+    pub max_deposit_per_tx: u64,
+    // Minimum liquidity per deposit
+    pub min_deposit: u64
+}
+
+#[event]
+pub struct UserDepositCapEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Maximum cumulative liquidity deposited per user
+    pub max_user_deposit: u64
+}
+
+#[event]
+pub struct AllowlistRootEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Allowlist Merkle root
+    pub allowlist_root: [u8; 32]
+}
+
+#[event]
+pub struct WithdrawRateLimitEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Window length in slots
+    pub withdraw_window_slots: u64,
+    // Maximum liquidity withdrawn per window
+    pub max_withdraw_per_window: u64
+}
@@ -1,9 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use crate::ErrorCode;
 
 #[account]
 pub struct LiquidityLockbox {
   // Lockbox bump
   pub bump: [u8; 1],
+  // State layout version, right after the bump such that it can be read before the account is deserialized
+  pub version: u8,
   // Bridged token mint address
   pub bridged_token_mint: Pubkey,
   // Fee collector ATA for token A
@@ -15,11 +19,37 @@ pub struct LiquidityLockbox {
   pub total_liquidity: u64,
   // Total number of lockbox positions
   // Even if position is created every second, it would take 136+ years to create 2^32 - 1 positions
-  pub num_positions: u32
+  pub num_positions: u32,
+  // Maximum total liquidity, zero if not capped
+  pub max_total_liquidity: u64,
+  // Maximum liquidity per deposit, zero if not capped
+  pub max_deposit_per_tx: u64,
+  // Minimum liquidity per deposit
+  pub min_deposit: u64,
+  // Maximum cumulative liquidity deposited per user, zero if not capped
+  pub max_user_deposit: u64,
+  // Depositor allowlist Merkle root, zero if the allowlist mode is disabled
+  pub allowlist_root: [u8; 32],
+  // Withdrawal rate limit window length in slots, zero if withdrawals are not rate limited
+  pub withdraw_window_slots: u64,
+  // Maximum liquidity withdrawn per window
+  pub max_withdraw_per_window: u64,
+  // Start slot of the current withdrawal window
+  pub withdraw_window_start: u64,
+  // Liquidity withdrawn in the current window
+  pub withdrawn_in_window: u64,
+  // Lockbox whirlpool
+  pub whirlpool: Pubkey
 }
 
 impl LiquidityLockbox {
-  pub const LEN: usize = 8 + 1 + 32 * 3 + 8 + 4;
+  pub const LEN: usize = 8 + 1 + 1 + 32 * 3 + 8 + 4 + 8 * 4 + 32 + 8 * 4 + 32;
+  // Length of the account created before the state layout versioning
+  pub const LEN_V0: usize = 8 + 1 + 32 * 3 + 8 + 4;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+  // Offset of the version field in the account data
+  pub const VERSION_OFFSET: usize = 8 + 1;
 
   pub fn seeds(&self) -> [&[u8]; 2] {
     [
@@ -33,14 +63,232 @@ impl LiquidityLockbox {
     bump: u8,
     bridged_token_mint: Pubkey,
     fee_collector_token_owner_account_a: Pubkey,
-    fee_collector_token_owner_account_b: Pubkey
+    fee_collector_token_owner_account_b: Pubkey,
+    whirlpool: Pubkey
   ) -> Result<()> {
     self.bridged_token_mint = bridged_token_mint;
     self.fee_collector_token_owner_account_a = fee_collector_token_owner_account_a;
     self.fee_collector_token_owner_account_b = fee_collector_token_owner_account_b;
     self.total_liquidity = 0;
     self.num_positions = 0;
+    self.max_total_liquidity = 0;
+    self.max_deposit_per_tx = 0;
+    self.min_deposit = 0;
+    self.max_user_deposit = 0;
+    self.allowlist_root = [0u8; 32];
+    self.withdraw_window_slots = 0;
+    self.max_withdraw_per_window = 0;
+    self.withdraw_window_start = 0;
+    self.withdrawn_in_window = 0;
+    self.whirlpool = whirlpool;
     self.bump = [bump];
+    self.version = Self::VERSION;
+
+    Ok(())
+  }
+
+  pub fn check_version(&self) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    Ok(())
+  }
+
+  /// Checks the deposit liquidity against the configured deposit limits.
+  pub fn check_deposit_limits(&self, liquidity: u64) -> Result<()> {
+    if liquidity < self.min_deposit {
+      return Err(ErrorCode::DepositBelowMinimum.into());
+    }
+
+    if self.max_deposit_per_tx > 0 && liquidity > self.max_deposit_per_tx {
+      return Err(ErrorCode::DepositExceedsLimit.into());
+    }
+
+    if self.max_total_liquidity > 0 {
+      match self.total_liquidity.checked_add(liquidity) {
+        Some(new_liquidity) if new_liquidity <= self.max_total_liquidity => {},
+        _ => return Err(ErrorCode::TotalLiquidityCapExceeded.into()),
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn set_deposit_limits(
+    &mut self,
+    max_total_liquidity: u64,
+    max_deposit_per_tx: u64,
+    min_deposit: u64
+  ) -> Result<()> {
+    // The per-transaction limit cannot be below the minimum deposit
+    if max_deposit_per_tx > 0 && max_deposit_per_tx < min_deposit {
+      return Err(ErrorCode::WrongDepositLimits.into());
+    }
+
+    self.max_total_liquidity = max_total_liquidity;
+    self.max_deposit_per_tx = max_deposit_per_tx;
+    self.min_deposit = min_deposit;
+
+    Ok(())
+  }
+
+  /// Checks the user against the allowlist Merkle root, if the allowlist mode is enabled.
+  /// Leaves are keccak hashes of user addresses, and node pairs are hashed in the sorted order.
+  pub fn check_allowlist(&self, user: &Pubkey, proof: &[[u8; 32]]) -> Result<()> {
+    // Zero root disables the allowlist mode
+    if self.allowlist_root == [0u8; 32] {
+      return Ok(());
+    }
+
+    let mut node = keccak::hash(user.as_ref()).to_bytes();
+    for sibling in proof {
+      node = if node <= *sibling {
+        keccak::hashv(&[&node, sibling]).to_bytes()
+      } else {
+        keccak::hashv(&[sibling, &node]).to_bytes()
+      };
+    }
+
+    if node != self.allowlist_root {
+      return Err(ErrorCode::NotAllowlisted.into());
+    }
+
+    Ok(())
+  }
+
+  /// Records the withdrawn liquidity against the withdrawal rate limit of the current window.
+  pub fn record_withdraw(&mut self, liquidity: u64, slot: u64) -> Result<()> {
+    // Zero window length disables the withdrawal rate limit
+    if self.withdraw_window_slots == 0 {
+      return Ok(());
+    }
+
+    // Start a new window when the current one has elapsed
+    if slot >= self.withdraw_window_start.saturating_add(self.withdraw_window_slots) {
+      self.withdraw_window_start = slot;
+      self.withdrawn_in_window = 0;
+    }
+
+    let withdrawn_in_window = self.withdrawn_in_window.checked_add(liquidity)
+      .ok_or(ErrorCode::LiquidityOverflow)?;
+    if withdrawn_in_window > self.max_withdraw_per_window {
+      return Err(ErrorCode::WithdrawRateLimitExceeded.into());
+    }
+    self.withdrawn_in_window = withdrawn_in_window;
+
+    Ok(())
+  }
+
+  pub fn set_withdraw_rate_limit(&mut self, withdraw_window_slots: u64, max_withdraw_per_window: u64) -> Result<()> {
+    self.withdraw_window_slots = withdraw_window_slots;
+    self.max_withdraw_per_window = max_withdraw_per_window;
+    // Restart the window such that the new limit applies from the next withdrawal
+    self.withdraw_window_start = 0;
+    self.withdrawn_in_window = 0;
+
+    Ok(())
+  }
+}
+
+// Lockbox account layout before the state layout versioning
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LiquidityLockboxV0 {
+  pub bump: [u8; 1],
+  pub bridged_token_mint: Pubkey,
+  pub fee_collector_token_owner_account_a: Pubkey,
+  pub fee_collector_token_owner_account_b: Pubkey,
+  pub total_liquidity: u64,
+  pub num_positions: u32
+}
+
+impl From<LiquidityLockboxV0> for LiquidityLockbox {
+  fn from(lockbox: LiquidityLockboxV0) -> Self {
+    Self {
+      bump: lockbox.bump,
+      version: Self::VERSION,
+      bridged_token_mint: lockbox.bridged_token_mint,
+      fee_collector_token_owner_account_a: lockbox.fee_collector_token_owner_account_a,
+      fee_collector_token_owner_account_b: lockbox.fee_collector_token_owner_account_b,
+      total_liquidity: lockbox.total_liquidity,
+      num_positions: lockbox.num_positions,
+      max_total_liquidity: 0,
+      max_deposit_per_tx: 0,
+      min_deposit: 0,
+      max_user_deposit: 0,
+      allowlist_root: [0u8; 32],
+      withdraw_window_slots: 0,
+      max_withdraw_per_window: 0,
+      withdraw_window_start: 0,
+      withdrawn_in_window: 0,
+      // The whirlpool is set during the migration
+      whirlpool: Pubkey::default()
+    }
+  }
+}
+
+#[account]
+pub struct UserState {
+  // User state bump
+  pub bump: [u8; 1],
+  // State layout version
+  pub version: u8,
+  // Lockbox the user state belongs to
+  pub lockbox: Pubkey,
+  // User address
+  pub user: Pubkey,
+  // Cumulative liquidity deposited by the user
+  pub total_deposited: u64,
+  // Cumulative liquidity withdrawn by the user
+  pub total_withdrawn: u64
+}
+
+impl UserState {
+  pub const LEN: usize = 8 + 1 + 1 + 32 + 32 + 8 + 8;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+
+  /// Initializes the user state on its creation, otherwise checks its layout version.
+  pub fn initialize_if_needed(&mut self, bump: u8, lockbox: Pubkey, user: Pubkey) -> Result<()> {
+    if self.version == 0 {
+      self.bump = [bump];
+      self.lockbox = lockbox;
+      self.user = user;
+      self.total_deposited = 0;
+      self.total_withdrawn = 0;
+      self.version = Self::VERSION;
+    }
+
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    Ok(())
+  }
+
+  /// Records the deposited liquidity, checking the user cumulative deposited liquidity against the user cap.
+  /// Withdrawals do not free up the cap since bridged tokens are fungible and can be acquired from other holders.
+  pub fn record_deposit(&mut self, liquidity: u64, max_user_deposit: u64) -> Result<()> {
+    let total_deposited = self.total_deposited.checked_add(liquidity)
+      .ok_or(ErrorCode::LiquidityOverflow)?;
+
+    if max_user_deposit > 0 && total_deposited > max_user_deposit {
+      return Err(ErrorCode::UserDepositCapExceeded.into());
+    }
+
+    self.total_deposited = total_deposited;
+
+    Ok(())
+  }
+
+  /// Records the withdrawn liquidity, which is tracked for accounting only and does not affect the user cap.
+  pub fn record_withdraw(&mut self, liquidity: u64) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    self.total_withdrawn = self.total_withdrawn.checked_add(liquidity)
+      .ok_or(ErrorCode::LiquidityOverflow)?;
 
     Ok(())
   }
@@ -52,16 +300,32 @@ pub struct LockboxPosition {
   pub id: [u8; 4],
   // Position bump
   pub bump: [u8; 1],
+  // State layout version
+  pub version: u8,
   // Locked position data account
   pub position_account: Pubkey,
   // Locked position PDA ATA
   pub position_pda_ata: Pubkey,
   // Locked position liquidity
-  pub position_liquidity: u64
+  pub position_liquidity: u64,
+  // Position depositor, receives the rent when the position is closed
+  pub depositor: Pubkey,
+  // Deposit slot
+  pub deposit_slot: u64,
+  // Deposit unix timestamp
+  pub deposit_timestamp: i64,
+  // Liquidity of the position at the time of deposit
+  pub initial_liquidity: u64
 }
 
 impl LockboxPosition {
-  pub const LEN: usize = 8 + 4 + 1 + 32 + 32 + 8;
+  pub const LEN: usize = 8 + 4 + 1 + 1 + 32 + 32 + 8 + 32 + 8 + 8 + 8;
+  // Length of the account created before the state layout versioning
+  pub const LEN_V0: usize = 8 + 4 + 1 + 32 + 32 + 8;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+  // Offset of the version field in the account data
+  pub const VERSION_OFFSET: usize = 8 + 4 + 1;
 
   pub fn seeds(&self) -> [&[u8]; 3] {
     [
@@ -77,14 +341,157 @@ impl LockboxPosition {
     bump: u8,
     position_liquidity: u64,
     position_account: Pubkey,
-    position_pda_ata: Pubkey
+    position_pda_ata: Pubkey,
+    depositor: Pubkey,
+    deposit_slot: u64,
+    deposit_timestamp: i64
   ) -> Result<()> {
     self.id = id.to_be_bytes();
     self.bump = [bump];
     self.position_liquidity = position_liquidity;
     self.position_account = position_account;
     self.position_pda_ata = position_pda_ata;
+    self.depositor = depositor;
+    self.deposit_slot = deposit_slot;
+    self.deposit_timestamp = deposit_timestamp;
+    self.initial_liquidity = position_liquidity;
+    self.version = Self::VERSION;
+
+    Ok(())
+  }
+
+  pub fn check_version(&self) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
 
     Ok(())
   }
-}
\ No newline at end of file
+
+  /// Converts the position account created before the state layout versioning.
+  /// Deposit details were not recorded, such that the depositor is taken from the position deposit transaction
+  /// in order to return the position rent to it when the position is closed.
+  pub fn from_v0(position: LockboxPositionV0, depositor: Pubkey) -> Self {
+    Self {
+      id: position.id,
+      bump: position.bump,
+      version: Self::VERSION,
+      position_account: position.position_account,
+      position_pda_ata: position.position_pda_ata,
+      position_liquidity: position.position_liquidity,
+      depositor,
+      deposit_slot: 0,
+      deposit_timestamp: 0,
+      initial_liquidity: position.position_liquidity
+    }
+  }
+}
+
+// Lockbox position account layout before the state layout versioning
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LockboxPositionV0 {
+  pub id: [u8; 4],
+  pub bump: [u8; 1],
+  pub position_account: Pubkey,
+  pub position_pda_ata: Pubkey,
+  pub position_liquidity: u64
+}
+
+// Maximum number of active positions tracked by the position index, positions deposited over it are not indexed
+pub const MAX_INDEXED_POSITIONS: usize = 512;
+
+#[zero_copy]
+pub struct PositionIndexEntry {
+  // Lockbox position liquidity
+  pub liquidity: u64,
+  // Lockbox position identifier
+  pub id: u32
+}
+
+#[account(zero_copy)]
+pub struct PositionIndex {
+  // Position index bump
+  pub bump: [u8; 1],
+  // Number of active lockbox positions
+  pub num_active: u32,
+  // Entry slot of the next position to drain
+  pub next_to_drain: u32,
+  // Active lockbox positions, densely packed in the first num_active slots
+  pub entries: [PositionIndexEntry; MAX_INDEXED_POSITIONS]
+}
+
+impl PositionIndex {
+  pub const LEN: usize = 8 + 1 + 4 + 4 + (8 + 4) * MAX_INDEXED_POSITIONS;
+
+  pub fn initialize(&mut self, bump: u8) -> Result<()> {
+    self.bump = [bump];
+    self.num_active = 0;
+    self.next_to_drain = 0;
+
+    Ok(())
+  }
+
+  /// Gets the next position to drain, if any position is active.
+  pub fn next_position(&self) -> Option<PositionIndexEntry> {
+    if self.num_active == 0 {
+      None
+    } else {
+      Some(self.entries[self.next_to_drain as usize])
+    }
+  }
+
+  /// Adds a newly deposited position to the index, skipping it if the index is full.
+  pub fn add(&mut self, id: u32, liquidity: u64) -> Result<()> {
+    let num_active = self.num_active as usize;
+    if num_active == MAX_INDEXED_POSITIONS {
+      return Ok(());
+    }
+
+    self.entries[num_active] = PositionIndexEntry { liquidity, id };
+    self.num_active += 1;
+    self.update_next_to_drain();
+
+    Ok(())
+  }
+
+  /// Updates the position liquidity, removing the position from the index when it is fully drained.
+  /// Positions that were not indexed as the index was full are skipped.
+  pub fn update(&mut self, id: u32, liquidity: u64) -> Result<()> {
+    let slot = match self.find(id) {
+      Some(slot) => slot,
+      None => return Ok(()),
+    };
+
+    if liquidity == 0 {
+      // Move the last active entry into the freed slot to keep entries densely packed
+      let last = self.num_active as usize - 1;
+      self.entries[slot] = self.entries[last];
+      self.entries[last] = PositionIndexEntry { liquidity: 0, id: 0 };
+      self.num_active -= 1;
+    } else {
+      self.entries[slot].liquidity = liquidity;
+    }
+    self.update_next_to_drain();
+
+    Ok(())
+  }
+
+  /// Finds the entry slot of the position by its id.
+  pub fn find(&self, id: u32) -> Option<usize> {
+    let num_active = self.num_active as usize;
+    // Packed fields are copied out before the comparison as references to them might be unaligned
+    self.entries[..num_active].iter().position(|entry| { entry.id } == id)
+  }
+
+  // Points to the position with the smallest liquidity such that positions get closed as soon as possible
+  fn update_next_to_drain(&mut self) {
+    let num_active = self.num_active as usize;
+    let mut next_to_drain = 0;
+    for slot in 1..num_active {
+      if { self.entries[slot].liquidity } < { self.entries[next_to_drain].liquidity } {
+        next_to_drain = slot;
+      }
+    }
+    self.next_to_drain = next_to_drain as u32;
+  }
+}
@@ -163,6 +163,7 @@ async function main() {
               bridgedTokenMint: bridgedTokenMint,
               feeCollectorTokenOwnerAccountA: feeCollectorTokenOwnerAccountA.address,
               feeCollectorTokenOwnerAccountB: feeCollectorTokenOwnerAccountB.address,
+              whirlpool: whirlpool,
             }
           )
           .rpc();
@@ -190,6 +191,7 @@ async function main() {
               bridgedTokenMint: bridgedTokenMint,
               feeCollectorTokenOwnerAccountA: feeCollectorTokenOwnerAccountA.address,
               feeCollectorTokenOwnerAccountB: feeCollectorTokenOwnerAccountB.address,
+              whirlpool: whirlpool,
             }
           )
           .rpc();
@@ -318,7 +320,7 @@ async function main() {
 
     // Try to pass another user ATA with a mint that is different from the position mint
     try {
-        signature = await program.methods.deposit(numPosition)
+        signature = await program.methods.deposit(numPosition, [])
           .accounts(
               {
                 lockbox: pdaProgram,
@@ -336,7 +338,7 @@ async function main() {
 
     // Try to pass user position ATA instead of the PDA position ATA
     try {
-        signature = await program.methods.deposit(numPosition)
+        signature = await program.methods.deposit(numPosition, [])
           .accounts(
               {
                 lockbox: pdaProgram,
@@ -354,7 +356,7 @@ async function main() {
 
     // Execute the correct deposit tx
     try {
-        signature = await program.methods.deposit(numPosition)
+        signature = await program.methods.deposit(numPosition, [])
           .accounts(
               {
                 lockbox: pdaProgram,
@@ -412,6 +414,12 @@ async function main() {
   lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
   expect(data.liquidity.toString()).toEqual(lockboxStateData.totalLiquidity.toString());
 
+    // Check the position is recorded in the index of active positions
+    const [pdaPositionIndex] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("position_index", "utf-8")], program.programId);
+    let positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+    expect(positionIndexData.numActive).toEqual(1);
+    expect(positionIndexData.entries[positionIndexData.nextToDrain].id).toEqual(numPosition);
+
     // Get the current position number
     numPosition = lockboxStateData.numPositions;
 
@@ -423,7 +431,7 @@ async function main() {
 
     // Execute the correct deposit tx for position 2
     try {
-        signature = await program.methods.deposit(numPosition)
+        signature = await program.methods.deposit(numPosition, [])
           .accounts(
               {
                 lockbox: pdaProgram,
@@ -486,6 +494,7 @@ async function main() {
                 position: position.publicKey,
                 positionMint: positionMint,
                 pdaLockboxPosition: pdaLockboxPosition,
+                depositor: userWallet.publicKey,
                 bridgedTokenAccount: bridgedTokenAccount.address,
                 bridgedTokenMint: bridgedTokenMint,
                 pdaPositionAccount: pdaPositionAccount,
@@ -518,6 +527,7 @@ async function main() {
                 position: bridgedTokenAccount.address,
                 positionMint: positionMint,
                 pdaLockboxPosition: pdaLockboxPosition,
+                depositor: userWallet.publicKey,
                 bridgedTokenAccount: bridgedTokenAccount.address,
                 bridgedTokenMint: bridgedTokenMint,
                 pdaPositionAccount: pdaPositionAccount,
@@ -544,6 +554,8 @@ async function main() {
     expect(positionStateData.positionAccount.toString()).toEqual(position.publicKey.toString());
     expect(positionStateData.positionPdaAta.toString()).toEqual(pdaPositionAccount.toString());
     expect(positionStateData.positionLiquidity.toString()).toEqual(data.liquidity.toString());
+    expect(positionStateData.depositor.toString()).toEqual(userWallet.publicKey.toString());
+    expect(positionStateData.initialLiquidity.toString()).toEqual(data.liquidity.toString());
 
     // Execute the correct withdraw tx
     console.log("Amount of bridged tokens to withdraw:", tBalalnce.toString());
@@ -558,6 +570,7 @@ async function main() {
                 position: position.publicKey,
                 positionMint: positionMint,
                 pdaLockboxPosition: pdaLockboxPosition,
+                depositor: userWallet.publicKey,
                 bridgedTokenAccount: bridgedTokenAccount.address,
                 bridgedTokenMint: bridgedTokenMint,
                 pdaPositionAccount: pdaPositionAccount,
@@ -613,6 +626,7 @@ async function main() {
                 position: position2.publicKey,
                 positionMint: positionMint2,
                 pdaLockboxPosition: pdaLockboxPosition2,
+                depositor: userWallet.publicKey,
                 bridgedTokenAccount: bridgedTokenAccount.address,
                 bridgedTokenMint: bridgedTokenMint,
                 pdaPositionAccount: pdaPositionAccount2,
@@ -652,6 +666,10 @@ async function main() {
 
   lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
   console.log("Liquidity now:", lockboxStateData.totalLiquidity.toString());
+
+    // All the positions are drained and removed from the index
+    positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+    expect(positionIndexData.numActive).toEqual(0);
 }
 
 main();
@@ -0,0 +1,155 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getOrCreateAssociatedTokenAccount, getAccount, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import expect from "expect";
+import {
+  confirm, setupLockbox, orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test checks the index of active positions, and must be run on a freshly started validator.sh right after
+// the initialization script
+async function main() {
+  const {
+    provider, program, client, userWallet, token_a, token_b, pdaProgram, pdaPositionIndex, tokenOwnerAccountA, tokenOwnerAccountB,
+    bridgedTokenAccount, openAndDeposit, positionAccounts
+  } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  // Get the lockbox token ATAs holding the tokens of consolidated positions
+  const lockboxTokenAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      pdaProgram,
+      true
+  );
+  const lockboxTokenAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      pdaProgram,
+      true
+  );
+
+
+  // Token max amounts cover the rounding of the primary position liquidity increase, and are mostly returned
+  const tokenMax = new anchor.BN(1000000);
+
+  async function consolidate(primary, remainingAccounts) {
+    const signature = await program.methods.consolidate(tokenMax, tokenMax)
+      .accounts(
+          {
+            whirlpool: whirlpool,
+            position: primary.position,
+            pdaPositionAccount: primary.pdaPositionAccount,
+            pdaLockboxPosition: primary.pdaLockboxPosition,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            lockboxTokenAccountA: lockboxTokenAccountA.address,
+            lockboxTokenAccountB: lockboxTokenAccountB.address,
+            feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+            feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: tickArrayLower,
+            tickArrayUpper: tickArrayUpper,
+            lockbox: pdaProgram,
+            positionIndex: pdaPositionIndex,
+            whirlpoolProgram: orca,
+            tokenProgram: TOKEN_PROGRAM_ID
+          }
+      )
+      .remainingAccounts(remainingAccounts)
+      .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 1000000 })])
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing three positions");
+
+  const position1 = await openAndDeposit("10" /* olas */);
+  const position2 = await openAndDeposit("20" /* olas */);
+  const position3 = await openAndDeposit("30" /* olas */);
+
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  let positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(3);
+
+  // ############################## WRONG POSITIONS ##############################
+  console.log("\nRejecting wrong consolidations");
+
+  // Try to consolidate without supplying any position
+  try {
+      await consolidate(position1, []);
+      throw new Error("Consolidate must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongNumberOfPositions");
+  }
+
+  // Try to consolidate the primary position into itself
+  try {
+      await consolidate(position1, positionAccounts(position1));
+      throw new Error("Consolidate must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPositionId");
+  }
+
+  // Try to supply the same position twice
+  try {
+      await consolidate(position1, [...positionAccounts(position2), ...positionAccounts(position2)]);
+      throw new Error("Consolidate must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPositionId");
+  }
+
+  // ############################## CONSOLIDATE ##############################
+  console.log("\nConsolidating positions into the primary one");
+
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  const balanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  const lockboxBalanceABefore = (await getAccount(provider.connection, lockboxTokenAccountA.address)).amount;
+  const lockboxBalanceBBefore = (await getAccount(provider.connection, lockboxTokenAccountB.address)).amount;
+
+  await consolidate(position1, [...positionAccounts(position2), ...positionAccounts(position3)]);
+
+  // The primary position holds the whole liquidity, and the total liquidity and bridged tokens are unchanged
+  const consolidatedLiquidity = position1.liquidity.add(position2.liquidity).add(position3.liquidity);
+  const positionData = await program.account.lockboxPosition.fetch(position1.pdaLockboxPosition);
+  expect(positionData.positionLiquidity.toString()).toEqual(consolidatedLiquidity.toString());
+  expect((await client.getPosition(position1.position, true)).getData().liquidity.toString())
+    .toEqual(consolidatedLiquidity.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.toString());
+  const bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect(bridgedBalance.toString()).toEqual(bridgedBalanceBefore.toString());
+
+  // Consolidated positions are closed and removed from the index of active positions
+  expect(await provider.connection.getAccountInfo(position2.pdaLockboxPosition)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position3.pdaLockboxPosition)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position2.position)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position3.position)).toBeNull();
+  positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(1);
+  expect(positionIndexData.entries[positionIndexData.nextToDrain].id).toEqual(position1.id);
+
+  // Only the rounding is taken from the signer tokens, and nothing is left in the lockbox token accounts
+  const balanceA = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceB = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  expect(balanceABefore - balanceA).toBeGreaterThanOrEqual(BigInt(0));
+  expect(balanceABefore - balanceA).toBeLessThanOrEqual(BigInt(10));
+  expect(balanceBBefore - balanceB).toBeGreaterThanOrEqual(BigInt(0));
+  expect(balanceBBefore - balanceB).toBeLessThanOrEqual(BigInt(10));
+  expect((await getAccount(provider.connection, lockboxTokenAccountA.address)).amount).toEqual(lockboxBalanceABefore);
+  expect((await getAccount(provider.connection, lockboxTokenAccountB.address)).amount).toEqual(lockboxBalanceBBefore);
+
+  console.log("Successfully tested position consolidation");
+}
+
+main();
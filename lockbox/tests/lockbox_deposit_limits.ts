@@ -0,0 +1,114 @@
+import * as anchor from "@coral-xyz/anchor";
+import expect from "expect";
+import { confirm, setupLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const { provider, program, userWallet, pdaProgram, adminAccounts, openAndDeposit } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  async function setDepositLimits(maxTotalLiquidity: anchor.BN, maxDepositPerTx: anchor.BN, minDeposit: anchor.BN, signer = userWallet) {
+    const signature = await program.methods.setDepositLimits(maxTotalLiquidity, maxDepositPerTx, minDeposit)
+      .accounts(adminAccounts(signer))
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  const zero = new anchor.BN(0);
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing a position without limits");
+
+  // Liquidity of a position of the same OLAS amount is the same at the unchanged whirlpool price
+  const liquidity = (await openAndDeposit("10" /* olas */)).liquidity;
+
+  // ############################## ADMIN ##############################
+  console.log("\nSetting deposit limits");
+
+  // Try to set deposit limits by someone other than the program upgrade authority
+  try {
+      await setDepositLimits(zero, zero, zero, anchor.web3.Keypair.generate());
+      throw new Error("Set deposit limits must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongAdmin");
+  }
+
+  // Try to set the per-deposit limit below the minimum deposit
+  try {
+      await setDepositLimits(zero, liquidity, liquidity.add(new anchor.BN(1)));
+      throw new Error("Set deposit limits must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongDepositLimits");
+  }
+
+  // ############################## PER-DEPOSIT LIMITS ##############################
+  console.log("\nDepositing within the per-deposit limits");
+
+  await setDepositLimits(zero, liquidity.mul(new anchor.BN(2)), liquidity.div(new anchor.BN(2)));
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.maxTotalLiquidity.toString()).toEqual("0");
+  expect(lockboxStateData.maxDepositPerTx.toString()).toEqual(liquidity.mul(new anchor.BN(2)).toString());
+  expect(lockboxStateData.minDeposit.toString()).toEqual(liquidity.div(new anchor.BN(2)).toString());
+
+  // Try to deposit below the minimum deposit
+  try {
+      await openAndDeposit("1" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("DepositBelowMinimum");
+  }
+
+  // Try to deposit above the per-deposit limit
+  try {
+      await openAndDeposit("30" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("DepositExceedsLimit");
+  }
+
+  let totalLiquidity = lockboxStateData.totalLiquidity;
+  await openAndDeposit("10" /* olas */);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.add(liquidity).toString());
+
+  // ############################## TOTAL LIQUIDITY CAP ##############################
+  console.log("\nDepositing up to the total liquidity cap");
+
+  // The cap leaves room for less than a deposit
+  totalLiquidity = lockboxStateData.totalLiquidity;
+  await setDepositLimits(totalLiquidity.add(liquidity).sub(new anchor.BN(1)), zero, zero);
+
+  // Try to deposit above the total liquidity cap
+  try {
+      await openAndDeposit("10" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("TotalLiquidityCapExceeded");
+  }
+
+  // A deposit reaching exactly the cap is accepted
+  await setDepositLimits(totalLiquidity.add(liquidity), zero, zero);
+  await openAndDeposit("10" /* olas */);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(lockboxStateData.maxTotalLiquidity.toString());
+
+  // Try to deposit any liquidity once the cap is reached
+  try {
+      await openAndDeposit("1" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("TotalLiquidityCapExceeded");
+  }
+
+  // Zero limits disable the deposit limits
+  await setDepositLimits(zero, zero, zero);
+  await openAndDeposit("1" /* olas */);
+
+  console.log("Successfully tested deposit limits");
+}
+
+main();
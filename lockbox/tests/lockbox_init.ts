@@ -109,6 +109,7 @@ async function main() {
               bridgedTokenMint: bridgedTokenMint,
               feeCollectorTokenOwnerAccountA: feeCollectorTokenOwnerAccountA.address,
               feeCollectorTokenOwnerAccountB: feeCollectorTokenOwnerAccountB.address,
+              whirlpool: whirlpool,
             }
           )
           .rpc();
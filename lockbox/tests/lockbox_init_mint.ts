@@ -0,0 +1,127 @@
+import * as idl from "../target/idl/liquidity_lockbox.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { LiquidityLockbox } from "../target/types/liquidity_lockbox";
+import {
+  createMint, mintTo, setAuthority, AuthorityType, getOrCreateAssociatedTokenAccount
+} from "@solana/spl-token";
+import { WhirlpoolContext, buildWhirlpoolClient } from "@orca-so/whirlpools-sdk";
+import expect from "expect";
+import { PROGRAM_ID, orca, whirlpool, confirm } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test initializes the lockbox, and must be run on a freshly started validator.sh without the initialization script
+async function main() {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = new Program(idl as anchor.Idl, PROGRAM_ID, anchor.getProvider()) as Program<LiquidityLockbox>;
+
+  // User wallet is the provider payer
+  const userWallet = provider.wallet["payer"];
+  console.log("User wallet:", userWallet.publicKey.toBase58());
+
+  const ctx = WhirlpoolContext.withProvider(provider, orca);
+  const client = buildWhirlpoolClient(ctx);
+  const whirlpoolClient = await client.getPool(whirlpool);
+  const token_a = whirlpoolClient.getTokenAInfo();
+  const token_b = whirlpoolClient.getTokenBInfo();
+
+  // Find a PDA account for the program
+  const [pdaProgram] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("liquidity_lockbox", "utf-8")], program.programId);
+
+  // Get the user token ATAs used as fee collectors
+  const feeCollectorTokenOwnerAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      userWallet.publicKey
+  );
+  const feeCollectorTokenOwnerAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      userWallet.publicKey
+  );
+
+  async function initialize(bridgedTokenMint: anchor.web3.PublicKey) {
+    const signature = await program.methods
+      .initialize()
+      .accounts(
+        {
+          bridgedTokenMint: bridgedTokenMint,
+          feeCollectorTokenOwnerAccountA: feeCollectorTokenOwnerAccountA.address,
+          feeCollectorTokenOwnerAccountB: feeCollectorTokenOwnerAccountB.address,
+          whirlpool: whirlpool
+        }
+      )
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## WRONG MINTS ##############################
+  console.log("\nRejecting misconfigured bridged token mints");
+
+  // Try to initialize with a mint that has a freeze authority
+  let bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, userWallet.publicKey, 8);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("FreezeAuthoritySet");
+  }
+
+  // Try to initialize with a mint of wrong decimals
+  bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, null, 6);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongMintDecimals");
+  }
+
+  // Try to initialize with a mint that already has a supply
+  bridgedTokenMint = await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8);
+  const userBridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      userWallet.publicKey
+  );
+  await mintTo(provider.connection, userWallet, bridgedTokenMint, userBridgedTokenAccount.address, userWallet, 1);
+  await setAuthority(provider.connection, userWallet, bridgedTokenMint, userWallet, AuthorityType.MintTokens, pdaProgram);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("BridgedSupplyNotZero");
+  }
+
+  // Try to initialize with a mint that has no mint authority, which is rejected without a panic
+  bridgedTokenMint = await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8);
+  await setAuthority(provider.connection, userWallet, bridgedTokenMint, userWallet, AuthorityType.MintTokens, null);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongMintAuthority");
+  }
+
+  // ############################## INITIALIZE ##############################
+  console.log("\nInitializing with a valid bridged token mint");
+
+  bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, null, 8);
+  await initialize(bridgedTokenMint);
+
+  const lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.bridgedTokenMint.toBase58()).toEqual(bridgedTokenMint.toBase58());
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual("0");
+
+  console.log("Successfully tested the bridged token mint validation");
+}
+
+main();
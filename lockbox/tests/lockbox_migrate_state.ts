@@ -0,0 +1,127 @@
+import * as idl from "../target/idl/liquidity_lockbox.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { LiquidityLockbox } from "../target/types/liquidity_lockbox";
+import expect from "expect";
+import { PROGRAM_ID, BPF_LOADER_UPGRADEABLE, whirlpool, confirm } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test migrates the legacy lockbox and lockbox position accounts loaded from fork_v0, and must be run on a freshly
+// started validator_migration.sh without the initialization script
+async function main() {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = new Program(idl as anchor.Idl, PROGRAM_ID, anchor.getProvider()) as Program<LiquidityLockbox>;
+  const [programData] = await anchor.web3.PublicKey.findProgramAddress([PROGRAM_ID.toBuffer()], BPF_LOADER_UPGRADEABLE);
+
+  // User wallet is the provider payer and the program upgrade authority
+  const userWallet = provider.wallet["payer"];
+  console.log("User wallet:", userWallet.publicKey.toBase58());
+
+  // Find PDA accounts for the program, the first lockbox position and the index of active positions
+  const [pdaProgram] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("liquidity_lockbox", "utf-8")], program.programId);
+  const idBytes = Buffer.alloc(4);
+  const [pdaLockboxPosition] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("lockbox_position", "utf-8"), idBytes], program.programId
+  );
+  const [pdaPositionIndex] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("position_index", "utf-8")], program.programId);
+
+  // Legacy account lengths before the state layout versioning
+  const lockboxLenV0 = 8 + 1 + 32 * 3 + 8 + 4;
+  const positionLenV0 = 8 + 4 + 1 + 32 + 32 + 8;
+
+  async function setDepositLimits(maxTotalLiquidity: anchor.BN) {
+    const signature = await program.methods.setDepositLimits(maxTotalLiquidity, new anchor.BN(0), new anchor.BN(0))
+      .accounts({ signer: userWallet.publicKey, program: PROGRAM_ID, programData, lockbox: pdaProgram })
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  async function migrateState(signer = userWallet) {
+    const signature = await program.methods.migrateState([userWallet.publicKey])
+      .accounts(
+          {
+            signer: signer.publicKey,
+            lockbox: pdaProgram,
+            whirlpool: whirlpool,
+            positionIndex: pdaPositionIndex,
+            program: PROGRAM_ID,
+            programData: programData
+          }
+      )
+      .remainingAccounts([{ pubkey: pdaLockboxPosition, isSigner: false, isWritable: true }])
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## LEGACY ACCOUNTS ##############################
+  console.log("\nChecking the legacy accounts");
+
+  expect((await provider.connection.getAccountInfo(pdaProgram)).data.length).toEqual(lockboxLenV0);
+  expect((await provider.connection.getAccountInfo(pdaLockboxPosition)).data.length).toEqual(positionLenV0);
+
+  // Legacy lockbox cannot be used before the migration
+  try {
+      await setDepositLimits(new anchor.BN(0));
+      throw new Error("Set deposit limits must fail");
+  } catch (error) {
+      expect(error.message).not.toContain("must fail");
+  }
+
+  // Try to migrate by someone other than the program upgrade authority
+  const notAdmin = anchor.web3.Keypair.generate();
+  await confirm(provider, await provider.connection.requestAirdrop(notAdmin.publicKey, 1000000000));
+  try {
+      await migrateState(notAdmin);
+      throw new Error("Migrate state must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongAdmin");
+  }
+
+  // ############################## MIGRATE ##############################
+  console.log("\nMigrating the legacy accounts");
+
+  await migrateState();
+
+  // Accounts are reallocated to the current layout length and keep their legacy data
+  expect((await provider.connection.getAccountInfo(pdaProgram)).data.length).toEqual(program.account.liquidityLockbox.size);
+  expect((await provider.connection.getAccountInfo(pdaLockboxPosition)).data.length)
+    .toEqual(program.account.lockboxPosition.size);
+
+  const lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.version).toEqual(1);
+  expect(lockboxStateData.whirlpool.toBase58()).toEqual(whirlpool.toBase58());
+  expect(lockboxStateData.numPositions).toEqual(1);
+  const positionData = await program.account.lockboxPosition.fetch(pdaLockboxPosition);
+  expect(positionData.version).toEqual(1);
+  expect(positionData.depositor.toBase58()).toEqual(userWallet.publicKey.toBase58());
+  expect(positionData.positionLiquidity.toString()).toEqual(lockboxStateData.totalLiquidity.toString());
+  expect(positionData.initialLiquidity.toString()).toEqual(positionData.positionLiquidity.toString());
+
+  // The active position is added to the index of active positions created by the migration
+  const positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(1);
+  expect(positionIndexData.entries[positionIndexData.nextToDrain].id).toEqual(0);
+
+  // The migrated lockbox passes the state layout version check
+  await setDepositLimits(lockboxStateData.totalLiquidity);
+  expect((await program.account.liquidityLockbox.fetch(pdaProgram)).maxTotalLiquidity.toString())
+    .toEqual(lockboxStateData.totalLiquidity.toString());
+  await setDepositLimits(new anchor.BN(0));
+
+  // Migrating the current layout again leaves the accounts untouched
+  await migrateState();
+  expect((await program.account.lockboxPosition.fetch(pdaLockboxPosition)).version).toEqual(1);
+  expect((await program.account.positionIndex.fetch(pdaPositionIndex)).numActive).toEqual(1);
+
+  console.log("Successfully tested the state migration");
+}
+
+main();
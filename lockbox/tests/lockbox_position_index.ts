@@ -0,0 +1,56 @@
+import * as anchor from "@coral-xyz/anchor";
+import expect from "expect";
+import { setupLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test checks the index of active positions, and must be run on a freshly started validator.sh right after
+// the initialization script
+async function main() {
+  const { program, pdaPositionIndex, openAndDeposit, withdraw } = await setupLockbox();
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing two positions of different sizes");
+
+  const position1 = await openAndDeposit("10" /* olas */);
+  const position2 = await openAndDeposit("20" /* olas */);
+
+  // Both positions are active, and the smallest one is the next to drain
+  let positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(2);
+  let nextToDrain = positionIndexData.entries[positionIndexData.nextToDrain];
+  expect(nextToDrain.id).toEqual(position1.id);
+  expect(nextToDrain.liquidity.toString()).toEqual(position1.liquidity.toString());
+
+  // ############################## PARTIAL WITHDRAW ##############################
+  console.log("\nWithdrawing a part of the biggest position");
+
+  // Leave the second position with half of the first position liquidity
+  const remainder = position1.liquidity.div(new anchor.BN(2));
+  await withdraw(position2, position2.liquidity.sub(remainder));
+
+  // The partially withdrawn position stays active and becomes the next to drain
+  positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(2);
+  nextToDrain = positionIndexData.entries[positionIndexData.nextToDrain];
+  expect(nextToDrain.id).toEqual(position2.id);
+  expect(nextToDrain.liquidity.toString()).toEqual(remainder.toString());
+
+  // ############################## FULL WITHDRAW ##############################
+  console.log("\nWithdrawing the rest of the partially withdrawn position");
+
+  await withdraw(position2, remainder);
+
+  // The drained position is removed from the index, and the remaining one is the next to drain
+  positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(1);
+  nextToDrain = positionIndexData.entries[positionIndexData.nextToDrain];
+  expect(nextToDrain.id).toEqual(position1.id);
+  expect(nextToDrain.liquidity.toString()).toEqual(position1.liquidity.toString());
+
+  console.log("Successfully tested the index of active positions");
+}
+
+main();
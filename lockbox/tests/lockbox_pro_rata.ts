@@ -0,0 +1,160 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getAccount, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import expect from "expect";
+import {
+  confirm, setupLockbox, orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test drains the lockbox, and must be run on a freshly started validator.sh right after the initialization script
+async function main() {
+  const {
+    provider, program, client, userWallet, pdaProgram, pdaPositionIndex, bridgedTokenMint, tokenOwnerAccountA, tokenOwnerAccountB,
+    bridgedTokenAccount, openAndDeposit, positionAccounts
+  } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  async function withdrawProRata(amount: anchor.BN, remainingAccounts, numMins: number) {
+    const mins = new Array(numMins).fill(new anchor.BN(0));
+    const signature = await program.methods.withdrawProRata(amount, mins, mins)
+      .accounts(
+          {
+            whirlpool: whirlpool,
+            bridgedTokenMint: bridgedTokenMint,
+            bridgedTokenAccount: bridgedTokenAccount.address,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+            feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: tickArrayLower,
+            tickArrayUpper: tickArrayUpper,
+            lockbox: pdaProgram,
+            positionIndex: pdaPositionIndex,
+            whirlpoolProgram: orca,
+            tokenProgram: TOKEN_PROGRAM_ID
+          }
+      )
+      .remainingAccounts(remainingAccounts)
+      .preInstructions([anchor.web3.ComputeBudgetProgram.setComputeUnitLimit({ units: 1000000 })])
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing two positions of different sizes");
+
+  const position1 = await openAndDeposit("10" /* olas */);
+  const position2 = await openAndDeposit("30" /* olas */);
+
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  let totalLiquidity = lockboxStateData.totalLiquidity;
+  expect(totalLiquidity.toString()).toEqual(position1.liquidity.add(position2.liquidity).toString());
+
+  // ############################## WRONG POSITIONS ##############################
+  console.log("\nRejecting wrong pro-rata withdrawals");
+
+  const halfAmount = totalLiquidity.div(new anchor.BN(2));
+
+  // Try to withdraw without supplying any position
+  try {
+      await withdrawProRata(halfAmount, [], 0);
+      throw new Error("Withdraw pro-rata must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongNumberOfPositions");
+  }
+
+  // Try to withdraw with a number of minimum amounts different from the number of positions
+  try {
+      await withdrawProRata(halfAmount, positionAccounts(position1), 2);
+      throw new Error("Withdraw pro-rata must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongNumberOfPositions");
+  }
+
+  // Try to supply the same position twice
+  try {
+      await withdrawProRata(halfAmount, [...positionAccounts(position1), ...positionAccounts(position1)], 2);
+      throw new Error("Withdraw pro-rata must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPositionId");
+  }
+
+  // Try to supply a depositor other than the recorded one
+  try {
+      await withdrawProRata(halfAmount, positionAccounts(position1, anchor.web3.Keypair.generate().publicKey), 1);
+      throw new Error("Withdraw pro-rata must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongDepositor");
+  }
+
+  // Try to withdraw more than the total liquidity
+  try {
+      await withdrawProRata(totalLiquidity.add(new anchor.BN(1)), positionAccounts(position1), 1);
+      throw new Error("Withdraw pro-rata must fail");
+  } catch (error) {
+      expect(error.message).toContain("AmountExceedsTotalLiquidity");
+  }
+
+  // Try to withdraw from a subset of the active positions
+  try {
+      await withdrawProRata(halfAmount, positionAccounts(position1), 1);
+      throw new Error("Withdraw pro-rata must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongNumberOfPositions");
+  }
+
+  // ############################## PARTIAL WITHDRAW ##############################
+  console.log("\nWithdrawing pro-rata a part of the total liquidity");
+
+  // Each position gives up its share of the amount, and the sum of the shares is burned
+  const share1 = halfAmount.mul(position1.liquidity).div(totalLiquidity);
+  const share2 = halfAmount.mul(position2.liquidity).div(totalLiquidity);
+  let bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  await withdrawProRata(halfAmount, [...positionAccounts(position1), ...positionAccounts(position2)], 2);
+
+  let bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalanceBefore - bridgedBalance).toString()).toEqual(share1.add(share2).toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.sub(share1).sub(share2).toString());
+
+  // Both positions are decreased by their shares
+  let positionData = await program.account.lockboxPosition.fetch(position1.pdaLockboxPosition);
+  expect(positionData.positionLiquidity.toString()).toEqual(position1.liquidity.sub(share1).toString());
+  expect((await client.getPosition(position1.position, true)).getData().liquidity.toString())
+    .toEqual(position1.liquidity.sub(share1).toString());
+  positionData = await program.account.lockboxPosition.fetch(position2.pdaLockboxPosition);
+  expect(positionData.positionLiquidity.toString()).toEqual(position2.liquidity.sub(share2).toString());
+  expect((await client.getPosition(position2.position, true)).getData().liquidity.toString())
+    .toEqual(position2.liquidity.sub(share2).toString());
+
+  // ############################## FULL WITHDRAW ##############################
+  console.log("\nWithdrawing the whole liquidity pro-rata");
+
+  // Withdrawing the total liquidity from all the positions drains each of them
+  totalLiquidity = lockboxStateData.totalLiquidity;
+  bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  await withdrawProRata(totalLiquidity, [...positionAccounts(position1), ...positionAccounts(position2)], 2);
+
+  bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalanceBefore - bridgedBalance).toString()).toEqual(totalLiquidity.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual("0");
+
+  // Drained positions are closed and removed from the index of active positions
+  expect(await provider.connection.getAccountInfo(position1.pdaLockboxPosition)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position2.pdaLockboxPosition)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position1.position)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position2.position)).toBeNull();
+  const positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(0);
+
+  console.log("Successfully tested pro-rata withdrawals");
+}
+
+main();
@@ -0,0 +1,181 @@
+import * as anchor from "@coral-xyz/anchor";
+import {
+  getOrCreateAssociatedTokenAccount, getAssociatedTokenAddress, getAccount, transfer, TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
+import expect from "expect";
+import {
+  confirm, setupLockbox, orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test drains the lockbox, and must be run on a freshly started validator.sh right after the initialization script
+async function main() {
+  const {
+    provider, program, client, userWallet, pdaProgram, pdaPositionIndex, bridgedTokenMint, bridgedTokenAccount, openAndDeposit
+  } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  async function redeemPosition(lockboxPosition, signer = userWallet, id = lockboxPosition.id, depositor = userWallet.publicKey) {
+    // The position NFT ATA of the signer is closed on deposit and needs to be recreated
+    const positionTokenAccount = await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        userWallet,
+        lockboxPosition.positionMint,
+        signer.publicKey
+    );
+    const signerBridgedTokenAccount = await getAssociatedTokenAddress(bridgedTokenMint, signer.publicKey);
+
+    const signature = await program.methods.redeemPosition(id)
+      .accounts(
+          {
+            whirlpool: whirlpool,
+            signer: signer.publicKey,
+            bridgedTokenMint: bridgedTokenMint,
+            bridgedTokenAccount: signerBridgedTokenAccount,
+            position: lockboxPosition.position,
+            pdaPositionAccount: lockboxPosition.pdaPositionAccount,
+            positionMint: lockboxPosition.positionMint,
+            positionTokenAccount: positionTokenAccount.address,
+            pdaLockboxPosition: lockboxPosition.pdaLockboxPosition,
+            depositor: depositor,
+            feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+            feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: tickArrayLower,
+            tickArrayUpper: tickArrayUpper,
+            lockbox: pdaProgram,
+            positionIndex: pdaPositionIndex,
+            whirlpoolProgram: orca,
+            tokenProgram: TOKEN_PROGRAM_ID
+          }
+      )
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+
+    return positionTokenAccount.address;
+  }
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing two positions");
+
+  const position1 = await openAndDeposit("10" /* olas */);
+  const position2 = await openAndDeposit("20" /* olas */);
+
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+
+  // ############################## WRONG REDEMPTIONS ##############################
+  console.log("\nRejecting wrong redemptions");
+
+  // Try to redeem a position ID that does not exist
+  try {
+      await redeemPosition(position1, userWallet, lockboxStateData.numPositions);
+      throw new Error("Redeem position must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPositionId");
+  }
+
+  // Try to redeem with a position ID that does not match the lockbox position
+  try {
+      await redeemPosition(position1, userWallet, position2.id);
+      throw new Error("Redeem position must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPDAPositionAccount");
+  }
+
+  // Try to send the closed accounts rent to someone else than the original depositor
+  try {
+      await redeemPosition(position1, userWallet, position1.id, anchor.web3.Keypair.generate().publicKey);
+      throw new Error("Redeem position must fail");
+  } catch (error) {
+      expect(error.message).toContain("ConstraintAddress");
+  }
+
+  // Find the user state PDA of the depositor created on the deposit
+  const [pdaUserState] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("user_state", "utf-8"), pdaProgram.toBuffer(), userWallet.publicKey.toBuffer()], program.programId
+  );
+  const totalWithdrawn = (await program.account.userState.fetch(pdaUserState)).totalWithdrawn;
+
+  // ############################## REDEEM ##############################
+  console.log("\nRedeeming a position NFT by the depositor");
+
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  let positionTokenAccount = await redeemPosition(position2);
+
+  // The whole position liquidity is burned from the bridged tokens, and the untouched position NFT is sent to the signer
+  const bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalanceBefore - bridgedBalance).toString()).toEqual(position2.liquidity.toString());
+  expect((await getAccount(provider.connection, positionTokenAccount)).amount.toString()).toEqual("1");
+  expect((await client.getPosition(position2.position, true)).getData().liquidity.toString())
+    .toEqual(position2.liquidity.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.sub(position2.liquidity).toString());
+
+  // The lockbox position and its PDA ATA are closed, and the position is removed from the index of active positions
+  expect(await provider.connection.getAccountInfo(position2.pdaLockboxPosition)).toBeNull();
+  expect(await provider.connection.getAccountInfo(position2.pdaPositionAccount)).toBeNull();
+  let positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(1);
+
+  // The redeemed liquidity is recorded in the depositor user state
+  const userStateData = await program.account.userState.fetch(pdaUserState);
+  expect(userStateData.totalWithdrawn.toString()).toEqual(totalWithdrawn.add(position2.liquidity).toString());
+
+  // ############################## REDEEM BY ANOTHER HOLDER ##############################
+  console.log("\nRedeeming a position NFT by another bridged token holder");
+
+  // Any holder of enough bridged tokens can redeem a position, while the closed accounts rent goes to the depositor
+  const redeemer = anchor.web3.Keypair.generate();
+  const redeemerBridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      redeemer.publicKey
+  );
+
+  // Try to redeem without enough bridged tokens
+  try {
+      await redeemPosition(position1, redeemer);
+      throw new Error("Redeem position must fail");
+  } catch (error) {
+      expect(error.message).not.toContain("must fail");
+  }
+
+  await transfer(
+      provider.connection,
+      userWallet,
+      bridgedTokenAccount.address,
+      redeemerBridgedTokenAccount.address,
+      userWallet,
+      BigInt(position1.liquidity.toString())
+  );
+
+  const depositorLamportsBefore = await provider.connection.getBalance(userWallet.publicKey);
+  positionTokenAccount = await redeemPosition(position1, redeemer);
+  expect((await getAccount(provider.connection, positionTokenAccount)).amount.toString()).toEqual("1");
+  expect((await getAccount(provider.connection, redeemerBridgedTokenAccount.address)).amount.toString()).toEqual("0");
+
+  // The depositor gets the rent of the lockbox position and its PDA ATA, that exceeds the paid transaction fees
+  expect(await provider.connection.getBalance(userWallet.publicKey)).toBeGreaterThan(depositorLamportsBefore);
+
+  // The redeemer has never deposited, and the redemption does not create a user state for it
+  const [pdaRedeemerUserState] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("user_state", "utf-8"), pdaProgram.toBuffer(), redeemer.publicKey.toBuffer()], program.programId
+  );
+  expect(await provider.connection.getAccountInfo(pdaRedeemerUserState)).toBeNull();
+
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual("0");
+  positionIndexData = await program.account.positionIndex.fetch(pdaPositionIndex);
+  expect(positionIndexData.numActive).toEqual(0);
+
+  console.log("Successfully tested position redemptions");
+}
+
+main();
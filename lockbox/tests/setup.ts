@@ -0,0 +1,187 @@
+import * as idl from "../target/idl/liquidity_lockbox.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { LiquidityLockbox } from "../target/types/liquidity_lockbox";
+import { getOrCreateAssociatedTokenAccount, getAssociatedTokenAddress, syncNative, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import {
+  WhirlpoolContext, buildWhirlpoolClient, increaseLiquidityQuoteByInputTokenWithParams, TickUtil, PDAUtil
+} from "@orca-so/whirlpools-sdk";
+import { DecimalUtil, Percentage } from "@orca-so/common-sdk";
+import Decimal from "decimal.js";
+
+// Shared localnet setup of the lockbox test scripts, the accounts are the ones loaded by validator.sh
+
+export const PROGRAM_ID = new anchor.web3.PublicKey("7ahQGWysExobjeZ91RTsNqTCN3kWyHGZ43ud2vB7VVoZ");
+export const BPF_LOADER_UPGRADEABLE = new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+export const orca = new anchor.web3.PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+export const whirlpool = new anchor.web3.PublicKey("5dMKUYJDsjZkAD3wiV3ViQkuq9pSmWQ5eAzcQLtDnUT3");
+export const olas = new anchor.web3.PublicKey("Ez3nzG9ofodYCvEmw73XhQ87LWNYVRM2s7diB5tBZPyM");
+export const tokenVaultA = new anchor.web3.PublicKey("CLA8hU8SkdCZ9cJVLMfZQfcgAsywZ9txBJ6qrRAqthLx");
+export const tokenVaultB = new anchor.web3.PublicKey("6E8pzDK8uwpENc49kp5xo5EGydYjtamPSmUKXxum4ybb");
+export const tickArrayLower = new anchor.web3.PublicKey("3oJAqTKTCdGvLS9zpoBquWvMjwthu9Np67Qp4W8AT843");
+export const tickArrayUpper = new anchor.web3.PublicKey("J3eMJUQWLmSsG5VnXVFHCGwakpKmzi4jkNvi3vbCZQ3o");
+
+// Waits for the transaction to be confirmed
+export async function confirm(provider: anchor.AnchorProvider, signature: string) {
+  await provider.connection.confirmTransaction({
+      signature: signature,
+      ...(await provider.connection.getLatestBlockhash()),
+  });
+}
+
+// Gets the initialized lockbox along with the user token accounts and the deposit helpers,
+// the lockbox must be initialized with tests/lockbox_init.ts
+export async function setupLockbox() {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = new Program(idl as anchor.Idl, PROGRAM_ID, anchor.getProvider()) as Program<LiquidityLockbox>;
+  const [programData] = await anchor.web3.PublicKey.findProgramAddress([PROGRAM_ID.toBuffer()], BPF_LOADER_UPGRADEABLE);
+
+  // User wallet is the provider payer and the program upgrade authority
+  const userWallet = provider.wallet["payer"];
+  console.log("User wallet:", userWallet.publicKey.toBase58());
+
+  const ctx = WhirlpoolContext.withProvider(provider, orca);
+  const client = buildWhirlpoolClient(ctx);
+  const whirlpoolClient = await client.getPool(whirlpool);
+  const whirlpool_data = whirlpoolClient.getData();
+  const token_a = whirlpoolClient.getTokenAInfo();
+  const token_b = whirlpoolClient.getTokenBInfo();
+  const [lower_tick_index, upper_tick_index] = TickUtil.getFullRangeTickIndex(whirlpool_data.tickSpacing);
+
+  // Find PDA accounts for the program and the index of active positions
+  const [pdaProgram] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("liquidity_lockbox", "utf-8")], program.programId);
+  const [pdaPositionIndex] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("position_index", "utf-8")], program.programId);
+
+  // Get the lockbox state data
+  const lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const bridgedTokenMint = lockboxStateData.bridgedTokenMint;
+
+  // Get the user token ATAs
+  const tokenOwnerAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      userWallet.publicKey
+  );
+  await provider.connection.requestAirdrop(tokenOwnerAccountA.address, 100000000000);
+  await syncNative(provider.connection, userWallet, tokenOwnerAccountA.address);
+  const tokenOwnerAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      userWallet.publicKey
+  );
+  const bridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      userWallet.publicKey
+  );
+
+  // Opens a full range position with the provided amount of OLAS and deposits it into the lockbox
+  async function openAndDeposit(olasAmount: string, proof = []) {
+    const quote = increaseLiquidityQuoteByInputTokenWithParams({
+      tokenMintA: token_a.mint,
+      tokenMintB: token_b.mint,
+      sqrtPrice: whirlpool_data.sqrtPrice,
+      tickCurrentIndex: whirlpool_data.tickCurrentIndex,
+      tickLowerIndex: lower_tick_index,
+      tickUpperIndex: upper_tick_index,
+      inputTokenMint: olas,
+      inputTokenAmount: DecimalUtil.toBN(new Decimal(olasAmount), 8),
+      slippageTolerance: Percentage.fromFraction(10, 1000),
+    });
+    const openPositionTx = await whirlpoolClient.openPosition(lower_tick_index, upper_tick_index, quote);
+    await confirm(provider, await openPositionTx.tx.buildAndExecute());
+
+    const positionMint = openPositionTx.positionMint;
+    const position = PDAUtil.getPosition(orca, positionMint).publicKey;
+    const positionTokenAccount = await getAssociatedTokenAddress(positionMint, userWallet.publicKey);
+    const pdaPositionAccount = await getAssociatedTokenAddress(positionMint, pdaProgram, true);
+
+    const id = (await program.account.liquidityLockbox.fetch(pdaProgram)).numPositions;
+    const idBytes = Buffer.allocUnsafe(4);
+    idBytes.writeInt32BE(id);
+    const [pdaLockboxPosition] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("lockbox_position", "utf-8"), idBytes], program.programId
+    );
+
+    const signature = await program.methods.deposit(id, proof)
+      .accounts(
+          {
+            lockbox: pdaProgram,
+            positionTokenAccount: positionTokenAccount,
+            pdaPositionAccount: pdaPositionAccount,
+            positionMint: positionMint,
+            pdaLockboxPosition: pdaLockboxPosition,
+            bridgedTokenAccount: bridgedTokenAccount.address,
+            bridgedTokenMint: bridgedTokenMint,
+            position: position
+          }
+      )
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+
+    const liquidity = (await program.account.lockboxPosition.fetch(pdaLockboxPosition)).positionLiquidity;
+    return { id, position, positionMint, pdaPositionAccount, pdaLockboxPosition, liquidity };
+  }
+
+  // Withdraws the provided amount of liquidity from the lockbox position with no token minimums
+  async function withdraw(lockboxPosition, amount: anchor.BN) {
+    const state = await program.account.liquidityLockbox.fetch(pdaProgram);
+    const signature = await program.methods.withdraw(lockboxPosition.id, amount, new anchor.BN(0), new anchor.BN(0))
+      .accounts(
+          {
+            lockbox: pdaProgram,
+            whirlpoolProgram: orca,
+            whirlpool: whirlpool,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            position: lockboxPosition.position,
+            positionMint: lockboxPosition.positionMint,
+            pdaLockboxPosition: lockboxPosition.pdaLockboxPosition,
+            depositor: userWallet.publicKey,
+            bridgedTokenAccount: bridgedTokenAccount.address,
+            bridgedTokenMint: bridgedTokenMint,
+            pdaPositionAccount: lockboxPosition.pdaPositionAccount,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            feeCollectorTokenOwnerAccountA: state.feeCollectorTokenOwnerAccountA,
+            feeCollectorTokenOwnerAccountB: state.feeCollectorTokenOwnerAccountB,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: tickArrayLower,
+            tickArrayUpper: tickArrayUpper,
+            positionIndex: pdaPositionIndex
+          }
+      )
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // Remaining accounts of a lockbox position in the order expected by the program
+  function positionAccounts(lockboxPosition, depositor = userWallet.publicKey) {
+    return [
+      { pubkey: lockboxPosition.pdaLockboxPosition, isSigner: false, isWritable: true },
+      { pubkey: lockboxPosition.position, isSigner: false, isWritable: true },
+      { pubkey: lockboxPosition.pdaPositionAccount, isSigner: false, isWritable: true },
+      { pubkey: lockboxPosition.positionMint, isSigner: false, isWritable: true },
+      { pubkey: depositor, isSigner: false, isWritable: true }
+    ];
+  }
+
+  // Accounts of the admin instructions gated by the program upgrade authority
+  function adminAccounts(signer = userWallet) {
+    return { signer: signer.publicKey, program: PROGRAM_ID, programData, lockbox: pdaProgram };
+  }
+
+  return {
+    provider, program, programData, userWallet, ctx, client, whirlpoolClient, whirlpool_data, token_a, token_b,
+    lower_tick_index, upper_tick_index, pdaProgram, pdaPositionIndex, lockboxStateData, bridgedTokenMint,
+    tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, openAndDeposit, withdraw, positionAccounts, adminAccounts
+  };
+}
@@ -1,18 +1,34 @@
 pub mod state;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Approve};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Approve, Transfer, CloseAccount};
+use anchor_spl::associated_token::AssociatedToken;
 use whirlpool::{
   self,
-  state::{Whirlpool, TickArray, Position},
+  state::{Whirlpool, TickArray, Position, OpenPositionBumps},
   cpi::accounts::ModifyLiquidity,
   cpi::accounts::UpdateFeesAndRewards,
-  cpi::accounts::CollectFees
+  cpi::accounts::CollectFees,
+  cpi::accounts::OpenPosition,
+  cpi::accounts::ClosePosition,
+  cpi::accounts::Swap
+};
+use solana_program::{
+  pubkey::Pubkey,
+  program::{invoke, invoke_signed},
+  program_option::COption,
+  instruction::{AccountMeta, Instruction},
+  system_instruction,
+  hash::hash
 };
-use solana_program::{pubkey::Pubkey, program::invoke_signed};
 use spl_token::instruction::{burn_checked, mint_to};
 pub use state::*;
 
-declare_id!("7ahQGWysExobjeZ91RTsNqTCN3kWyHGZ43ud2vB7VVoZ");
+declare_id!("1BoXeb8hobfLCHNsyCoG1jpEv41ez4w4eDrJ48N1jY3");
+
+// Whirlpool min and max sqrt prices in the Q64.64 format
+const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+const MAX_SQRT_PRICE_X64: u128 = 79226673515401279992447579055;
 
 #[program]
 pub mod liquidity_lockbox {
@@ -21,19 +37,32 @@ pub mod liquidity_lockbox {
 
   // Orca Whirlpool program address
   const ORCA: Pubkey = pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
-  // OLAS-SOL Whirlpool address
-  const WHIRLPOOL: Pubkey = pubkey!("5dMKUYJDsjZkAD3wiV3ViQkuq9pSmWQ5eAzcQLtDnUT3");
-  // SOL address
-  const SOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
-  // OLAS address
-  const OLAS: Pubkey = pubkey!("Ez3nzG9ofodYCvEmw73XhQ87LWNYVRM2s7diB5tBZPyM");
+  // Lockbox v1 program address
+  const LOCKBOX_V1: Pubkey = pubkey!("7ahQGWysExobjeZ91RTsNqTCN3kWyHGZ43ud2vB7VVoZ");
+  // Lockbox v1 state layout version
+  const LOCKBOX_V1_VERSION: u8 = 1;
   // Position account discriminator
   const POSITION_HEADER: [u8; 8] = [0xaa, 0xbc, 0x8f, 0xe4, 0x7a, 0x40, 0xf7, 0xd0];
-  // Full range lower and upper indexes
-  const TICK_LOWER_INDEX: i32 = -443584;
-  const TICK_UPPER_INDEX: i32 = 443584;
+  // Whirlpool min and max tick indexes
+  const MIN_TICK_INDEX: i32 = -443636;
+  const MAX_TICK_INDEX: i32 = 443636;
   // Bridged token decimals
   const BRIDGED_TOKEN_DECIMALS: u8 = 8;
+  // Wormhole token bridge transfer native instruction index
+  const TOKEN_BRIDGE_TRANSFER_NATIVE: u8 = 5;
+  // Wormhole token bridge complete native instruction index
+  const TOKEN_BRIDGE_COMPLETE_NATIVE: u8 = 2;
+  // Posted VAA sequence, emitter chain, emitter address and payload offsets
+  const POSTED_VAA_SEQUENCE_OFFSET: usize = 49;
+  const POSTED_VAA_EMITTER_CHAIN_OFFSET: usize = 57;
+  const POSTED_VAA_EMITTER_ADDRESS_OFFSET: usize = 59;
+  const POSTED_VAA_PAYLOAD_OFFSET: usize = 91;
+  // Wormhole core bridge post message instruction index
+  const CORE_BRIDGE_POST_MESSAGE: u8 = 1;
+  // Wormhole finalized consistency level
+  const CONSISTENCY_LEVEL_FINALIZED: u8 = 1;
+  // Reserves message payload identifier
+  const RESERVES_PAYLOAD_ID: u8 = 1;
 
 
   /// Initializes a Lockbox account that stores state data.
@@ -46,8 +75,8 @@ pub mod liquidity_lockbox {
     let tick_lower_index = ctx.accounts.position.tick_lower_index;
     let tick_upper_index = ctx.accounts.position.tick_upper_index;
 
-    // Check the whirlpool
-    if whirlpool != WHIRLPOOL {
+    // Check that the position belongs to the lockbox whirlpool
+    if whirlpool != ctx.accounts.whirlpool.key() {
         return Err(ErrorCode::WrongWhirlpool.into());
     }
 
@@ -70,9 +99,12 @@ pub mod liquidity_lockbox {
       return Err(ErrorCode::LiquidityNotZero.into());
     }
 
-    // Check tick values
-    if tick_lower_index != TICK_LOWER_INDEX || tick_upper_index != TICK_UPPER_INDEX {
-      return Err(ErrorCode::OutOfRange.into());
+    // Check that the tick range is valid and aligned with the whirlpool tick spacing
+    let tick_spacing = ctx.accounts.whirlpool.tick_spacing as i32;
+    if tick_spacing == 0 || tick_lower_index >= tick_upper_index ||
+      tick_lower_index < MIN_TICK_INDEX || tick_upper_index > MAX_TICK_INDEX ||
+      tick_lower_index % tick_spacing != 0 || tick_upper_index % tick_spacing != 0 {
+      return Err(ErrorCode::WrongTickRange.into());
     }
 
     // Check the PDA ownership
@@ -86,13 +118,33 @@ pub mod liquidity_lockbox {
       return Err(ErrorCode::WrongPositionPDA.into());
     }
 
-    // Check that the first token mint is SOL
-    if ctx.accounts.fee_collector_token_owner_account_a.mint != SOL {
+    // Check the bridged token mint authority
+    if ctx.accounts.bridged_token_mint.mint_authority != COption::Some(ctx.accounts.lockbox.key()) {
+      return Err(ErrorCode::WrongMintAuthority.into());
+    }
+
+    // Check that the bridged token mint has no freeze authority
+    if ctx.accounts.bridged_token_mint.freeze_authority.is_some() {
+      return Err(ErrorCode::FreezeAuthoritySet.into());
+    }
+
+    // Check the bridged token decimals
+    if ctx.accounts.bridged_token_mint.decimals != BRIDGED_TOKEN_DECIMALS {
+      return Err(ErrorCode::WrongMintDecimals.into());
+    }
+
+    // Check that no bridged tokens are minted yet
+    if ctx.accounts.bridged_token_mint.supply != 0 {
+      return Err(ErrorCode::BridgedSupplyNotZero.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    if ctx.accounts.fee_collector_token_owner_account_a.mint != ctx.accounts.whirlpool.token_mint_a {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
-    // Check that the second token mint is OLAS
-    if ctx.accounts.fee_collector_token_owner_account_b.mint != OLAS {
+    // Check that the second token mint is the whirlpool token B
+    if ctx.accounts.fee_collector_token_owner_account_b.mint != ctx.accounts.whirlpool.token_mint_b {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
@@ -109,22 +161,25 @@ pub mod liquidity_lockbox {
       ctx.accounts.fee_collector_token_owner_account_a.key(),
       ctx.accounts.fee_collector_token_owner_account_b.key(),
       ctx.accounts.position.key(),
-      ctx.accounts.pda_position_account.key()
+      ctx.accounts.pda_position_account.key(),
+      ctx.accounts.whirlpool.key()
     )?;
 
     Ok(())
   }
 
-  /// Deposits SOL and OLAS tokens to be added to the Lockbox position and gets bridged tokens minted in return.
+  /// Deposits whirlpool A and B tokens to be added to the Lockbox position and gets bridged tokens minted in return.
   ///
   /// ### Parameters
   /// - `liquidity_amount` - Requested liquidity amount.
-  /// - `token_max_a` - Max amount of SOL token to be added for liquidity.
-  /// - `token_max_b` - Max amount of OLAS token to be added for liquidity.
+  /// - `token_max_a` - Max amount of token A to be added for liquidity.
+  /// - `token_max_b` - Max amount of token B to be added for liquidity.
+  /// - `proof` - Allowlist Merkle proof of the signer, empty if the allowlist mode is disabled.
   pub fn deposit(ctx: Context<DepositPositionForLiquidity>,
     liquidity_amount: u64,
     token_max_a: u64,
     token_max_b: u64,
+    proof: Vec<[u8; 32]>
   ) -> Result<()> {
     // Check the initial token amounts
     if token_max_a == 0 || token_max_b == 0 {
@@ -148,8 +203,27 @@ pub mod liquidity_lockbox {
       return Err(ErrorCode::WrongLockboxPDA.into());
     }
 
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the lockbox is not in the emergency mode
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Check the deposit limits
+    ctx.accounts.lockbox.check_deposit_limits(liquidity_amount)?;
+
+    // Check that the signer is allowlisted
+    ctx.accounts.lockbox.check_allowlist(&ctx.accounts.signer.key(), &proof)?;
+
     // Check the whirlpool
-    if ctx.accounts.whirlpool.key() != WHIRLPOOL {
+    if ctx.accounts.whirlpool.key() != ctx.accounts.lockbox.whirlpool {
         return Err(ErrorCode::WrongWhirlpool.into());
     }
 
@@ -158,13 +232,15 @@ pub mod liquidity_lockbox {
         return Err(ErrorCode::WrongOrcaAccount.into());
     }
 
-    // Check that the first token mint is SOL
-    if ctx.accounts.token_owner_account_a.mint != SOL || ctx.accounts.token_vault_a.mint != SOL {
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
-    // Check that the second token mint is OLAS
-    if ctx.accounts.token_owner_account_b.mint != OLAS || ctx.accounts.token_vault_b.mint != OLAS {
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
@@ -186,101 +262,35 @@ pub mod liquidity_lockbox {
         None => return Err(ErrorCode::LiquidityOverflow.into()),
       };
 
-    // Approve SOL tokens for the lockbox
-    token::approve(
-      CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Approve {
-          to: ctx.accounts.token_owner_account_a.to_account_info(),
-          delegate: ctx.accounts.lockbox.to_account_info(),
-          authority: ctx.accounts.signer.to_account_info(),
-        },
-      ),
-      token_max_a,
-    )?;
-
-    // Approve OLAS tokens for the lockbox
-    token::approve(
-      CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Approve {
-          to: ctx.accounts.token_owner_account_b.to_account_info(),
-          delegate: ctx.accounts.lockbox.to_account_info(),
-          authority: ctx.accounts.signer.to_account_info(),
-        },
-      ),
-      token_max_b,
-    )?;
-
-    // Get lockbox signer seeds
-    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
-
-    // CPI call to increase liquidity
-    let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
-    let cpi_accounts_modify_liquidity = ModifyLiquidity {
-      whirlpool: ctx.accounts.whirlpool.to_account_info(),
-      position: ctx.accounts.position.to_account_info(),
-      position_authority: ctx.accounts.lockbox.to_account_info(),
-      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
-      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
-      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
-      token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
-      token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
-      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
-      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
-      token_program: ctx.accounts.token_program.to_account_info(),
-    };
-
-    let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
-      cpi_program_modify_liquidity,
-      cpi_accounts_modify_liquidity,
-      signer_seeds
-    );
-    whirlpool::cpi::increase_liquidity(cpi_ctx_modify_liquidity, liquidity_amount as u128, token_max_a, token_max_b)?;
-
-    // Mint bridged tokens in the amount of position liquidity
-    invoke_signed(
-      &mint_to(
-        ctx.accounts.token_program.key,
-        ctx.accounts.bridged_token_mint.to_account_info().key,
-        ctx.accounts.bridged_token_account.to_account_info().key,
-        ctx.accounts.lockbox.to_account_info().key,
-        &[ctx.accounts.lockbox.to_account_info().key],
-        liquidity_amount,
-      )?,
-      &[
-        ctx.accounts.bridged_token_mint.to_account_info(),
-        ctx.accounts.bridged_token_account.to_account_info(),
-        ctx.accounts.lockbox.to_account_info(),
-        ctx.accounts.token_program.to_account_info(),
-      ],
-      &[&ctx.accounts.lockbox.seeds()],
-    )?;
-
-    // Revoke approval for unused SOL tokens
-    token::approve(
-      CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Approve {
-          to: ctx.accounts.token_owner_account_a.to_account_info(),
-          delegate: ctx.accounts.lockbox.to_account_info(),
-          authority: ctx.accounts.signer.to_account_info(),
-        },
-      ),
-      0,
-    )?;
+    // Record the deposit in the user state and check it against the user cap
+    let lockbox = ctx.accounts.lockbox.key();
+    let user = ctx.accounts.signer.key();
+    ctx.accounts.user_state.initialize_if_needed(*ctx.bumps.get("user_state").unwrap(), lockbox, user)?;
+    ctx.accounts.user_state.record_deposit(liquidity_amount, ctx.accounts.lockbox.max_user_deposit)?;
 
-    // Revoke approval for OLAS tokens
-    token::approve(
-      CpiContext::new(
-        ctx.accounts.token_program.to_account_info(),
-        Approve {
-          to: ctx.accounts.token_owner_account_b.to_account_info(),
-          delegate: ctx.accounts.lockbox.to_account_info(),
-          authority: ctx.accounts.signer.to_account_info(),
-        },
-      ),
-      0,
+    // Increase the lockbox position liquidity and mint bridged tokens in the amount of position liquidity
+    increase_liquidity_and_mint(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+      },
+      ctx.accounts.signer.to_account_info(),
+      ctx.accounts.bridged_token_mint.to_account_info(),
+      ctx.accounts.bridged_token_account.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      liquidity_amount,
+      token_max_a,
+      token_max_b
     )?;
 
     emit!(DepositEvent {
@@ -293,26 +303,40 @@ pub mod liquidity_lockbox {
     Ok(())
   }
 
-  /// Withdraws a specified amount of liquidity for supplied bridged tokens.
+  /// Deposits whirlpool A and B tokens to be added to the Lockbox position and bridges the minted bridged tokens
+  /// to the target chain via the Wormhole token bridge.
   ///
   /// ### Parameters
-  /// - `amount` - Amount of bridged tokens corresponding to the position liquidity amount to withdraw.
-  /// - `token_min_a` - The minimum amount of SOL the signer is willing to withdraw.
-  /// - `token_min_b` - The minimum amount of OLAS the signer is willing to withdraw.
-  pub fn withdraw(
-    ctx: Context<WithdrawLiquidityForTokens>,
-    amount: u64,
-    token_min_a: u64,
-    token_min_b: u64
+  /// - `liquidity_amount` - Requested liquidity amount.
+  /// - `token_max_a` - Max amount of token A to be added for liquidity.
+  /// - `token_max_b` - Max amount of token B to be added for liquidity.
+  /// - `proof` - Allowlist Merkle proof of the signer, empty if the allowlist mode is disabled.
+  /// - `nonce` - Wormhole message nonce.
+  /// - `target_chain` - Wormhole target chain id.
+  /// - `target_address` - Recipient address on the target chain, left-padded to 32 bytes.
+  pub fn deposit_and_bridge(ctx: Context<DepositAndBridge>,
+    liquidity_amount: u64,
+    token_max_a: u64,
+    token_max_b: u64,
+    proof: Vec<[u8; 32]>,
+    nonce: u32,
+    target_chain: u16,
+    target_address: [u8; 32]
   ) -> Result<()> {
-    // Check if there is any liquidity left in the Lockbox
-    if ctx.accounts.position.liquidity == 0 {
+    // Check the initial token amounts
+    if token_max_a == 0 || token_max_b == 0 {
       return Err(ErrorCode::LiquidityZero.into());
     }
 
-    // Check the token amount
-    if amount == 0 {
-      return Err(ErrorCode::LiquidityZero.into());
+    // Check the position account
+    if ctx.accounts.position.key() != ctx.accounts.lockbox.position {
+      return Err(ErrorCode::WrongPositionPDA.into());
+    }
+
+    // Check the position PDA address correctness
+    let position_pda = Pubkey::find_program_address(&[b"position", ctx.accounts.position.position_mint.as_ref()], &ORCA);
+    if position_pda.0 != ctx.accounts.position.key() {
+      return Err(ErrorCode::WrongPositionPDA.into());
     }
 
     // Check the lockbox PDA address correctness
@@ -321,177 +345,3953 @@ pub mod liquidity_lockbox {
       return Err(ErrorCode::WrongLockboxPDA.into());
     }
 
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the lockbox is not in the emergency mode
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Check that the token bridge is configured
+    if ctx.accounts.lockbox.token_bridge == Pubkey::default() {
+      return Err(ErrorCode::BridgeNotConfigured.into());
+    }
+
+    // Check the deposit limits
+    ctx.accounts.lockbox.check_deposit_limits(liquidity_amount)?;
+
+    // Check that the signer is allowlisted
+    ctx.accounts.lockbox.check_allowlist(&ctx.accounts.signer.key(), &proof)?;
+
+    // Check the whirlpool
+    if ctx.accounts.whirlpool.key() != ctx.accounts.lockbox.whirlpool {
+        return Err(ErrorCode::WrongWhirlpool.into());
+    }
+
     // Check the Orca Whirlpool program address
     if ctx.accounts.whirlpool_program.key() != ORCA {
         return Err(ErrorCode::WrongOrcaAccount.into());
     }
 
-    // Check that the first token mint is SOL
-    if ctx.accounts.token_owner_account_a.mint != SOL || ctx.accounts.token_vault_a.mint != SOL {
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
-    // Check that the second token mint is OLAS
-    if ctx.accounts.token_owner_account_b.mint != OLAS || ctx.accounts.token_vault_b.mint != OLAS {
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
       return Err(ErrorCode::WrongTokenMint.into());
     }
 
-    // Check the requested amount to be smaller or equal than the position liquidity
-    if amount > ctx.accounts.position.liquidity as u64 {
-      return Err(ErrorCode::AmountExceedsPositionLiquidity.into());
+    // Calculate token deltas
+    let tick_index_lower = ctx.accounts.position.tick_lower_index;
+    let tick_index_upper = ctx.accounts.position.tick_upper_index;
+    let tick_index_current = ctx.accounts.whirlpool.tick_current_index;
+
+    // assuming InRange status
+    if tick_index_current < tick_index_lower || tick_index_upper <= tick_index_current {
+      return Err(ErrorCode::OutOfRange.into());
     }
 
-    // Burn provided amount of bridged tokens
-    invoke_signed(
-      &burn_checked(
-        ctx.accounts.token_program.key,
-        ctx.accounts.bridged_token_account.to_account_info().key,
-        ctx.accounts.bridged_token_mint.to_account_info().key,
-        ctx.accounts.signer.to_account_info().key,
-        &[],
-        amount,
-        BRIDGED_TOKEN_DECIMALS,
-      )?,
-      &[
-        ctx.accounts.token_program.to_account_info(),
-        ctx.accounts.bridged_token_account.to_account_info(),
-        ctx.accounts.bridged_token_mint.to_account_info(),
-        ctx.accounts.signer.to_account_info(),
-      ],
-      &[]
+    // Total liquidity update with the check
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_add(liquidity_amount) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityOverflow.into()),
+      };
+
+    // Record the deposit in the user state and check it against the user cap
+    let lockbox = ctx.accounts.lockbox.key();
+    let user = ctx.accounts.signer.key();
+    ctx.accounts.user_state.initialize_if_needed(*ctx.bumps.get("user_state").unwrap(), lockbox, user)?;
+    ctx.accounts.user_state.record_deposit(liquidity_amount, ctx.accounts.lockbox.max_user_deposit)?;
+
+    // Increase the lockbox position liquidity and mint bridged tokens into the lockbox escrow
+    increase_liquidity_and_mint(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+      },
+      ctx.accounts.signer.to_account_info(),
+      ctx.accounts.bridged_token_mint.to_account_info(),
+      ctx.accounts.pda_bridged_token_account.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      liquidity_amount,
+      token_max_a,
+      token_max_b
     )?;
 
-    // Get program signer seeds
+    // Get lockbox signer seeds
     let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
 
-    // Update fees for the position
-    let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
-    let cpi_accounts_update_fees = UpdateFeesAndRewards {
-      whirlpool: ctx.accounts.whirlpool.to_account_info(),
-      position: ctx.accounts.position.to_account_info(),
-      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
-      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
-    };
-
-    let cpi_ctx_update_fees = CpiContext::new_with_signer(
-      cpi_program_update_fees,
-      cpi_accounts_update_fees,
-      signer_seeds
-    );
-    whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+    // Approve the escrowed bridged tokens for the token bridge authority signer
+    token::approve(
+      CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Approve {
+          to: ctx.accounts.pda_bridged_token_account.to_account_info(),
+          delegate: ctx.accounts.token_bridge_authority_signer.to_account_info(),
+          authority: ctx.accounts.lockbox.to_account_info(),
+        },
+        signer_seeds
+      ),
+      liquidity_amount,
+    )?;
 
-    // Collect fees from the position
-    let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
-    let cpi_accounts_collect_fees = CollectFees {
-      whirlpool: ctx.accounts.whirlpool.to_account_info(),
-      position_authority: ctx.accounts.lockbox.to_account_info(),
-      position: ctx.accounts.position.to_account_info(),
-      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
-      token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
-      token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
-      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
-      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
-      token_program: ctx.accounts.token_program.to_account_info()
+    // Pay the core bridge message fee, stored after the guardian set index and last lamports in the bridge data
+    let message_fee = {
+      let data = ctx.accounts.wormhole_bridge.try_borrow_data()?;
+      if data.len() < 24 {
+        return Err(ErrorCode::WrongBridgeAccount.into());
+      }
+      u64::from_le_bytes(data[16..24].try_into().unwrap())
     };
+    if message_fee > 0 {
+      invoke(
+        &system_instruction::transfer(
+          ctx.accounts.signer.key,
+          ctx.accounts.wormhole_fee_collector.key,
+          message_fee
+        ),
+        &[
+          ctx.accounts.signer.to_account_info(),
+          ctx.accounts.wormhole_fee_collector.to_account_info(),
+          ctx.accounts.system_program.to_account_info(),
+        ]
+      )?;
+    }
 
-    let cpi_ctx_collect_fees = CpiContext::new_with_signer(
-      cpi_program_collect_fees,
-      cpi_accounts_collect_fees,
-      signer_seeds
-    );
-    whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+    // Transfer the escrowed bridged tokens to the target chain via the token bridge
+    let mut data = vec![TOKEN_BRIDGE_TRANSFER_NATIVE];
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&liquidity_amount.to_le_bytes());
+    // Zero relayer fee
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.extend_from_slice(&target_address);
+    data.extend_from_slice(&target_chain.to_le_bytes());
 
-    // CPI to decrease liquidity
-    let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
-    let cpi_accounts_modify_liquidity = ModifyLiquidity {
-      whirlpool: ctx.accounts.whirlpool.to_account_info(),
-      position: ctx.accounts.position.to_account_info(),
-      position_authority: ctx.accounts.lockbox.to_account_info(),
-      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
-      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
-      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
-      token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
-      token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
-      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
-      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
-      token_program: ctx.accounts.token_program.to_account_info()
+    let transfer_native = Instruction {
+      program_id: ctx.accounts.token_bridge_program.key(),
+      accounts: vec![
+        AccountMeta::new(ctx.accounts.signer.key(), true),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_config.key(), false),
+        AccountMeta::new(ctx.accounts.pda_bridged_token_account.key(), false),
+        AccountMeta::new(ctx.accounts.bridged_token_mint.key(), false),
+        AccountMeta::new(ctx.accounts.token_bridge_custody.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_authority_signer.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_custody_signer.key(), false),
+        AccountMeta::new(ctx.accounts.wormhole_bridge.key(), false),
+        AccountMeta::new(ctx.accounts.wormhole_message.key(), true),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_emitter.key(), false),
+        AccountMeta::new(ctx.accounts.token_bridge_sequence.key(), false),
+        AccountMeta::new(ctx.accounts.wormhole_fee_collector.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.clock.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.core_bridge_program.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+      ],
+      data
     };
 
-    let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
-      cpi_program_modify_liquidity,
-      cpi_accounts_modify_liquidity,
+    invoke_signed(
+      &transfer_native,
+      &[
+        ctx.accounts.signer.to_account_info(),
+        ctx.accounts.token_bridge_config.to_account_info(),
+        ctx.accounts.pda_bridged_token_account.to_account_info(),
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.token_bridge_custody.to_account_info(),
+        ctx.accounts.token_bridge_authority_signer.to_account_info(),
+        ctx.accounts.token_bridge_custody_signer.to_account_info(),
+        ctx.accounts.wormhole_bridge.to_account_info(),
+        ctx.accounts.wormhole_message.to_account_info(),
+        ctx.accounts.token_bridge_emitter.to_account_info(),
+        ctx.accounts.token_bridge_sequence.to_account_info(),
+        ctx.accounts.wormhole_fee_collector.to_account_info(),
+        ctx.accounts.clock.to_account_info(),
+        ctx.accounts.rent.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.core_bridge_program.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.token_bridge_program.to_account_info(),
+      ],
       signer_seeds
-    );
-    whirlpool::cpi::decrease_liquidity(cpi_ctx_modify_liquidity, amount as u128, token_min_a, token_min_b)?;
-
-    // Update the position liquidity
-    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
-      .total_liquidity
-      .checked_sub(amount) {
-        Some(new_liquidity) => new_liquidity,
-        None => return Err(ErrorCode::LiquidityUnderflow.into()),
-      };
+    )?;
 
-    emit!(WithdrawEvent {
+    emit!(DepositAndBridgeEvent {
       signer: ctx.accounts.signer.key(),
       position: ctx.accounts.position.key(),
-      token_owner_account_a: ctx.accounts.token_owner_account_a.key(),
-      token_owner_account_b: ctx.accounts.token_owner_account_b.key(),
-      withdraw_liquidity: amount,
-      total_liquidity: ctx.accounts.lockbox.total_liquidity
+      deposit_liquidity: liquidity_amount,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity,
+      target_chain,
+      target_address,
+      wormhole_message: ctx.accounts.wormhole_message.key()
     });
 
     Ok(())
   }
-}
 
-#[derive(Accounts)]
-pub struct InitializeLiquidityLockbox<'info> {
+  /// Deposits an existing position liquidity with the Lockbox position tick range into the Lockbox position and gets bridged tokens minted in return.
+  ///
+  /// ### Parameters
+  /// - `token_max_a` - Max amount of token A to be added to cover liquidity rounding.
+  /// - `token_max_b` - Max amount of token B to be added to cover liquidity rounding.
+  /// - `proof` - Allowlist Merkle proof of the signer, empty if the allowlist mode is disabled.
+  pub fn deposit_position(ctx: Context<DepositPositionLiquidity>,
+    token_max_a: u64,
+    token_max_b: u64,
+    proof: Vec<[u8; 32]>
+  ) -> Result<()> {
+    let whirlpool = ctx.accounts.user_position.whirlpool;
+    let position_mint = ctx.accounts.user_position.position_mint;
+    let liquidity = ctx.accounts.user_position.liquidity;
+    let tick_lower_index = ctx.accounts.user_position.tick_lower_index;
+    let tick_upper_index = ctx.accounts.user_position.tick_upper_index;
+
+    // Check the whirlpool
+    if whirlpool != ctx.accounts.lockbox.whirlpool {
+        return Err(ErrorCode::WrongWhirlpool.into());
+    }
+
+    // Check the discriminator
+    let account = &ctx.accounts.user_position.to_account_info();
+
+    let data = account.try_borrow_data()?;
+    if data.len() != Position::LEN {
+        return Err(ErrorCode::WrongPositionHeader.into());
+    }
+
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&data[0..8]);
+    if discriminator != POSITION_HEADER {
+        return Err(ErrorCode::WrongPositionHeader.into());
+    }
+    drop(data);
+
+    // Check that the liquidity is within uint64 bounds
+    if liquidity > std::u64::MAX as u128 {
+      return Err(ErrorCode::LiquidityOverflow.into());
+    }
+
+    let position_liquidity = liquidity as u64;
+
+    // Check for the minimum liquidity in position
+    if position_liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check that the tick range matches the lockbox position one
+    if tick_lower_index != ctx.accounts.position.tick_lower_index ||
+      tick_upper_index != ctx.accounts.position.tick_upper_index {
+      return Err(ErrorCode::OutOfRange.into());
+    }
+
+    // Check the PDA ownership
+    if ctx.accounts.user_position.to_account_info().owner != &ORCA {
+      return Err(ErrorCode::WrongOwner.into());
+    }
+
+    // Check the position PDA address correctness
+    let position_pda = Pubkey::find_program_address(&[b"position", position_mint.as_ref()], &ORCA);
+    if position_pda.0 != ctx.accounts.user_position.key() {
+      return Err(ErrorCode::WrongPositionPDA.into());
+    }
+
+    // Check that the user position is not the lockbox one
+    if ctx.accounts.user_position.key() == ctx.accounts.lockbox.position {
+      return Err(ErrorCode::WrongPositionPDA.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the lockbox is not in the emergency mode
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Check the deposit limits
+    ctx.accounts.lockbox.check_deposit_limits(position_liquidity)?;
+
+    // Check that the signer is allowlisted
+    ctx.accounts.lockbox.check_allowlist(&ctx.accounts.signer.key(), &proof)?;
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Total liquidity update with the check
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_add(position_liquidity) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityOverflow.into()),
+      };
+
+    // Record the deposit in the user state and check it against the user cap
+    let lockbox = ctx.accounts.lockbox.key();
+    let user = ctx.accounts.signer.key();
+    ctx.accounts.user_state.initialize_if_needed(*ctx.bumps.get("user_state").unwrap(), lockbox, user)?;
+    ctx.accounts.user_state.record_deposit(position_liquidity, ctx.accounts.lockbox.max_user_deposit)?;
+
+    // CPI to decrease the whole user position liquidity into the user token accounts
+    let cpi_program_decrease_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_decrease_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.user_position.to_account_info(),
+      position_authority: ctx.accounts.signer.to_account_info(),
+      position_token_account: ctx.accounts.user_position_token_account.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_decrease_liquidity = CpiContext::new(
+      cpi_program_decrease_liquidity,
+      cpi_accounts_decrease_liquidity
+    );
+    whirlpool::cpi::decrease_liquidity(cpi_ctx_decrease_liquidity, liquidity, 0, 0)?;
+
+    // Increase the lockbox position liquidity by the same amount and mint bridged tokens in the amount of position liquidity
+    increase_liquidity_and_mint(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+      },
+      ctx.accounts.signer.to_account_info(),
+      ctx.accounts.bridged_token_mint.to_account_info(),
+      ctx.accounts.bridged_token_account.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      position_liquidity,
+      token_max_a,
+      token_max_b
+    )?;
+
+    emit!(DepositPositionEvent {
+      signer: ctx.accounts.signer.key(),
+      position: ctx.accounts.position.key(),
+      user_position: ctx.accounts.user_position.key(),
+      deposit_liquidity: position_liquidity,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Withdraws a specified amount of liquidity for supplied bridged tokens.
+  ///
+  /// ### Parameters
+  /// - `amount` - Amount of bridged tokens corresponding to the position liquidity amount to withdraw.
+  /// - `token_min_a` - The minimum amount of token A the signer is willing to withdraw.
+  /// - `token_min_b` - The minimum amount of token B the signer is willing to withdraw.
+  pub fn withdraw(
+    ctx: Context<WithdrawLiquidityForTokens>,
+    amount: u64,
+    token_min_a: u64,
+    token_min_b: u64
+  ) -> Result<()> {
+    // Check if there is any liquidity left in the Lockbox
+    if ctx.accounts.position.liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the token amount
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check the requested amount to be smaller or equal than the position liquidity
+    if amount > ctx.accounts.position.liquidity as u64 {
+      return Err(ErrorCode::AmountExceedsPositionLiquidity.into());
+    }
+
+    // Check that the requested amount does not take the liquidity reserved for queued withdrawals
+    if amount > ctx.accounts.lockbox.available_liquidity() {
+      return Err(ErrorCode::AmountExceedsTotalLiquidity.into());
+    }
+
+    // Burn provided amount of bridged tokens
+    burn_bridged_tokens(
+      ctx.accounts.token_program.to_account_info(),
+      ctx.accounts.bridged_token_account.to_account_info(),
+      ctx.accounts.bridged_token_mint.to_account_info(),
+      ctx.accounts.signer.to_account_info(),
+      amount,
+      BRIDGED_TOKEN_DECIMALS
+    )?;
+
+    // Collect fees into the fee collector accounts and decrease liquidity into the signer token accounts
+    collect_fees_and_decrease_liquidity(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      },
+      ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      amount as u128,
+      token_min_a,
+      token_min_b
+    )?;
+
+    // Update the position liquidity
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_sub(amount) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityUnderflow.into()),
+      };
+
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(amount, Clock::get()?.slot)?;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), amount)?;
+
+    emit!(WithdrawEvent {
+      signer: ctx.accounts.signer.key(),
+      position: ctx.accounts.position.key(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.key(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.key(),
+      withdraw_liquidity: amount,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Completes a bridged token transfer from a posted Wormhole VAA and withdraws the received amount of liquidity.
+  ///
+  /// ### Parameters
+  /// - `token_min_a` - The minimum amount of token A the signer is willing to withdraw.
+  /// - `token_min_b` - The minimum amount of token B the signer is willing to withdraw.
+  pub fn complete_and_withdraw(
+    ctx: Context<CompleteAndWithdraw>,
+    token_min_a: u64,
+    token_min_b: u64
+  ) -> Result<()> {
+    // Check if there is any liquidity left in the Lockbox
+    if ctx.accounts.position.liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the token bridge is configured
+    if ctx.accounts.lockbox.token_bridge == Pubkey::default() {
+      return Err(ErrorCode::BridgeNotConfigured.into());
+    }
+
+    // Complete the bridged token transfer, the posted VAA is verified and claimed by the token bridge
+    let amount_before = ctx.accounts.bridged_token_account.amount;
+
+    let complete_native = Instruction {
+      program_id: ctx.accounts.token_bridge_program.key(),
+      accounts: vec![
+        AccountMeta::new(ctx.accounts.signer.key(), true),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_config.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.posted_vaa.key(), false),
+        AccountMeta::new(ctx.accounts.token_bridge_claim.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_endpoint.key(), false),
+        AccountMeta::new(ctx.accounts.bridged_token_account.key(), false),
+        AccountMeta::new(ctx.accounts.relayer_fee_token_account.key(), false),
+        AccountMeta::new(ctx.accounts.token_bridge_custody.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.bridged_token_mint.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.token_bridge_custody_signer.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.core_bridge_program.key(), false),
+      ],
+      data: vec![TOKEN_BRIDGE_COMPLETE_NATIVE]
+    };
+
+    invoke(
+      &complete_native,
+      &[
+        ctx.accounts.signer.to_account_info(),
+        ctx.accounts.token_bridge_config.to_account_info(),
+        ctx.accounts.posted_vaa.to_account_info(),
+        ctx.accounts.token_bridge_claim.to_account_info(),
+        ctx.accounts.token_bridge_endpoint.to_account_info(),
+        ctx.accounts.bridged_token_account.to_account_info(),
+        ctx.accounts.relayer_fee_token_account.to_account_info(),
+        ctx.accounts.token_bridge_custody.to_account_info(),
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.token_bridge_custody_signer.to_account_info(),
+        ctx.accounts.rent.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.core_bridge_program.to_account_info(),
+        ctx.accounts.token_bridge_program.to_account_info(),
+      ]
+    )?;
+
+    // Withdraw the amount of bridged tokens received by the signer
+    ctx.accounts.bridged_token_account.reload()?;
+    let amount = ctx.accounts.bridged_token_account.amount - amount_before;
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the requested amount to be smaller or equal than the position liquidity
+    if amount > ctx.accounts.position.liquidity as u64 {
+      return Err(ErrorCode::AmountExceedsPositionLiquidity.into());
+    }
+
+    // Check that the requested amount does not take the liquidity reserved for queued withdrawals
+    if amount > ctx.accounts.lockbox.available_liquidity() {
+      return Err(ErrorCode::AmountExceedsTotalLiquidity.into());
+    }
+
+    // Burn provided amount of bridged tokens
+    burn_bridged_tokens(
+      ctx.accounts.token_program.to_account_info(),
+      ctx.accounts.bridged_token_account.to_account_info(),
+      ctx.accounts.bridged_token_mint.to_account_info(),
+      ctx.accounts.signer.to_account_info(),
+      amount,
+      BRIDGED_TOKEN_DECIMALS
+    )?;
+
+    // Collect fees into the fee collector accounts and decrease liquidity into the signer token accounts
+    collect_fees_and_decrease_liquidity(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      },
+      ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      amount as u128,
+      token_min_a,
+      token_min_b
+    )?;
+
+    // Update the position liquidity
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_sub(amount) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityUnderflow.into()),
+      };
+
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(amount, Clock::get()?.slot)?;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), amount)?;
+
+    emit!(CompleteAndWithdrawEvent {
+      signer: ctx.accounts.signer.key(),
+      position: ctx.accounts.position.key(),
+      posted_vaa: ctx.accounts.posted_vaa.key(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.key(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.key(),
+      withdraw_liquidity: amount,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Withdraws a specified amount of liquidity for supplied bridged tokens into a new position of the signer
+  /// with the Lockbox position tick range.
+  ///
+  /// ### Parameters
+  /// - `amount` - Amount of bridged tokens corresponding to the position liquidity amount to withdraw.
+  /// - `token_max_a` - Max amount of token A to be added for the new position liquidity.
+  /// - `token_max_b` - Max amount of token B to be added for the new position liquidity.
+  pub fn withdraw_to_position(
+    ctx: Context<WithdrawLiquidityToPosition>,
+    amount: u64,
+    token_max_a: u64,
+    token_max_b: u64
+  ) -> Result<()> {
+    // Check if there is any liquidity left in the Lockbox
+    if ctx.accounts.position.liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the token amount
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check the requested amount to be smaller or equal than the position liquidity
+    if amount > ctx.accounts.position.liquidity as u64 {
+      return Err(ErrorCode::AmountExceedsPositionLiquidity.into());
+    }
+
+    // Check that the requested amount does not take the liquidity reserved for queued withdrawals
+    if amount > ctx.accounts.lockbox.available_liquidity() {
+      return Err(ErrorCode::AmountExceedsTotalLiquidity.into());
+    }
+
+    // Check the new position PDA address correctness
+    let new_position_pda = Pubkey::find_program_address(&[b"position", ctx.accounts.new_position_mint.key().as_ref()], &ORCA);
+    if new_position_pda.0 != ctx.accounts.new_position.key() {
+      return Err(ErrorCode::WrongPositionPDA.into());
+    }
+
+    // Burn provided amount of bridged tokens
+    burn_bridged_tokens(
+      ctx.accounts.token_program.to_account_info(),
+      ctx.accounts.bridged_token_account.to_account_info(),
+      ctx.accounts.bridged_token_mint.to_account_info(),
+      ctx.accounts.signer.to_account_info(),
+      amount,
+      BRIDGED_TOKEN_DECIMALS
+    )?;
+
+    // Collect fees into the fee collector accounts and decrease liquidity into the signer token accounts
+    // Token amounts are not bounded, as they are added right away to the new position
+    collect_fees_and_decrease_liquidity(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      },
+      ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      amount as u128,
+      0,
+      0
+    )?;
+
+    // Open a new position owned by the signer over the lockbox position tick range
+    let cpi_program_open_position = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_open_position = OpenPosition {
+      funder: ctx.accounts.signer.to_account_info(),
+      owner: ctx.accounts.signer.to_account_info(),
+      position: ctx.accounts.new_position.to_account_info(),
+      position_mint: ctx.accounts.new_position_mint.to_account_info(),
+      position_token_account: ctx.accounts.new_position_token_account.to_account_info(),
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+      system_program: ctx.accounts.system_program.to_account_info(),
+      rent: ctx.accounts.rent.to_account_info(),
+      associated_token_program: ctx.accounts.associated_token_program.to_account_info()
+    };
+
+    let cpi_ctx_open_position = CpiContext::new(
+      cpi_program_open_position,
+      cpi_accounts_open_position
+    );
+    whirlpool::cpi::open_position(
+      cpi_ctx_open_position,
+      OpenPositionBumps { position_bump: new_position_pda.1 },
+      ctx.accounts.position.tick_lower_index,
+      ctx.accounts.position.tick_upper_index
+    )?;
+
+    // CPI to increase the new position liquidity by the withdrawn amount
+    // Rounding differences between the withdrawn and required token amounts are covered by the signer up to token max values
+    let cpi_program_increase_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_increase_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.new_position.to_account_info(),
+      position_authority: ctx.accounts.signer.to_account_info(),
+      position_token_account: ctx.accounts.new_position_token_account.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_increase_liquidity = CpiContext::new(
+      cpi_program_increase_liquidity,
+      cpi_accounts_increase_liquidity
+    );
+    whirlpool::cpi::increase_liquidity(cpi_ctx_increase_liquidity, amount as u128, token_max_a, token_max_b)?;
+
+    // Update the position liquidity
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_sub(amount) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityUnderflow.into()),
+      };
+
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(amount, Clock::get()?.slot)?;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), amount)?;
+
+    emit!(WithdrawToPositionEvent {
+      signer: ctx.accounts.signer.key(),
+      position: ctx.accounts.position.key(),
+      new_position: ctx.accounts.new_position.key(),
+      withdraw_liquidity: amount,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Requests a queued withdrawal of a specified amount of liquidity for supplied bridged tokens.
+  /// Bridged tokens are burned right away, and the liquidity is reserved and can be withdrawn after the cooldown period.
+  ///
+  /// ### Parameters
+  /// - `amount` - Amount of bridged tokens corresponding to the position liquidity amount to withdraw.
+  pub fn request_withdraw(ctx: Context<RequestWithdraw>, amount: u64) -> Result<()> {
+    // Check the token amount
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the lockbox is not in the emergency mode
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Reserve the requested liquidity, such that it is not withdrawn by others during the cooldown period
+    ctx.accounts.lockbox.reserve_withdraw(amount)?;
+
+    // Burn provided amount of bridged tokens
+    invoke_signed(
+      &burn_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.bridged_token_account.to_account_info().key,
+        ctx.accounts.bridged_token_mint.to_account_info().key,
+        ctx.accounts.signer.to_account_info().key,
+        &[],
+        amount,
+        BRIDGED_TOKEN_DECIMALS,
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.bridged_token_account.to_account_info(),
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+      ],
+      &[]
+    )?;
+
+    // Record the withdraw request
+    let clock = Clock::get()?;
+    ctx.accounts.withdraw_request.initialize(
+      *ctx.bumps.get("withdraw_request").unwrap(),
+      ctx.accounts.signer.key(),
+      amount,
+      clock.slot,
+      ctx.accounts.lockbox.withdraw_cooldown_slots
+    )?;
+
+    emit!(RequestWithdrawEvent {
+      signer: ctx.accounts.signer.key(),
+      withdraw_request: ctx.accounts.withdraw_request.key(),
+      amount,
+      unlock_slot: ctx.accounts.withdraw_request.unlock_slot
+    });
+
+    Ok(())
+  }
+
+  /// Executes a queued withdrawal after its cooldown period.
+  ///
+  /// ### Parameters
+  /// - `token_min_a` - The minimum amount of token A the signer is willing to withdraw.
+  /// - `token_min_b` - The minimum amount of token B the signer is willing to withdraw.
+  pub fn execute_withdraw(
+    ctx: Context<ExecuteWithdraw>,
+    token_min_a: u64,
+    token_min_b: u64
+  ) -> Result<()> {
+    let amount = ctx.accounts.withdraw_request.amount;
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox and withdraw request state layout versions
+    ctx.accounts.lockbox.check_version()?;
+    ctx.accounts.withdraw_request.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the cooldown period has passed
+    let clock = Clock::get()?;
+    if clock.slot < ctx.accounts.withdraw_request.unlock_slot {
+      return Err(ErrorCode::WithdrawRequestLocked.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check the requested amount to be smaller or equal than the position liquidity
+    if amount > ctx.accounts.position.liquidity as u64 {
+      return Err(ErrorCode::AmountExceedsPositionLiquidity.into());
+    }
+
+    // Collect fees into the fee collector accounts and decrease liquidity into the signer token accounts
+    collect_fees_and_decrease_liquidity(
+      ctx.accounts.whirlpool_program.to_account_info(),
+      ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      },
+      ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      &ctx.accounts.lockbox.seeds(),
+      amount as u128,
+      token_min_a,
+      token_min_b
+    )?;
+
+    // Release the reserved liquidity and update the position liquidity
+    ctx.accounts.lockbox.release_withdraw(amount)?;
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_sub(amount) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityUnderflow.into()),
+      };
+
+    // Check the withdrawal rate limit
+    ctx.accounts.lockbox.record_withdraw(amount, clock.slot)?;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), amount)?;
+
+    emit!(ExecuteWithdrawEvent {
+      signer: ctx.accounts.signer.key(),
+      withdraw_request: ctx.accounts.withdraw_request.key(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.key(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.key(),
+      withdraw_liquidity: amount,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    // The withdraw request account is closed on exit
+    Ok(())
+  }
+
+  /// Cancels a queued withdrawal during its cooldown period, or at any time in the emergency mode, and mints the burned
+  /// bridged tokens back to the requester.
+  pub fn cancel_withdraw(ctx: Context<CancelWithdraw>) -> Result<()> {
+    let amount = ctx.accounts.withdraw_request.amount;
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox and withdraw request state layout versions
+    ctx.accounts.lockbox.check_version()?;
+    ctx.accounts.withdraw_request.check_version()?;
+
+    // Check that the cooldown period has not passed yet
+    // In the emergency mode, requests cannot be executed anymore and are cancelled at any time for the emergency redeem
+    if Clock::get()?.slot >= ctx.accounts.withdraw_request.unlock_slot && !ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::WithdrawRequestUnlocked.into());
+    }
+
+    // Release the reserved liquidity
+    ctx.accounts.lockbox.release_withdraw(amount)?;
+
+    // Mint the burned bridged tokens back to the requester
+    invoke_signed(
+      &mint_to(
+        ctx.accounts.token_program.key,
+        ctx.accounts.bridged_token_mint.to_account_info().key,
+        ctx.accounts.bridged_token_account.to_account_info().key,
+        ctx.accounts.lockbox.to_account_info().key,
+        &[ctx.accounts.lockbox.to_account_info().key],
+        amount,
+      )?,
+      &[
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.bridged_token_account.to_account_info(),
+        ctx.accounts.lockbox.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+      ],
+      &[&ctx.accounts.lockbox.seeds()],
+    )?;
+
+    emit!(CancelWithdrawEvent {
+      admin: ctx.accounts.signer.key(),
+      requester: ctx.accounts.requester.key(),
+      withdraw_request: ctx.accounts.withdraw_request.key(),
+      amount
+    });
+
+    // The withdraw request account is closed on exit
+    Ok(())
+  }
+
+  /// Enables the emergency mode by fully decreasing the position liquidity into lockbox-owned token accounts.
+  /// Bridged tokens are then redeemed pro-rata against these token accounts without any further Orca CPI.
+  ///
+  /// ### Parameters
+  /// - `token_min_a` - The minimum amount of token A to be withdrawn from the position.
+  /// - `token_min_b` - The minimum amount of token B to be withdrawn from the position.
+  pub fn emergency_exit(
+    ctx: Context<EmergencyExit>,
+    token_min_a: u64,
+    token_min_b: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not in the emergency mode yet
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Get program signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    let position_liquidity = ctx.accounts.position.liquidity;
+    if position_liquidity > 0 {
+      // Update fees for the position
+      let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_update_fees = UpdateFeesAndRewards {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
+      };
+
+      let cpi_ctx_update_fees = CpiContext::new_with_signer(
+        cpi_program_update_fees,
+        cpi_accounts_update_fees,
+        signer_seeds
+      );
+      whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+
+      // Collect fees from the position
+      let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_collect_fees = CollectFees {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
+
+      let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+        cpi_program_collect_fees,
+        cpi_accounts_collect_fees,
+        signer_seeds
+      );
+      whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+
+      // CPI to decrease the whole position liquidity into the lockbox token accounts
+      let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_modify_liquidity = ModifyLiquidity {
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        position: ctx.accounts.position.to_account_info(),
+        position_authority: ctx.accounts.lockbox.to_account_info(),
+        position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+        tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+        tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+        token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info()
+      };
+
+      let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+        cpi_program_modify_liquidity,
+        cpi_accounts_modify_liquidity,
+        signer_seeds
+      );
+      whirlpool::cpi::decrease_liquidity(cpi_ctx_modify_liquidity, position_liquidity, token_min_a, token_min_b)?;
+    }
+
+    // Record the emergency token accounts that back the remaining total liquidity
+    ctx.accounts.lockbox.emergency_mode = true;
+    ctx.accounts.lockbox.emergency_token_account_a = ctx.accounts.lockbox_token_account_a.key();
+    ctx.accounts.lockbox.emergency_token_account_b = ctx.accounts.lockbox_token_account_b.key();
+
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+
+    emit!(EmergencyExitEvent {
+      admin: ctx.accounts.signer.key(),
+      lockbox_token_account_a: ctx.accounts.lockbox_token_account_a.key(),
+      lockbox_token_account_b: ctx.accounts.lockbox_token_account_b.key(),
+      amount_a: ctx.accounts.lockbox_token_account_a.amount,
+      amount_b: ctx.accounts.lockbox_token_account_b.amount,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Redeems supplied bridged tokens pro-rata against the emergency token accounts in the emergency mode.
+  ///
+  /// ### Parameters
+  /// - `amount` - Amount of bridged tokens to redeem.
+  pub fn emergency_redeem(ctx: Context<EmergencyRedeem>, amount: u64) -> Result<()> {
+    // Check the token amount
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is in the emergency mode
+    if !ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeDisabled.into());
+    }
+
+    // Check the requested amount to be smaller or equal than the total liquidity
+    let total_liquidity = ctx.accounts.lockbox.total_liquidity;
+    if amount > total_liquidity {
+      return Err(ErrorCode::AmountExceedsPositionLiquidity.into());
+    }
+
+    // Calculate the pro-rata token amounts, rounded down in favor of the remaining holders
+    let amount_a = (ctx.accounts.lockbox_token_account_a.amount as u128 * amount as u128 / total_liquidity as u128) as u64;
+    let amount_b = (ctx.accounts.lockbox_token_account_b.amount as u128 * amount as u128 / total_liquidity as u128) as u64;
+
+    // Burn provided amount of bridged tokens
+    invoke_signed(
+      &burn_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.bridged_token_account.to_account_info().key,
+        ctx.accounts.bridged_token_mint.to_account_info().key,
+        ctx.accounts.signer.to_account_info().key,
+        &[],
+        amount,
+        BRIDGED_TOKEN_DECIMALS,
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.bridged_token_account.to_account_info(),
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+      ],
+      &[]
+    )?;
+
+    // Get program signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    if amount_a > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            to: ctx.accounts.token_owner_account_a.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        amount_a,
+      )?;
+    }
+
+    if amount_b > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            to: ctx.accounts.token_owner_account_b.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        amount_b,
+      )?;
+    }
+
+    // Decrease the total liquidity amount
+    ctx.accounts.lockbox.total_liquidity = total_liquidity - amount;
+
+    // Record the withdrawal in the user state
+    record_user_withdraw(&ctx.accounts.user_state.to_account_info(), amount)?;
+
+    emit!(EmergencyRedeemEvent {
+      signer: ctx.accounts.signer.key(),
+      token_owner_account_a: ctx.accounts.token_owner_account_a.key(),
+      token_owner_account_b: ctx.accounts.token_owner_account_b.key(),
+      amount,
+      amount_a,
+      amount_b,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Rebalances the Lockbox position liquidity into a new position centered on the whirlpool current tick.
+  /// The new position keeps the tick range width and the liquidity of the Lockbox position, such that bridged tokens
+  /// remain backed by the same liquidity amount.
+  ///
+  /// The new position liquidity is funded by the tokens of the closed position, then by the lockbox token reserve left
+  /// from previous rebalances, and only then by the keeper cover, which is not reimbursed when used. Unused keeper cover
+  /// is returned to the keeper, and any token surplus is kept in the lockbox token accounts as the reserve.
+  ///
+  /// ### Parameters
+  /// - `swap_amount` - Amount of input tokens to swap for reaching the new position token ratio, zero to skip the swap.
+  /// - `swap_min_amount_out` - Minimum amount of output tokens to receive from the swap.
+  /// - `a_to_b` - Swap direction, true if token A is swapped for token B.
+  /// - `token_max_a` - Max amount of token A to be added by the keeper to cover the new position liquidity.
+  /// - `token_max_b` - Max amount of token B to be added by the keeper to cover the new position liquidity.
+  pub fn rebalance(ctx: Context<RebalancePosition>,
+    swap_amount: u64,
+    swap_min_amount_out: u64,
+    a_to_b: bool,
+    token_max_a: u64,
+    token_max_b: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the lockbox is not in the emergency mode
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Check the rebalance keeper and the rebalance frequency
+    ctx.accounts.lockbox.record_rebalance(ctx.accounts.signer.key, Clock::get()?.slot)?;
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Get the position liquidity
+    let liquidity = ctx.accounts.position.liquidity;
+
+    // Check if there is any liquidity left in the Lockbox
+    if liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check that the position is out of range
+    let tick_index_lower = ctx.accounts.position.tick_lower_index;
+    let tick_index_upper = ctx.accounts.position.tick_upper_index;
+    let tick_index_current = ctx.accounts.whirlpool.tick_current_index;
+    if tick_index_lower <= tick_index_current && tick_index_current < tick_index_upper {
+      return Err(ErrorCode::PositionInRange.into());
+    }
+
+    // Get the new position tick range of the same width centered on the current tick
+    let tick_spacing = ctx.accounts.whirlpool.tick_spacing as i32;
+    let tick_width = tick_index_upper - tick_index_lower;
+    let new_tick_lower_index = tick_index_current.div_euclid(tick_spacing) * tick_spacing -
+      (tick_width / tick_spacing / 2) * tick_spacing;
+    let new_tick_upper_index = new_tick_lower_index + tick_width;
+    if new_tick_lower_index < MIN_TICK_INDEX || new_tick_upper_index > MAX_TICK_INDEX {
+      return Err(ErrorCode::WrongTickRange.into());
+    }
+
+    // Check the new position PDA address correctness
+    let new_position_pda = Pubkey::find_program_address(&[b"position", ctx.accounts.new_position_mint.key().as_ref()], &ORCA);
+    if new_position_pda.0 != ctx.accounts.new_position.key() {
+      return Err(ErrorCode::WrongPositionPDA.into());
+    }
+
+    // Get the swap sqrt price limit bounded by the maximum rebalance price slippage
+    let sqrt_price_limit = get_sqrt_price_limit(
+      ctx.accounts.whirlpool.sqrt_price,
+      ctx.accounts.lockbox.max_rebalance_slippage_bps,
+      a_to_b
+    );
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    // Transfer the liquidity cover of A tokens to the lockbox
+    if token_max_a > 0 {
+      token::transfer(
+        CpiContext::new(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.token_owner_account_a.to_account_info(),
+            to: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+          },
+        ),
+        token_max_a,
+      )?;
+    }
+
+    // Transfer the liquidity cover of B tokens to the lockbox
+    if token_max_b > 0 {
+      token::transfer(
+        CpiContext::new(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.token_owner_account_b.to_account_info(),
+            to: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+          },
+        ),
+        token_max_b,
+      )?;
+    }
+
+    // Update fees for the position
+    let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_update_fees = UpdateFeesAndRewards {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
+    };
+
+    let cpi_ctx_update_fees = CpiContext::new_with_signer(
+      cpi_program_update_fees,
+      cpi_accounts_update_fees,
+      signer_seeds
+    );
+    whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+
+    // Collect fees from the position
+    let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_collect_fees = CollectFees {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+      cpi_program_collect_fees,
+      cpi_accounts_collect_fees,
+      signer_seeds
+    );
+    whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+
+    // CPI to decrease the whole position liquidity into the lockbox token accounts
+    let cpi_program_decrease_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_decrease_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_decrease_liquidity = CpiContext::new_with_signer(
+      cpi_program_decrease_liquidity,
+      cpi_accounts_decrease_liquidity,
+      signer_seeds
+    );
+    whirlpool::cpi::decrease_liquidity(cpi_ctx_decrease_liquidity, liquidity, 0, 0)?;
+
+    // Close the position
+    let cpi_program_close_position = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_close_position = ClosePosition {
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      receiver: ctx.accounts.signer.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_mint: ctx.accounts.position_mint.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_close_position = CpiContext::new_with_signer(
+      cpi_program_close_position,
+      cpi_accounts_close_position,
+      signer_seeds
+    );
+    whirlpool::cpi::close_position(cpi_ctx_close_position)?;
+
+    // Swap the lockbox tokens towards the new position token ratio within the sqrt price limit
+    if swap_amount > 0 {
+      let cpi_program_swap = ctx.accounts.whirlpool_program.to_account_info();
+      let cpi_accounts_swap = Swap {
+        token_program: ctx.accounts.token_program.to_account_info(),
+        token_authority: ctx.accounts.lockbox.to_account_info(),
+        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+        token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+        token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+        token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+        token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+        tick_array_0: ctx.accounts.tick_array_0.to_account_info(),
+        tick_array_1: ctx.accounts.tick_array_1.to_account_info(),
+        tick_array_2: ctx.accounts.tick_array_2.to_account_info(),
+        oracle: ctx.accounts.oracle.to_account_info()
+      };
+
+      let cpi_ctx_swap = CpiContext::new_with_signer(
+        cpi_program_swap,
+        cpi_accounts_swap,
+        signer_seeds
+      );
+      whirlpool::cpi::swap(cpi_ctx_swap, swap_amount, swap_min_amount_out, sqrt_price_limit, true, a_to_b)?;
+    }
+
+    // Open a new position owned by the lockbox centered on the current tick
+    let cpi_program_open_position = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_open_position = OpenPosition {
+      funder: ctx.accounts.signer.to_account_info(),
+      owner: ctx.accounts.lockbox.to_account_info(),
+      position: ctx.accounts.new_position.to_account_info(),
+      position_mint: ctx.accounts.new_position_mint.to_account_info(),
+      position_token_account: ctx.accounts.new_position_token_account.to_account_info(),
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+      system_program: ctx.accounts.system_program.to_account_info(),
+      rent: ctx.accounts.rent.to_account_info(),
+      associated_token_program: ctx.accounts.associated_token_program.to_account_info()
+    };
+
+    let cpi_ctx_open_position = CpiContext::new(
+      cpi_program_open_position,
+      cpi_accounts_open_position
+    );
+    whirlpool::cpi::open_position(
+      cpi_ctx_open_position,
+      OpenPositionBumps { position_bump: new_position_pda.1 },
+      new_tick_lower_index,
+      new_tick_upper_index
+    )?;
+
+    // Get the tokens available for increasing the new position liquidity, including the reserve and the keeper cover
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+    let token_available_a = ctx.accounts.lockbox_token_account_a.amount;
+    let token_available_b = ctx.accounts.lockbox_token_account_b.amount;
+
+    // CPI call to increase the new position liquidity by the rebalanced amount
+    let cpi_program_increase_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_increase_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.new_position.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position_token_account: ctx.accounts.new_position_token_account.to_account_info(),
+      tick_array_lower: ctx.accounts.new_tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.new_tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_increase_liquidity = CpiContext::new_with_signer(
+      cpi_program_increase_liquidity,
+      cpi_accounts_increase_liquidity,
+      signer_seeds
+    );
+    whirlpool::cpi::increase_liquidity(cpi_ctx_increase_liquidity, liquidity, token_available_a, token_available_b)?;
+
+    // Return unused keeper cover tokens, the keeper cover is only used after the lockbox tokens are exhausted
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+    let refund_a = std::cmp::min(ctx.accounts.lockbox_token_account_a.amount, token_max_a);
+    let refund_b = std::cmp::min(ctx.accounts.lockbox_token_account_b.amount, token_max_b);
+
+    if refund_a > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            to: ctx.accounts.token_owner_account_a.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        refund_a,
+      )?;
+    }
+
+    if refund_b > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            to: ctx.accounts.token_owner_account_b.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        refund_b,
+      )?;
+    }
+
+    // Update the lockbox position
+    ctx.accounts.lockbox.position = ctx.accounts.new_position.key();
+    ctx.accounts.lockbox.pda_position_account = ctx.accounts.new_position_token_account.key();
+
+    emit!(RebalanceEvent {
+      signer: ctx.accounts.signer.key(),
+      position: ctx.accounts.position.key(),
+      new_position: ctx.accounts.new_position.key(),
+      tick_lower_index: new_tick_lower_index,
+      tick_upper_index: new_tick_upper_index,
+      liquidity: liquidity as u64
+    });
+
+    Ok(())
+  }
+
+  /// Migrates a lockbox v1 position into the Lockbox position and mints bridged tokens for the v1 bridged token swap.
+  /// The migrated liquidity is subject to the deposit limits, and the unused rounding cover is returned to the signer.
+  ///
+  /// ### Parameters
+  /// - `id` - Lockbox v1 position ID.
+  /// - `token_max_a` - Max amount of token A to be added to cover liquidity rounding.
+  /// - `token_max_b` - Max amount of token B to be added to cover liquidity rounding.
+  pub fn migrate_position(ctx: Context<MigrateV1Position>,
+    id: u32,
+    token_max_a: u64,
+    token_max_b: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the lockbox is not paused
+    if ctx.accounts.lockbox.paused {
+      return Err(ErrorCode::LockboxPaused.into());
+    }
+
+    // Check that the lockbox is not in the emergency mode
+    if ctx.accounts.lockbox.emergency_mode {
+      return Err(ErrorCode::EmergencyModeEnabled.into());
+    }
+
+    // Check the lockbox v1 PDA address correctness
+    let lockbox_v1_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &LOCKBOX_V1);
+    if lockbox_v1_pda.0 != ctx.accounts.lockbox_v1.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox v1 program address
+    if ctx.accounts.lockbox_v1_program.key() != LOCKBOX_V1 {
+      return Err(ErrorCode::WrongLockboxProgram.into());
+    }
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that the first token mint is the whirlpool token A
+    let token_mint_a = ctx.accounts.whirlpool.token_mint_a;
+    if ctx.accounts.token_owner_account_a.mint != token_mint_a || ctx.accounts.token_vault_a.mint != token_mint_a {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the second token mint is the whirlpool token B
+    let token_mint_b = ctx.accounts.whirlpool.token_mint_b;
+    if ctx.accounts.token_owner_account_b.mint != token_mint_b || ctx.accounts.token_vault_b.mint != token_mint_b {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+
+    // Check that the lockbox v1 position tick range matches the lockbox position one
+    if ctx.accounts.v1_position.tick_lower_index != ctx.accounts.position.tick_lower_index ||
+      ctx.accounts.v1_position.tick_upper_index != ctx.accounts.position.tick_upper_index {
+      return Err(ErrorCode::OutOfRange.into());
+    }
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    // CPI to the lockbox v1 program to transfer the position NFT to the lockbox
+    let mut data = hash(b"global:migrate_position").to_bytes()[..8].to_vec();
+    data.extend_from_slice(&id.to_le_bytes());
+    invoke_signed(
+      &Instruction {
+        program_id: LOCKBOX_V1,
+        accounts: vec![
+          AccountMeta::new(ctx.accounts.lockbox.key(), true),
+          AccountMeta::new(ctx.accounts.pda_v1_position_account.key(), false),
+          AccountMeta::new_readonly(ctx.accounts.v1_position.key(), false),
+          AccountMeta::new(ctx.accounts.v1_pda_position_account.key(), false),
+          AccountMeta::new_readonly(ctx.accounts.v1_position_mint.key(), false),
+          AccountMeta::new(ctx.accounts.v1_pda_lockbox_position.key(), false),
+          AccountMeta::new(ctx.accounts.v1_depositor.key(), false),
+          AccountMeta::new(ctx.accounts.lockbox_v1.key(), false),
+          AccountMeta::new(ctx.accounts.v1_position_index.key(), false),
+          AccountMeta::new_readonly(ctx.accounts.token_program.key(), false),
+        ],
+        data
+      },
+      &[
+        ctx.accounts.lockbox.to_account_info(),
+        ctx.accounts.pda_v1_position_account.to_account_info(),
+        ctx.accounts.v1_position.to_account_info(),
+        ctx.accounts.v1_pda_position_account.to_account_info(),
+        ctx.accounts.v1_position_mint.to_account_info(),
+        ctx.accounts.v1_pda_lockbox_position.to_account_info(),
+        ctx.accounts.v1_depositor.to_account_info(),
+        ctx.accounts.lockbox_v1.to_account_info(),
+        ctx.accounts.v1_position_index.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.lockbox_v1_program.to_account_info(),
+      ],
+      signer_seeds
+    )?;
+
+    // Get the migrated position liquidity
+    let liquidity = ctx.accounts.v1_position.liquidity;
+
+    // Check that the liquidity is within uint64 bounds
+    if liquidity > std::u64::MAX as u128 {
+      return Err(ErrorCode::LiquidityOverflow.into());
+    }
+
+    let position_liquidity = liquidity as u64;
+
+    // Check for the minimum liquidity in position
+    if position_liquidity == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the deposit limits
+    ctx.accounts.lockbox.check_deposit_limits(position_liquidity)?;
+
+    // Total liquidity update with the check
+    ctx.accounts.lockbox.total_liquidity = match ctx.accounts.lockbox
+      .total_liquidity
+      .checked_add(position_liquidity) {
+        Some(new_liquidity) => new_liquidity,
+        None => return Err(ErrorCode::LiquidityOverflow.into()),
+      };
+
+    // Record lockbox token balances before the migration
+    let lockbox_amount_a_before = ctx.accounts.lockbox_token_account_a.amount;
+    let lockbox_amount_b_before = ctx.accounts.lockbox_token_account_b.amount;
+
+    // Transfer the rounding cover of A tokens to the lockbox
+    if token_max_a > 0 {
+      token::transfer(
+        CpiContext::new(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.token_owner_account_a.to_account_info(),
+            to: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+          },
+        ),
+        token_max_a,
+      )?;
+    }
+
+    // Transfer the rounding cover of B tokens to the lockbox
+    if token_max_b > 0 {
+      token::transfer(
+        CpiContext::new(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.token_owner_account_b.to_account_info(),
+            to: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+          },
+        ),
+        token_max_b,
+      )?;
+    }
+
+    // Update fees for the migrated position
+    let cpi_program_update_fees = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_update_fees = UpdateFeesAndRewards {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.v1_position.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info()
+    };
+
+    let cpi_ctx_update_fees = CpiContext::new_with_signer(
+      cpi_program_update_fees,
+      cpi_accounts_update_fees,
+      signer_seeds
+    );
+    whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+
+    // Collect fees from the migrated position
+    let cpi_program_collect_fees = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_collect_fees = CollectFees {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position: ctx.accounts.v1_position.to_account_info(),
+      position_token_account: ctx.accounts.pda_v1_position_account.to_account_info(),
+      token_owner_account_a: ctx.accounts.fee_collector_token_owner_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.fee_collector_token_owner_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+      cpi_program_collect_fees,
+      cpi_accounts_collect_fees,
+      signer_seeds
+    );
+    whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+
+    // CPI to decrease the whole migrated position liquidity into the lockbox token accounts
+    let cpi_program_decrease_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_decrease_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.v1_position.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position_token_account: ctx.accounts.pda_v1_position_account.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_decrease_liquidity = CpiContext::new_with_signer(
+      cpi_program_decrease_liquidity,
+      cpi_accounts_decrease_liquidity,
+      signer_seeds
+    );
+    whirlpool::cpi::decrease_liquidity(cpi_ctx_decrease_liquidity, liquidity, 0, 0)?;
+
+    // Close the migrated position
+    let cpi_program_close_position = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_close_position = ClosePosition {
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      receiver: ctx.accounts.signer.to_account_info(),
+      position: ctx.accounts.v1_position.to_account_info(),
+      position_mint: ctx.accounts.v1_position_mint.to_account_info(),
+      position_token_account: ctx.accounts.pda_v1_position_account.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_close_position = CpiContext::new_with_signer(
+      cpi_program_close_position,
+      cpi_accounts_close_position,
+      signer_seeds
+    );
+    whirlpool::cpi::close_position(cpi_ctx_close_position)?;
+
+    // Get the tokens available for increasing the lockbox position liquidity
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+    let token_available_a = ctx.accounts.lockbox_token_account_a.amount - lockbox_amount_a_before;
+    let token_available_b = ctx.accounts.lockbox_token_account_b.amount - lockbox_amount_b_before;
+
+    // CPI call to increase the lockbox position liquidity by the migrated amount
+    let cpi_program_modify_liquidity = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_modify_liquidity = ModifyLiquidity {
+      whirlpool: ctx.accounts.whirlpool.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+      tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+      token_owner_account_a: ctx.accounts.lockbox_token_account_a.to_account_info(),
+      token_owner_account_b: ctx.accounts.lockbox_token_account_b.to_account_info(),
+      token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+      token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+    };
+
+    let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+      cpi_program_modify_liquidity,
+      cpi_accounts_modify_liquidity,
+      signer_seeds
+    );
+    whirlpool::cpi::increase_liquidity(cpi_ctx_modify_liquidity, liquidity, token_available_a, token_available_b)?;
+
+    // Return unused rounding cover tokens to the signer, capped by the cover the signer has transferred
+    ctx.accounts.lockbox_token_account_a.reload()?;
+    ctx.accounts.lockbox_token_account_b.reload()?;
+    let refund_a = std::cmp::min(ctx.accounts.lockbox_token_account_a.amount - lockbox_amount_a_before, token_max_a);
+    let refund_b = std::cmp::min(ctx.accounts.lockbox_token_account_b.amount - lockbox_amount_b_before, token_max_b);
+
+    if refund_a > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_a.to_account_info(),
+            to: ctx.accounts.token_owner_account_a.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        refund_a,
+      )?;
+    }
+
+    if refund_b > 0 {
+      token::transfer(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          Transfer {
+            from: ctx.accounts.lockbox_token_account_b.to_account_info(),
+            to: ctx.accounts.token_owner_account_b.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        ),
+        refund_b,
+      )?;
+    }
+
+    // Mint bridged tokens in the amount of migrated liquidity to the lockbox for v1 bridged token swaps
+    invoke_signed(
+      &mint_to(
+        ctx.accounts.token_program.key,
+        ctx.accounts.bridged_token_mint.to_account_info().key,
+        ctx.accounts.pda_bridged_token_account.to_account_info().key,
+        ctx.accounts.lockbox.to_account_info().key,
+        &[ctx.accounts.lockbox.to_account_info().key],
+        position_liquidity,
+      )?,
+      &[
+        ctx.accounts.bridged_token_mint.to_account_info(),
+        ctx.accounts.pda_bridged_token_account.to_account_info(),
+        ctx.accounts.lockbox.to_account_info(),
+        ctx.accounts.token_program.to_account_info(),
+      ],
+      &[&ctx.accounts.lockbox.seeds()],
+    )?;
+
+    emit!(MigratePositionEvent {
+      signer: ctx.accounts.signer.key(),
+      position: ctx.accounts.position.key(),
+      v1_position: ctx.accounts.v1_position.key(),
+      migrated_liquidity: position_liquidity,
+      total_liquidity: ctx.accounts.lockbox.total_liquidity
+    });
+
+    Ok(())
+  }
+
+  /// Swaps lockbox v1 bridged tokens for bridged tokens backed by the migrated liquidity.
+  ///
+  /// ### Parameters
+  /// - `amount` - Amount of v1 bridged tokens to swap.
+  pub fn swap_bridged_tokens(ctx: Context<SwapBridgedTokens>, amount: u64) -> Result<()> {
+    // Check the token amount
+    if amount == 0 {
+      return Err(ErrorCode::LiquidityZero.into());
+    }
+
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check the lockbox v1 PDA address correctness
+    let lockbox_v1_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &LOCKBOX_V1);
+    if lockbox_v1_pda.0 != ctx.accounts.lockbox_v1.key() || ctx.accounts.lockbox_v1.owner != &LOCKBOX_V1 {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox v1 state layout version, following its discriminator and bump
+    let data = ctx.accounts.lockbox_v1.try_borrow_data()?;
+    if data.len() < 42 || data[9] != LOCKBOX_V1_VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    // Check the v1 bridged token mint recorded in the lockbox v1 account, following its version
+    if data[10..42] != ctx.accounts.v1_bridged_token_mint.key().to_bytes()[..] {
+      return Err(ErrorCode::WrongTokenMint.into());
+    }
+    drop(data);
+
+    // Check the requested amount to be covered by the migrated liquidity
+    if amount > ctx.accounts.pda_bridged_token_account.amount {
+      return Err(ErrorCode::AmountExceedsTotalLiquidity.into());
+    }
+
+    // Burn provided amount of v1 bridged tokens
+    invoke_signed(
+      &burn_checked(
+        ctx.accounts.token_program.key,
+        ctx.accounts.v1_bridged_token_account.to_account_info().key,
+        ctx.accounts.v1_bridged_token_mint.to_account_info().key,
+        ctx.accounts.signer.to_account_info().key,
+        &[],
+        amount,
+        BRIDGED_TOKEN_DECIMALS,
+      )?,
+      &[
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.v1_bridged_token_account.to_account_info(),
+        ctx.accounts.v1_bridged_token_mint.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+      ],
+      &[]
+    )?;
+
+    // Transfer the same amount of bridged tokens from the lockbox to the signer
+    token::transfer(
+      CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+          from: ctx.accounts.pda_bridged_token_account.to_account_info(),
+          to: ctx.accounts.bridged_token_account.to_account_info(),
+          authority: ctx.accounts.lockbox.to_account_info(),
+        },
+        &[&ctx.accounts.lockbox.seeds()[..]]
+      ),
+      amount,
+    )?;
+
+    emit!(SwapBridgedTokensEvent {
+      signer: ctx.accounts.signer.key(),
+      v1_bridged_token_account: ctx.accounts.v1_bridged_token_account.key(),
+      bridged_token_account: ctx.accounts.bridged_token_account.key(),
+      amount
+    });
+
+    Ok(())
+  }
+
+  /// Migrates the lockbox account to the current state layout version.
+  /// The account is reallocated to the current size with the rent difference paid by the admin, and the whirlpool is
+  /// recorded from the lockbox position.
+  pub fn migrate_state(ctx: Context<MigrateState>) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    let lockbox = ctx.accounts.lockbox.to_account_info();
+    let signer = ctx.accounts.signer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+
+    // Migrate the lockbox account according to its stored layout version
+    let lockbox_state = match get_state_version(&lockbox)? {
+      0 => {
+        let legacy_lockbox = {
+          let data = lockbox.try_borrow_data()?;
+          LiquidityLockboxV0::deserialize(&mut &data[8..])?
+        };
+
+        realloc_account(&lockbox, &signer, &system_program, LiquidityLockbox::LEN)?;
+        let mut lockbox_state = LiquidityLockbox::from(legacy_lockbox);
+        lockbox_state.whirlpool = ctx.accounts.position.whirlpool;
+        lockbox_state
+      },
+      _ => return Err(ErrorCode::UnsupportedVersion.into())
+    };
+
+    // Check that the whirlpool is taken from the lockbox position
+    if ctx.accounts.position.key() != lockbox_state.position {
+      return Err(ErrorCode::WrongPositionPDA.into());
+    }
+
+    {
+      let mut data = lockbox.try_borrow_mut_data()?;
+      let mut writer: &mut [u8] = &mut data[..];
+      lockbox_state.try_serialize(&mut writer)?;
+    }
+
+    emit!(MigrateStateEvent {
+      admin: ctx.accounts.signer.key(),
+      lockbox: lockbox.key(),
+      version: LiquidityLockbox::VERSION
+    });
+
+    Ok(())
+  }
+
+  /// Decommissions the Lockbox by closing its position and accounts, and reclaiming all the rent to the admin.
+  ///
+  /// ### Remaining accounts
+  /// Lockbox-owned token accounts to close. All of them must have a zero balance.
+  pub fn decommission<'c: 'info, 'info>(ctx: Context<'_, '_, 'c, 'info, DecommissionLockbox<'info>>) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check the Orca Whirlpool program address
+    if ctx.accounts.whirlpool_program.key() != ORCA {
+        return Err(ErrorCode::WrongOrcaAccount.into());
+    }
+
+    // Check that there is no liquidity left in the Lockbox
+    if ctx.accounts.lockbox.total_liquidity != 0 || ctx.accounts.position.liquidity != 0 {
+      return Err(ErrorCode::LiquidityNotZero.into());
+    }
+
+    // Check that there are no bridged tokens left
+    if ctx.accounts.bridged_token_mint.supply != 0 {
+      return Err(ErrorCode::BridgedSupplyNotZero.into());
+    }
+
+    // Get lockbox signer seeds
+    let signer_seeds = &[&ctx.accounts.lockbox.seeds()[..]];
+
+    // Close the position
+    let cpi_program_close_position = ctx.accounts.whirlpool_program.to_account_info();
+    let cpi_accounts_close_position = ClosePosition {
+      position_authority: ctx.accounts.lockbox.to_account_info(),
+      receiver: ctx.accounts.signer.to_account_info(),
+      position: ctx.accounts.position.to_account_info(),
+      position_mint: ctx.accounts.position_mint.to_account_info(),
+      position_token_account: ctx.accounts.pda_position_account.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info()
+    };
+
+    let cpi_ctx_close_position = CpiContext::new_with_signer(
+      cpi_program_close_position,
+      cpi_accounts_close_position,
+      signer_seeds
+    );
+    whirlpool::cpi::close_position(cpi_ctx_close_position)?;
+
+    // Close lockbox-owned token accounts
+    for token_account in ctx.remaining_accounts {
+      let lockbox_token_account = Account::<TokenAccount>::try_from(token_account)?;
+      if lockbox_token_account.owner != ctx.accounts.lockbox.key() {
+        return Err(ErrorCode::WrongOwner.into());
+      }
+
+      // Check that no tokens are left in the token account, as closing it would strand them
+      if lockbox_token_account.amount != 0 {
+        return Err(ErrorCode::TokenBalanceNotZero.into());
+      }
+
+      token::close_account(
+        CpiContext::new_with_signer(
+          ctx.accounts.token_program.to_account_info(),
+          CloseAccount {
+            account: token_account.clone(),
+            destination: ctx.accounts.signer.to_account_info(),
+            authority: ctx.accounts.lockbox.to_account_info(),
+          },
+          signer_seeds
+        )
+      )?;
+    }
+
+    emit!(DecommissionEvent {
+      admin: ctx.accounts.signer.key(),
+      lockbox: ctx.accounts.lockbox.key(),
+      position: ctx.accounts.position.key(),
+      num_token_accounts: ctx.remaining_accounts.len() as u32
+    });
+
+    // The lockbox account is closed on exit
+    Ok(())
+  }
+
+  /// Sets the deposit limits of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `max_total_liquidity` - Maximum total liquidity, zero to disable the cap.
+  /// - `max_deposit_per_tx` - Maximum liquidity per deposit, zero to disable the limit.
+  /// - `min_deposit` - Minimum liquidity per deposit.
+  pub fn set_deposit_limits(
+    ctx: Context<SetDepositLimits>,
+    max_total_liquidity: u64,
+    max_deposit_per_tx: u64,
+    min_deposit: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.set_deposit_limits(max_total_liquidity, max_deposit_per_tx, min_deposit)?;
+
+    emit!(DepositLimitsEvent {
+      admin: ctx.accounts.signer.key(),
+      max_total_liquidity,
+      max_deposit_per_tx,
+      min_deposit
+    });
+
+    Ok(())
+  }
+
+  /// Sets the maximum cumulative liquidity deposited per user of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `max_user_deposit` - Maximum cumulative liquidity deposited per user, zero to disable the cap.
+  pub fn set_user_deposit_cap(ctx: Context<SetDepositLimits>, max_user_deposit: u64) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.max_user_deposit = max_user_deposit;
+
+    emit!(UserDepositCapEvent {
+      admin: ctx.accounts.signer.key(),
+      max_user_deposit
+    });
+
+    Ok(())
+  }
+
+  /// Sets the depositor allowlist Merkle root of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `allowlist_root` - Allowlist Merkle root, zero to disable the allowlist mode.
+  pub fn set_allowlist_root(ctx: Context<SetDepositLimits>, allowlist_root: [u8; 32]) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.allowlist_root = allowlist_root;
+
+    emit!(AllowlistRootEvent {
+      admin: ctx.accounts.signer.key(),
+      allowlist_root
+    });
+
+    Ok(())
+  }
+
+  /// Sets the withdrawal rate limit of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `withdraw_window_slots` - Window length in slots, zero to disable the rate limit.
+  /// - `max_withdraw_per_window` - Maximum liquidity withdrawn per window.
+  pub fn set_withdraw_rate_limit(
+    ctx: Context<SetWithdrawRateLimit>,
+    withdraw_window_slots: u64,
+    max_withdraw_per_window: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.set_withdraw_rate_limit(withdraw_window_slots, max_withdraw_per_window)?;
+
+    emit!(WithdrawRateLimitEvent {
+      admin: ctx.accounts.signer.key(),
+      withdraw_window_slots,
+      max_withdraw_per_window
+    });
+
+    Ok(())
+  }
+
+  /// Sets the queued withdrawal cooldown of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `withdraw_cooldown_slots` - Cooldown in slots between the withdraw request and its execution.
+  pub fn set_withdraw_cooldown(ctx: Context<SetWithdrawRateLimit>, withdraw_cooldown_slots: u64) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.withdraw_cooldown_slots = withdraw_cooldown_slots;
+
+    emit!(WithdrawCooldownEvent {
+      admin: ctx.accounts.signer.key(),
+      withdraw_cooldown_slots
+    });
+
+    Ok(())
+  }
+
+  /// Sets the position rebalance parameters of the Lockbox.
+  ///
+  /// ### Parameters
+  /// - `rebalance_keeper` - Keeper allowed to rebalance the position, default to disable rebalancing.
+  /// - `rebalance_interval_slots` - Minimum number of slots between rebalances.
+  /// - `max_rebalance_slippage_bps` - Maximum rebalance swap move of the whirlpool price in basis points.
+  pub fn set_rebalance_params(
+    ctx: Context<SetRebalanceParams>,
+    rebalance_keeper: Pubkey,
+    rebalance_interval_slots: u64,
+    max_rebalance_slippage_bps: u16
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.set_rebalance_params(rebalance_keeper, rebalance_interval_slots, max_rebalance_slippage_bps)?;
+
+    emit!(RebalanceParamsEvent {
+      admin: ctx.accounts.signer.key(),
+      rebalance_keeper,
+      rebalance_interval_slots,
+      max_rebalance_slippage_bps
+    });
+
+    Ok(())
+  }
+
+  /// Sets the Wormhole token bridge and core bridge programs used for bridging.
+  ///
+  /// ### Parameters
+  /// - `token_bridge` - Token bridge program, default to disable bridging.
+  /// - `core_bridge` - Core bridge program.
+  pub fn set_bridge_programs(ctx: Context<SetBridgePrograms>, token_bridge: Pubkey, core_bridge: Pubkey) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.token_bridge = token_bridge;
+    ctx.accounts.lockbox.core_bridge = core_bridge;
+
+    emit!(BridgeProgramsEvent {
+      admin: ctx.accounts.signer.key(),
+      token_bridge,
+      core_bridge
+    });
+
+    Ok(())
+  }
+
+  /// Sets the cross-chain governance emitter whose verified Wormhole messages execute governance actions.
+  ///
+  /// ### Parameters
+  /// - `governance_chain` - Wormhole chain id of the governance emitter.
+  /// - `governance_emitter` - Governance emitter address, zero to disable the cross-chain governance.
+  pub fn set_governance_emitter(
+    ctx: Context<SetBridgePrograms>,
+    governance_chain: u16,
+    governance_emitter: [u8; 32]
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    ctx.accounts.lockbox.governance_chain = governance_chain;
+    ctx.accounts.lockbox.governance_emitter = governance_emitter;
+
+    emit!(GovernanceEmitterEvent {
+      admin: ctx.accounts.signer.key(),
+      governance_chain,
+      governance_emitter
+    });
+
+    Ok(())
+  }
+
+  /// Executes a governance action from a posted Wormhole VAA of the governance emitter.
+  ///
+  /// ### Parameters
+  /// - `sequence` - Governance message sequence, each sequence can only be executed once per governance emitter.
+  ///
+  /// ### Remaining accounts
+  /// Fee collector token accounts for tokens A and B, only for the fee collectors update action.
+  pub fn execute_governance<'c: 'info, 'info>(
+    ctx: Context<'_, '_, 'c, 'info, ExecuteGovernance<'info>>,
+    sequence: u64
+  ) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the cross-chain governance is configured
+    if ctx.accounts.lockbox.governance_emitter == [0u8; 32] || ctx.accounts.lockbox.core_bridge == Pubkey::default() {
+      return Err(ErrorCode::GovernanceNotConfigured.into());
+    }
+
+    // Check that the VAA is posted, and thus verified, by the core bridge
+    // Unverified messages published via the core bridge are owned by it as well, but start with a different magic
+    if ctx.accounts.posted_vaa.owner != &ctx.accounts.lockbox.core_bridge {
+      return Err(ErrorCode::WrongPostedVaa.into());
+    }
+
+    let action = {
+      let data = ctx.accounts.posted_vaa.try_borrow_data()?;
+      if data.len() < POSTED_VAA_PAYLOAD_OFFSET + 4 || &data[0..3] != b"vaa" {
+        return Err(ErrorCode::WrongPostedVaa.into());
+      }
+
+      // Check the message sequence
+      let vaa_sequence = u64::from_le_bytes(
+        data[POSTED_VAA_SEQUENCE_OFFSET..POSTED_VAA_EMITTER_CHAIN_OFFSET].try_into().unwrap()
+      );
+      if vaa_sequence != sequence {
+        return Err(ErrorCode::WrongPostedVaa.into());
+      }
+
+      // Check the governance emitter
+      let emitter_chain = u16::from_le_bytes(
+        data[POSTED_VAA_EMITTER_CHAIN_OFFSET..POSTED_VAA_EMITTER_ADDRESS_OFFSET].try_into().unwrap()
+      );
+      if emitter_chain != ctx.accounts.lockbox.governance_chain ||
+        data[POSTED_VAA_EMITTER_ADDRESS_OFFSET..POSTED_VAA_PAYLOAD_OFFSET] != ctx.accounts.lockbox.governance_emitter[..] {
+        return Err(ErrorCode::WrongGovernanceEmitter.into());
+      }
+
+      // Get the payload
+      let payload_len = u32::from_le_bytes(
+        data[POSTED_VAA_PAYLOAD_OFFSET..POSTED_VAA_PAYLOAD_OFFSET + 4].try_into().unwrap()
+      ) as usize;
+      let payload = match data.get(POSTED_VAA_PAYLOAD_OFFSET + 4..POSTED_VAA_PAYLOAD_OFFSET + 4 + payload_len) {
+        Some(payload) => payload,
+        None => return Err(ErrorCode::WrongPostedVaa.into()),
+      };
+
+      // Check that the action targets this program
+      if payload.len() < 32 || payload[0..32] != ID.to_bytes()[..] {
+        return Err(ErrorCode::WrongGovernanceAction.into());
+      }
+
+      match GovernanceAction::try_from_slice(&payload[32..]) {
+        Ok(action) => action,
+        Err(_) => return Err(ErrorCode::WrongGovernanceAction.into()),
+      }
+    };
+
+    // Check that new fee collectors are token accounts of the whirlpool tokens
+    if let GovernanceAction::SetFeeCollectors {
+      fee_collector_token_owner_account_a,
+      fee_collector_token_owner_account_b
+    } = action {
+      if ctx.remaining_accounts.len() != 2 ||
+        ctx.remaining_accounts[0].key() != fee_collector_token_owner_account_a ||
+        ctx.remaining_accounts[1].key() != fee_collector_token_owner_account_b {
+        return Err(ErrorCode::WrongFeeCollectors.into());
+      }
+
+      let fee_collector_a = Account::<TokenAccount>::try_from(&ctx.remaining_accounts[0])?;
+      let fee_collector_b = Account::<TokenAccount>::try_from(&ctx.remaining_accounts[1])?;
+      if fee_collector_a.mint != ctx.accounts.whirlpool.token_mint_a ||
+        fee_collector_b.mint != ctx.accounts.whirlpool.token_mint_b {
+        return Err(ErrorCode::WrongTokenMint.into());
+      }
+    }
+
+    ctx.accounts.lockbox.apply_governance_action(&action)?;
+
+    // Consume the governance message sequence
+    ctx.accounts.governance_sequence.initialize(*ctx.bumps.get("governance_sequence").unwrap(), sequence)?;
+
+    emit!(ExecuteGovernanceEvent {
+      posted_vaa: ctx.accounts.posted_vaa.key(),
+      sequence
+    });
+
+    Ok(())
+  }
+
+  /// Publishes the lockbox reserves via a Wormhole message such that the remote chain can track the bridged supply backing.
+  /// The payload is the reserves payload identifier followed by big-endian encoded `total_liquidity` (u64),
+  /// whirlpool position liquidity (u128), bridged token supply (u64) and the current slot (u64).
+  ///
+  /// ### Parameters
+  /// - `nonce` - Wormhole message nonce.
+  pub fn publish_reserves(ctx: Context<PublishReserves>, nonce: u32) -> Result<()> {
+    // Check the lockbox PDA address correctness
+    let lockbox_pda = Pubkey::find_program_address(&[b"liquidity_lockbox"], &ID);
+    if lockbox_pda.0 != ctx.accounts.lockbox.key() {
+      return Err(ErrorCode::WrongLockboxPDA.into());
+    }
+
+    // Check the lockbox state layout version
+    ctx.accounts.lockbox.check_version()?;
+
+    // Check that the core bridge is configured
+    if ctx.accounts.lockbox.core_bridge == Pubkey::default() {
+      return Err(ErrorCode::BridgeNotConfigured.into());
+    }
+
+    let total_liquidity = ctx.accounts.lockbox.total_liquidity;
+    let position_liquidity = ctx.accounts.position.liquidity;
+    let bridged_token_supply = ctx.accounts.bridged_token_mint.supply;
+    let slot = ctx.accounts.clock.slot;
+
+    let mut payload = vec![RESERVES_PAYLOAD_ID];
+    payload.extend_from_slice(&total_liquidity.to_be_bytes());
+    payload.extend_from_slice(&position_liquidity.to_be_bytes());
+    payload.extend_from_slice(&bridged_token_supply.to_be_bytes());
+    payload.extend_from_slice(&slot.to_be_bytes());
+
+    // Pay the core bridge message fee, stored after the guardian set index and last lamports in the bridge data
+    let message_fee = {
+      let data = ctx.accounts.wormhole_bridge.try_borrow_data()?;
+      if data.len() < 24 {
+        return Err(ErrorCode::WrongBridgeAccount.into());
+      }
+      u64::from_le_bytes(data[16..24].try_into().unwrap())
+    };
+    if message_fee > 0 {
+      invoke(
+        &system_instruction::transfer(
+          ctx.accounts.signer.key,
+          ctx.accounts.wormhole_fee_collector.key,
+          message_fee
+        ),
+        &[
+          ctx.accounts.signer.to_account_info(),
+          ctx.accounts.wormhole_fee_collector.to_account_info(),
+          ctx.accounts.system_program.to_account_info(),
+        ]
+      )?;
+    }
+
+    // Post the message with the Borsh-encoded nonce, payload and consistency level
+    let mut data = vec![CORE_BRIDGE_POST_MESSAGE];
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(&payload);
+    data.push(CONSISTENCY_LEVEL_FINALIZED);
+
+    let post_message = Instruction {
+      program_id: ctx.accounts.core_bridge_program.key(),
+      accounts: vec![
+        AccountMeta::new(ctx.accounts.wormhole_bridge.key(), false),
+        AccountMeta::new(ctx.accounts.wormhole_message.key(), true),
+        AccountMeta::new_readonly(ctx.accounts.wormhole_emitter.key(), true),
+        AccountMeta::new(ctx.accounts.wormhole_sequence.key(), false),
+        AccountMeta::new(ctx.accounts.signer.key(), true),
+        AccountMeta::new(ctx.accounts.wormhole_fee_collector.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.clock.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.rent.key(), false),
+        AccountMeta::new_readonly(ctx.accounts.system_program.key(), false),
+      ],
+      data
+    };
+
+    let emitter_bump = *ctx.bumps.get("wormhole_emitter").unwrap();
+    invoke_signed(
+      &post_message,
+      &[
+        ctx.accounts.wormhole_bridge.to_account_info(),
+        ctx.accounts.wormhole_message.to_account_info(),
+        ctx.accounts.wormhole_emitter.to_account_info(),
+        ctx.accounts.wormhole_sequence.to_account_info(),
+        ctx.accounts.signer.to_account_info(),
+        ctx.accounts.wormhole_fee_collector.to_account_info(),
+        ctx.accounts.clock.to_account_info(),
+        ctx.accounts.rent.to_account_info(),
+        ctx.accounts.system_program.to_account_info(),
+        ctx.accounts.core_bridge_program.to_account_info(),
+      ],
+      &[&[b"emitter", &[emitter_bump]]]
+    )?;
+
+    emit!(PublishReservesEvent {
+      signer: ctx.accounts.signer.key(),
+      wormhole_message: ctx.accounts.wormhole_message.key(),
+      total_liquidity,
+      position_liquidity,
+      bridged_token_supply,
+      slot
+    });
+
+    Ok(())
+  }
+}
+
+// Gets the state layout version of the lockbox account, zero for the legacy layout without the version field
+fn get_state_version(lockbox: &AccountInfo) -> Result<u8> {
+  if lockbox.owner != &ID {
+    return Err(ErrorCode::WrongOwner.into());
+  }
+
+  let data = lockbox.try_borrow_data()?;
+  if data.len() <= LiquidityLockbox::VERSION_OFFSET || data[..8] != LiquidityLockbox::discriminator() {
+    return Err(ErrorCode::UnsupportedVersion.into());
+  }
+
+  if data.len() == LiquidityLockbox::LEN_V0 {
+    Ok(0)
+  } else {
+    Ok(data[LiquidityLockbox::VERSION_OFFSET])
+  }
+}
+
+// Gets the swap sqrt price limit such that the price moves by at most the max slippage in bps
+// The price bound is converted to the sqrt price one with the sqrt of the price factor, rounded towards the current price
+fn get_sqrt_price_limit(sqrt_price: u128, max_slippage_bps: u16, a_to_b: bool) -> u128 {
+  // Price factor in bps scaled by 10^12 such that its sqrt is the sqrt price factor scaled by 10^8
+  let max_slippage_bps = max_slippage_bps as u128;
+  if a_to_b {
+    let factor = (10_000 - max_slippage_bps) * 1_000_000_000_000;
+    let mut sqrt_factor = isqrt(factor);
+    if sqrt_factor * sqrt_factor < factor {
+      sqrt_factor += 1;
+    }
+    std::cmp::max(sqrt_price * sqrt_factor / 100_000_000, MIN_SQRT_PRICE_X64)
+  } else {
+    let factor = (10_000 + max_slippage_bps) * 1_000_000_000_000;
+    std::cmp::min(sqrt_price * isqrt(factor) / 100_000_000, MAX_SQRT_PRICE_X64)
+  }
+}
+
+// Gets the integer square root rounded down
+fn isqrt(value: u128) -> u128 {
+  if value < 2 {
+    return value;
+  }
+
+  let mut x = value;
+  let mut y = (x + 1) / 2;
+  while y < x {
+    x = y;
+    y = (x + value / x) / 2;
+  }
+
+  x
+}
+
+// Increases the lockbox position liquidity with the signer tokens approved up to max amounts,
+// and mints bridged tokens in the amount of the added liquidity
+#[allow(clippy::too_many_arguments)]
+fn increase_liquidity_and_mint<'info>(
+  whirlpool_program: AccountInfo<'info>,
+  modify_liquidity: ModifyLiquidity<'info>,
+  signer: AccountInfo<'info>,
+  bridged_token_mint: AccountInfo<'info>,
+  bridged_token_account: AccountInfo<'info>,
+  lockbox_seeds: &[&[u8]],
+  liquidity_amount: u64,
+  token_max_a: u64,
+  token_max_b: u64
+) -> Result<()> {
+  let lockbox = modify_liquidity.position_authority.clone();
+  let token_program = modify_liquidity.token_program.clone();
+  let token_owner_account_a = modify_liquidity.token_owner_account_a.clone();
+  let token_owner_account_b = modify_liquidity.token_owner_account_b.clone();
+  let signer_seeds = &[lockbox_seeds];
+
+  // Approve A tokens for the lockbox
+  token::approve(
+    CpiContext::new(
+      token_program.clone(),
+      Approve {
+        to: token_owner_account_a.clone(),
+        delegate: lockbox.clone(),
+        authority: signer.clone(),
+      },
+    ),
+    token_max_a,
+  )?;
+
+  // Approve B tokens for the lockbox
+  token::approve(
+    CpiContext::new(
+      token_program.clone(),
+      Approve {
+        to: token_owner_account_b.clone(),
+        delegate: lockbox.clone(),
+        authority: signer.clone(),
+      },
+    ),
+    token_max_b,
+  )?;
+
+  // CPI call to increase liquidity
+  let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+    whirlpool_program,
+    modify_liquidity,
+    signer_seeds
+  );
+  whirlpool::cpi::increase_liquidity(cpi_ctx_modify_liquidity, liquidity_amount as u128, token_max_a, token_max_b)?;
+
+  // Mint bridged tokens in the amount of position liquidity
+  invoke_signed(
+    &mint_to(
+      token_program.key,
+      bridged_token_mint.key,
+      bridged_token_account.key,
+      lockbox.key,
+      &[lockbox.key],
+      liquidity_amount,
+    )?,
+    &[
+      bridged_token_mint,
+      bridged_token_account,
+      lockbox.clone(),
+      token_program.clone(),
+    ],
+    signer_seeds,
+  )?;
+
+  // Revoke approval for unused A tokens
+  token::approve(
+    CpiContext::new(
+      token_program.clone(),
+      Approve {
+        to: token_owner_account_a,
+        delegate: lockbox.clone(),
+        authority: signer.clone(),
+      },
+    ),
+    0,
+  )?;
+
+  // Revoke approval for B tokens
+  token::approve(
+    CpiContext::new(
+      token_program,
+      Approve {
+        to: token_owner_account_b,
+        delegate: lockbox,
+        authority: signer,
+      },
+    ),
+    0,
+  )?;
+
+  Ok(())
+}
+
+// Burns the bridged tokens of the signer
+fn burn_bridged_tokens<'info>(
+  token_program: AccountInfo<'info>,
+  bridged_token_account: AccountInfo<'info>,
+  bridged_token_mint: AccountInfo<'info>,
+  signer: AccountInfo<'info>,
+  amount: u64,
+  decimals: u8
+) -> Result<()> {
+  invoke_signed(
+    &burn_checked(
+      token_program.key,
+      bridged_token_account.key,
+      bridged_token_mint.key,
+      signer.key,
+      &[],
+      amount,
+      decimals,
+    )?,
+    &[
+      token_program,
+      bridged_token_account,
+      bridged_token_mint,
+      signer,
+    ],
+    &[]
+  )?;
+
+  Ok(())
+}
+
+// Collects the lockbox position fees into the fee collector accounts,
+// and decreases the position liquidity into the modify liquidity token owner accounts
+#[allow(clippy::too_many_arguments)]
+fn collect_fees_and_decrease_liquidity<'info>(
+  whirlpool_program: AccountInfo<'info>,
+  modify_liquidity: ModifyLiquidity<'info>,
+  fee_collector_token_owner_account_a: AccountInfo<'info>,
+  fee_collector_token_owner_account_b: AccountInfo<'info>,
+  lockbox_seeds: &[&[u8]],
+  liquidity: u128,
+  token_min_a: u64,
+  token_min_b: u64
+) -> Result<()> {
+  let signer_seeds = &[lockbox_seeds];
+
+  // Update fees for the position
+  let cpi_accounts_update_fees = UpdateFeesAndRewards {
+    whirlpool: modify_liquidity.whirlpool.clone(),
+    position: modify_liquidity.position.clone(),
+    tick_array_lower: modify_liquidity.tick_array_lower.clone(),
+    tick_array_upper: modify_liquidity.tick_array_upper.clone()
+  };
+
+  let cpi_ctx_update_fees = CpiContext::new_with_signer(
+    whirlpool_program.clone(),
+    cpi_accounts_update_fees,
+    signer_seeds
+  );
+  whirlpool::cpi::update_fees_and_rewards(cpi_ctx_update_fees)?;
+
+  // Collect fees from the position
+  let cpi_accounts_collect_fees = CollectFees {
+    whirlpool: modify_liquidity.whirlpool.clone(),
+    position_authority: modify_liquidity.position_authority.clone(),
+    position: modify_liquidity.position.clone(),
+    position_token_account: modify_liquidity.position_token_account.clone(),
+    token_owner_account_a: fee_collector_token_owner_account_a,
+    token_owner_account_b: fee_collector_token_owner_account_b,
+    token_vault_a: modify_liquidity.token_vault_a.clone(),
+    token_vault_b: modify_liquidity.token_vault_b.clone(),
+    token_program: modify_liquidity.token_program.clone()
+  };
+
+  let cpi_ctx_collect_fees = CpiContext::new_with_signer(
+    whirlpool_program.clone(),
+    cpi_accounts_collect_fees,
+    signer_seeds
+  );
+  whirlpool::cpi::collect_fees(cpi_ctx_collect_fees)?;
+
+  // CPI to decrease liquidity
+  let cpi_ctx_modify_liquidity = CpiContext::new_with_signer(
+    whirlpool_program,
+    modify_liquidity,
+    signer_seeds
+  );
+  whirlpool::cpi::decrease_liquidity(cpi_ctx_modify_liquidity, liquidity, token_min_a, token_min_b)?;
+
+  Ok(())
+}
+
+// Records the withdrawn liquidity in the user state if the user has one, as withdrawals never create it
+fn record_user_withdraw<'info>(user_state: &AccountInfo<'info>, liquidity: u64) -> Result<()> {
+  if user_state.owner != &ID || user_state.data_is_empty() {
+    return Ok(());
+  }
+
+  let mut user_state = Account::<UserState>::try_from(user_state)?;
+  user_state.record_withdraw(liquidity)?;
+  user_state.exit(&ID)?;
+
+  Ok(())
+}
+
+// Reallocates the program account to a new length, topping up its rent from the payer
+fn realloc_account<'info>(
+  account: &AccountInfo<'info>,
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+  new_len: usize
+) -> Result<()> {
+  let rent_exempt_lamports = Rent::get()?.minimum_balance(new_len);
+  let lamports = account.lamports();
+  if rent_exempt_lamports > lamports {
+    anchor_lang::system_program::transfer(
+      CpiContext::new(
+        system_program.clone(),
+        anchor_lang::system_program::Transfer {
+          from: payer.clone(),
+          to: account.clone(),
+        },
+      ),
+      rent_exempt_lamports - lamports,
+    )?;
+  }
+  account.realloc(new_len, false)?;
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeLiquidityLockbox<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(init,
+    seeds = [
+      b"liquidity_lockbox".as_ref()
+    ],
+    bump,
+    payer = signer,
+    space = LiquidityLockbox::LEN)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+
+  #[account(constraint = signer.key == &fee_collector_token_owner_account_a.owner,
+    constraint = fee_collector_token_owner_account_a.key() != fee_collector_token_owner_account_b.key()
+  )]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(constraint = signer.key == &fee_collector_token_owner_account_b.owner)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+
+  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+  pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+pub struct DepositPositionForLiquidity<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+
+  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = lockbox.key() == pda_position_account.owner,
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(init_if_needed,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump,
+    space = UserState::LEN,
+    payer = signer)]
+  pub user_state: Box<Account<'info, UserState>>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DepositAndBridge<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+
+  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = lockbox.key() == pda_position_account.owner,
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = pda_bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = lockbox.key() == pda_bridged_token_account.owner
+  )]
+  pub pda_bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(init_if_needed,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump,
+    space = UserState::LEN,
+    payer = signer)]
+  pub user_state: Box<Account<'info, UserState>>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  /// CHECK: Token bridge config, checked by the token bridge
+  pub token_bridge_config: UncheckedAccount<'info>,
+  /// CHECK: Token bridge custody account of the bridged token, checked by the token bridge
+  #[account(mut)]
+  pub token_bridge_custody: UncheckedAccount<'info>,
+  /// CHECK: Token bridge authority signer, checked by the token bridge
+  pub token_bridge_authority_signer: UncheckedAccount<'info>,
+  /// CHECK: Token bridge custody signer, checked by the token bridge
+  pub token_bridge_custody_signer: UncheckedAccount<'info>,
+  /// CHECK: Token bridge emitter, checked by the token bridge
+  pub token_bridge_emitter: UncheckedAccount<'info>,
+  /// CHECK: Token bridge emitter sequence, checked by the core bridge
+  #[account(mut)]
+  pub token_bridge_sequence: UncheckedAccount<'info>,
+  /// CHECK: Core bridge config, checked by the core bridge
+  #[account(mut)]
+  pub wormhole_bridge: UncheckedAccount<'info>,
+  /// CHECK: Core bridge fee collector, checked by the core bridge
+  #[account(mut)]
+  pub wormhole_fee_collector: UncheckedAccount<'info>,
+  /// New Wormhole message account
+  #[account(mut)]
+  pub wormhole_message: Signer<'info>,
+
+  /// CHECK: Token bridge program, checked against the configured one
+  #[account(executable, address = lockbox.token_bridge)]
+  pub token_bridge_program: UncheckedAccount<'info>,
+  /// CHECK: Core bridge program, checked against the configured one
+  #[account(executable, address = lockbox.core_bridge)]
+  pub core_bridge_program: UncheckedAccount<'info>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+  pub clock: Sysvar<'info, Clock>,
+  pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+pub struct DepositPositionLiquidity<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, has_one = whirlpool)]
+  pub user_position: Box<Account<'info, Position>>,
+  #[account(
+    constraint = signer.key == &user_position_token_account.owner,
+    constraint = user_position_token_account.mint == user_position.position_mint,
+    constraint = user_position_token_account.amount == 1
+  )]
+  pub user_position_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+
+  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = lockbox.key() == pda_position_account.owner,
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(init_if_needed,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump,
+    space = UserState::LEN,
+    payer = signer)]
+  pub user_state: Box<Account<'info, UserState>>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidityForTokens<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    address = position.position_mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct CompleteAndWithdraw<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    address = position.position_mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  /// CHECK: Relayer fee token account of the bridged token, checked by the token bridge
+  #[account(mut)]
+  pub relayer_fee_token_account: UncheckedAccount<'info>,
+  /// CHECK: Token bridge config, checked by the token bridge
+  pub token_bridge_config: UncheckedAccount<'info>,
+  /// CHECK: Posted VAA, verified by the core bridge and checked by the token bridge
+  pub posted_vaa: UncheckedAccount<'info>,
+  /// CHECK: Token bridge claim of the VAA preventing its replay, checked by the token bridge
+  #[account(mut)]
+  pub token_bridge_claim: UncheckedAccount<'info>,
+  /// CHECK: Token bridge registered foreign emitter endpoint, checked by the token bridge
+  pub token_bridge_endpoint: UncheckedAccount<'info>,
+  /// CHECK: Token bridge custody account of the bridged token, checked by the token bridge
+  #[account(mut)]
+  pub token_bridge_custody: UncheckedAccount<'info>,
+  /// CHECK: Token bridge custody signer, checked by the token bridge
+  pub token_bridge_custody_signer: UncheckedAccount<'info>,
+
+  /// CHECK: Token bridge program, checked against the configured one
+  #[account(executable, address = lockbox.token_bridge)]
+  pub token_bridge_program: UncheckedAccount<'info>,
+  /// CHECK: Core bridge program, checked against the configured one
+  #[account(executable, address = lockbox.core_bridge)]
+  pub core_bridge_program: UncheckedAccount<'info>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+  pub rent: Sysvar<'info, Rent>
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidityToPosition<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    address = position.position_mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  /// CHECK: New position PDA, checked against the new position mint and initialized by the Orca Whirlpool program
+  #[account(mut)]
+  pub new_position: UncheckedAccount<'info>,
+  #[account(mut)]
+  pub new_position_mint: Signer<'info>,
+  /// CHECK: Signer ATA for the new position mint, initialized by the Orca Whirlpool program
+  #[account(mut)]
+  pub new_position_token_account: UncheckedAccount<'info>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+  pub rent: Sysvar<'info, Rent>,
+  pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdraw<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(init,
+    seeds = [
+      b"withdraw_request".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump,
+    space = WithdrawRequest::LEN,
+    payer = signer)]
+  pub withdraw_request: Box<Account<'info, WithdrawRequest>>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ExecuteWithdraw<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(mut,
+    seeds = [
+      b"withdraw_request".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump = withdraw_request.bump[0],
+    close = signer)]
+  pub withdraw_request: Box<Account<'info, WithdrawRequest>>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    address = position.position_mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct CancelWithdraw<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut,
+    seeds = [
+      b"withdraw_request".as_ref(),
+      lockbox.key().as_ref(),
+      requester.key().as_ref()
+    ],
+    bump = withdraw_request.bump[0],
+    close = requester)]
+  pub withdraw_request: Box<Account<'info, WithdrawRequest>>,
+
+  /// CHECK: Withdraw request requester receiving the request rent, checked against the recorded one
+  #[account(mut, address = withdraw_request.requester)]
+  pub requester: UncheckedAccount<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = requester.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+
   #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct EmergencyExit<'info> {
   pub signer: Signer<'info>,
 
-  #[account(init,
-    seeds = [
-      b"liquidity_lockbox".as_ref()
-    ],
-    bump,
-    payer = signer,
-    space = LiquidityLockbox::LEN)]
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut, address = lockbox.position, has_one = whirlpool)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(
+    address = lockbox.pda_position_account.key(),
+    constraint = pda_position_account.mint == position.position_mint,
+    constraint = pda_position_account.amount == 1,
+    constraint = lockbox.key() == pda_position_account.owner
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = lockbox_token_account_a.mint == whirlpool.token_mint_a,
+    constraint = lockbox.key() == lockbox_token_account_a.owner
+  )]
+  pub lockbox_token_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = lockbox_token_account_b.mint == whirlpool.token_mint_b,
+    constraint = lockbox.key() == lockbox_token_account_b.owner
+  )]
+  pub lockbox_token_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.key() != tick_array_upper.key(),
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut)]
   pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
 
-  #[account(constraint = bridged_token_mint.mint_authority.unwrap() == lockbox.key())]
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct EmergencyRedeem<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(mut, address = lockbox.bridged_token_mint)]
   pub bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = signer.key == &bridged_token_account.owner,
+  )]
+  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
 
-  #[account(constraint = signer.key == &fee_collector_token_owner_account_a.owner,
-    constraint = fee_collector_token_owner_account_a.key() != fee_collector_token_owner_account_b.key()
+  #[account(mut, address = lockbox.emergency_token_account_a)]
+  pub lockbox_token_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.emergency_token_account_b)]
+  pub lockbox_token_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == lockbox_token_account_a.mint,
+    constraint = signer.key == &token_owner_account_a.owner
   )]
-  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
-  #[account(constraint = signer.key == &fee_collector_token_owner_account_b.owner)]
-  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == lockbox_token_account_b.mint,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
 
-  #[account(has_one = whirlpool, has_one = position_mint)]
-  pub position: Box<Account<'info, Position>>,
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  /// CHECK: User state of the signer, recorded only if it exists as withdrawals do not create it
+  #[account(mut,
+    seeds = [
+      b"user_state".as_ref(),
+      lockbox.key().as_ref(),
+      signer.key().as_ref()
+    ],
+    bump)]
+  pub user_state: UncheckedAccount<'info>,
 
-  #[account(address = position.position_mint, constraint = position_mint.supply == 1)]
-  pub position_mint: Box<Account<'info, Mint>>,
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct RebalancePosition<'info> {
+  #[account(mut, address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(mut)]
+  pub signer: Signer<'info>,
 
+  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
   #[account(mut,
+    address = lockbox.pda_position_account.key(),
     constraint = pda_position_account.mint == position_mint.key(),
     constraint = pda_position_account.amount == 1,
     constraint = lockbox.key() == pda_position_account.owner
   )]
   pub pda_position_account: Box<Account<'info, TokenAccount>>,
 
-  pub whirlpool: Box<Account<'info, Whirlpool>>,
+  #[account(mut,
+    address = position.position_mint,
+    constraint = position_mint.supply == 1
+  )]
+  pub position_mint: Box<Account<'info, Mint>>,
+
+  /// CHECK: New position PDA, checked against the new position mint and initialized by the Orca Whirlpool program
+  #[account(mut)]
+  pub new_position: UncheckedAccount<'info>,
+  #[account(mut)]
+  pub new_position_mint: Signer<'info>,
+  /// CHECK: Lockbox ATA for the new position mint, initialized by the Orca Whirlpool program
+  #[account(mut)]
+  pub new_position_token_account: UncheckedAccount<'info>,
+
+  #[account(mut,
+    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
+    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
+    constraint = signer.key == &token_owner_account_a.owner
+  )]
+  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
+    constraint = signer.key == &token_owner_account_b.owner
+  )]
+  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = lockbox_token_account_a.mint == whirlpool.token_mint_a,
+    constraint = lockbox.key() == lockbox_token_account_a.owner
+  )]
+  pub lockbox_token_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = lockbox_token_account_b.mint == whirlpool.token_mint_b,
+    constraint = lockbox.key() == lockbox_token_account_b.owner
+  )]
+  pub lockbox_token_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = token_vault_a.key() == whirlpool.token_vault_a,
+    constraint = token_vault_a.key() != token_vault_b.key()
+  )]
+  pub token_vault_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
+  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub tick_array_upper: AccountLoader<'info, TickArray>,
+
+  #[account(mut, has_one = whirlpool,
+    constraint = new_tick_array_lower.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub new_tick_array_lower: AccountLoader<'info, TickArray>,
+  #[account(mut, has_one = whirlpool,
+    constraint = new_tick_array_upper.to_account_info().owner == &whirlpool_program.key()
+  )]
+  pub new_tick_array_upper: AccountLoader<'info, TickArray>,
+
+  /// CHECK: Swap tick array, checked by the Orca Whirlpool program
+  #[account(mut)]
+  pub tick_array_0: UncheckedAccount<'info>,
+  /// CHECK: Swap tick array, checked by the Orca Whirlpool program
+  #[account(mut)]
+  pub tick_array_1: UncheckedAccount<'info>,
+  /// CHECK: Swap tick array, checked by the Orca Whirlpool program
+  #[account(mut)]
+  pub tick_array_2: UncheckedAccount<'info>,
+  /// CHECK: Whirlpool oracle, checked by the Orca Whirlpool program
+  pub oracle: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
 
   #[account(address = token::ID)]
   pub token_program: Program<'info, Token>,
   pub system_program: Program<'info, System>,
-  pub rent: Sysvar<'info, Rent>
+  pub rent: Sysvar<'info, Rent>,
+  pub associated_token_program: Program<'info, AssociatedToken>
 }
 
 #[derive(Accounts)]
-pub struct DepositPositionForLiquidity<'info> {
+pub struct MigrateV1Position<'info> {
   #[account(mut)]
   pub signer: Signer<'info>,
 
@@ -509,7 +4309,35 @@ pub struct DepositPositionForLiquidity<'info> {
   )]
   pub pda_position_account: Box<Account<'info, TokenAccount>>,
 
-  #[account(mut, address = position.whirlpool)]
+  #[account(mut, has_one = whirlpool, constraint = v1_position.key() != position.key())]
+  pub v1_position: Box<Account<'info, Position>>,
+  #[account(mut, address = v1_position.position_mint, constraint = v1_position_mint.supply == 1)]
+  pub v1_position_mint: Box<Account<'info, Mint>>,
+  #[account(init,
+    associated_token::authority = lockbox,
+    associated_token::mint = v1_position_mint,
+    payer = signer)]
+  pub pda_v1_position_account: Box<Account<'info, TokenAccount>>,
+
+  /// CHECK: Lockbox v1 PDA ATA holding the position NFT, checked by the lockbox v1 program
+  #[account(mut)]
+  pub v1_pda_position_account: UncheckedAccount<'info>,
+  /// CHECK: Lockbox v1 position account, checked by the lockbox v1 program
+  #[account(mut)]
+  pub v1_pda_lockbox_position: UncheckedAccount<'info>,
+  /// CHECK: Lockbox v1 position depositor, checked by the lockbox v1 program
+  #[account(mut)]
+  pub v1_depositor: UncheckedAccount<'info>,
+  /// CHECK: Lockbox v1 account, checked against the lockbox v1 PDA
+  #[account(mut)]
+  pub lockbox_v1: UncheckedAccount<'info>,
+  /// CHECK: Lockbox v1 position index, checked by the lockbox v1 program
+  #[account(mut)]
+  pub v1_position_index: UncheckedAccount<'info>,
+  /// CHECK: Lockbox v1 program, checked against its address
+  pub lockbox_v1_program: UncheckedAccount<'info>,
+
+  #[account(mut, address = lockbox.whirlpool)]
   pub whirlpool: Box<Account<'info, Whirlpool>>,
 
   #[account(mut,
@@ -524,6 +4352,22 @@ pub struct DepositPositionForLiquidity<'info> {
   )]
   pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
 
+  #[account(mut,
+    constraint = lockbox_token_account_a.mint == whirlpool.token_mint_a,
+    constraint = lockbox.key() == lockbox_token_account_a.owner
+  )]
+  pub lockbox_token_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = lockbox_token_account_b.mint == whirlpool.token_mint_b,
+    constraint = lockbox.key() == lockbox_token_account_b.owner
+  )]
+  pub lockbox_token_account_b: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
+  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
+  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
+  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
+
   #[account(mut,
     constraint = token_vault_a.key() == whirlpool.token_vault_a,
     constraint = token_vault_a.key() != token_vault_b.key()
@@ -545,93 +4389,220 @@ pub struct DepositPositionForLiquidity<'info> {
   #[account(mut, address = lockbox.bridged_token_mint)]
   pub bridged_token_mint: Box<Account<'info, Mint>>,
   #[account(mut,
-    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
-    constraint = bridged_token_account.mint == bridged_token_mint.key(),
-    constraint = signer.key == &bridged_token_account.owner,
+    constraint = pda_bridged_token_account.mint == bridged_token_mint.key(),
+    constraint = lockbox.key() == pda_bridged_token_account.owner
+  )]
+  pub pda_bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
+  pub rent: Sysvar<'info, Rent>,
+  pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+#[derive(Accounts)]
+pub struct SwapBridgedTokens<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(mut)]
+  pub v1_bridged_token_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    constraint = v1_bridged_token_account.mint == v1_bridged_token_mint.key(),
+    constraint = signer.key == &v1_bridged_token_account.owner
+  )]
+  pub v1_bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(mut,
+    constraint = bridged_token_account.mint == lockbox.bridged_token_mint
   )]
   pub bridged_token_account: Box<Account<'info, TokenAccount>>,
+  #[account(mut,
+    constraint = pda_bridged_token_account.mint == lockbox.bridged_token_mint,
+    constraint = lockbox.key() == pda_bridged_token_account.owner
+  )]
+  pub pda_bridged_token_account: Box<Account<'info, TokenAccount>>,
+
+  /// CHECK: Lockbox v1 account, checked against the lockbox v1 PDA
+  pub lockbox_v1: UncheckedAccount<'info>,
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct MigrateState<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  /// CHECK: Lockbox account at any supported state layout version, checked against the lockbox PDA
+  #[account(mut)]
+  pub lockbox: UncheckedAccount<'info>,
+
+  pub position: Box<Account<'info, Position>>,
 
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct DecommissionLockbox<'info> {
   #[account(mut)]
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut, address = lockbox.position, has_one = position_mint)]
+  pub position: Box<Account<'info, Position>>,
+  #[account(mut, address = position.position_mint, constraint = position_mint.supply == 1)]
+  pub position_mint: Box<Account<'info, Mint>>,
+  #[account(mut,
+    address = lockbox.pda_position_account.key(),
+    constraint = lockbox.key() == pda_position_account.owner,
+    constraint = pda_position_account.mint == position_mint.key(),
+    constraint = pda_position_account.amount == 1
+  )]
+  pub pda_position_account: Box<Account<'info, TokenAccount>>,
+
+  #[account(address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
+
+  #[account(mut, close = signer)]
   pub lockbox: Box<Account<'info, LiquidityLockbox>>,
   pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
 
-  #[account(address = token::ID)]
-  pub token_program: Program<'info, Token>
+  #[account(address = token::ID)]
+  pub token_program: Program<'info, Token>
+}
+
+#[derive(Accounts)]
+pub struct SetDepositLimits<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawRateLimit<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>
+}
+
+#[derive(Accounts)]
+pub struct SetRebalanceParams<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>
+}
+
+#[derive(Accounts)]
+pub struct SetBridgePrograms<'info> {
+  pub signer: Signer<'info>,
+
+  #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+  pub program: Program<'info, crate::program::LiquidityLockbox>,
+  #[account(constraint = program_data.upgrade_authority_address == Some(signer.key()) @ ErrorCode::WrongAdmin)]
+  pub program_data: Account<'info, ProgramData>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>
+}
+
+#[derive(Accounts)]
+#[instruction(sequence: u64)]
+pub struct ExecuteGovernance<'info> {
+  #[account(mut)]
+  pub signer: Signer<'info>,
+
+  /// CHECK: Posted VAA, checked against the core bridge ownership and the governance emitter
+  pub posted_vaa: UncheckedAccount<'info>,
+
+  #[account(mut)]
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
+  #[account(address = lockbox.whirlpool)]
+  pub whirlpool: Box<Account<'info, Whirlpool>>,
+
+  #[account(init,
+    seeds = [
+      b"governance_sequence".as_ref(),
+      lockbox.governance_chain.to_be_bytes().as_ref(),
+      lockbox.governance_emitter.as_ref(),
+      sequence.to_be_bytes().as_ref()
+    ],
+    bump,
+    space = GovernanceSequence::LEN,
+    payer = signer)]
+  pub governance_sequence: Box<Account<'info, GovernanceSequence>>,
+
+  pub system_program: Program<'info, System>
 }
 
 #[derive(Accounts)]
-pub struct WithdrawLiquidityForTokens<'info> {
-  #[account(mut, address = position.whirlpool)]
-  pub whirlpool: Box<Account<'info, Whirlpool>>,
-
+pub struct PublishReserves<'info> {
+  #[account(mut)]
   pub signer: Signer<'info>,
 
-  #[account(mut, address = lockbox.bridged_token_mint)]
-  pub bridged_token_mint: Box<Account<'info, Mint>>,
-  #[account(mut,
-    constraint = bridged_token_account.mint == lockbox.bridged_token_mint,
-    constraint = bridged_token_account.mint == bridged_token_mint.key(),
-    constraint = signer.key == &bridged_token_account.owner,
-  )]
-  pub bridged_token_account: Box<Account<'info, TokenAccount>>,
-
-  #[account(mut, address = lockbox.position, has_one = whirlpool, has_one = position_mint)]
+  #[account(address = lockbox.position)]
   pub position: Box<Account<'info, Position>>,
-  #[account(mut,
-    address = lockbox.pda_position_account.key(),
-    constraint = pda_position_account.mint == position_mint.key(),
-    constraint = pda_position_account.amount == 1,
-    constraint = lockbox.key() == pda_position_account.owner
-  )]
-  pub pda_position_account: Box<Account<'info, TokenAccount>>,
-
-  #[account(mut,
-    address = position.position_mint,
-    constraint = position_mint.supply == 1
-  )]
-  pub position_mint: Box<Account<'info, Mint>>,
-
-  #[account(mut,
-    constraint = token_owner_account_a.mint == whirlpool.token_mint_a,
-    constraint = token_owner_account_a.mint != token_owner_account_b.mint,
-    constraint = signer.key == &token_owner_account_a.owner
-  )]
-  pub token_owner_account_a: Box<Account<'info, TokenAccount>>,
-  #[account(mut,
-    constraint = token_owner_account_b.mint == whirlpool.token_mint_b,
-    constraint = signer.key == &token_owner_account_b.owner
-  )]
-  pub token_owner_account_b: Box<Account<'info, TokenAccount>>,
-
-  #[account(mut, address = lockbox.fee_collector_token_owner_account_a)]
-  pub fee_collector_token_owner_account_a: Box<Account<'info, TokenAccount>>,
-  #[account(mut, address = lockbox.fee_collector_token_owner_account_b)]
-  pub fee_collector_token_owner_account_b: Box<Account<'info, TokenAccount>>,
-
-  #[account(mut,
-    constraint = token_vault_a.key() == whirlpool.token_vault_a,
-    constraint = token_vault_a.key() != token_vault_b.key()
-  )]
-  pub token_vault_a: Box<Account<'info, TokenAccount>>,
-  #[account(mut, constraint = token_vault_b.key() == whirlpool.token_vault_b)]
-  pub token_vault_b: Box<Account<'info, TokenAccount>>,
+  #[account(address = lockbox.bridged_token_mint)]
+  pub bridged_token_mint: Box<Account<'info, Mint>>,
 
-  #[account(mut, has_one = whirlpool,
-    constraint = tick_array_lower.key() != tick_array_upper.key(),
-    constraint = tick_array_lower.to_account_info().owner == &whirlpool_program.key()
-  )]
-  pub tick_array_lower: AccountLoader<'info, TickArray>,
-  #[account(mut, has_one = whirlpool,
-    constraint = tick_array_upper.to_account_info().owner == &whirlpool_program.key()
-  )]
-  pub tick_array_upper: AccountLoader<'info, TickArray>,
+  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
 
+  /// CHECK: Lockbox Wormhole emitter PDA, signs the posted message
+  #[account(seeds = [b"emitter".as_ref()], bump)]
+  pub wormhole_emitter: UncheckedAccount<'info>,
+  /// CHECK: Core bridge emitter sequence, checked by the core bridge
   #[account(mut)]
-  pub lockbox: Box<Account<'info, LiquidityLockbox>>,
-  pub whirlpool_program: Program<'info, whirlpool::program::Whirlpool>,
+  pub wormhole_sequence: UncheckedAccount<'info>,
+  /// CHECK: Core bridge config, checked by the core bridge
+  #[account(mut)]
+  pub wormhole_bridge: UncheckedAccount<'info>,
+  /// CHECK: Core bridge fee collector, checked by the core bridge
+  #[account(mut)]
+  pub wormhole_fee_collector: UncheckedAccount<'info>,
+  /// New Wormhole message account
+  #[account(mut)]
+  pub wormhole_message: Signer<'info>,
 
-  #[account(address = token::ID)]
-  pub token_program: Program<'info, Token>
+  /// CHECK: Core bridge program, checked against the configured one
+  #[account(executable, address = lockbox.core_bridge)]
+  pub core_bridge_program: UncheckedAccount<'info>,
+
+  pub system_program: Program<'info, System>,
+  pub clock: Sysvar<'info, Clock>,
+  pub rent: Sysvar<'info, Rent>
 }
 
 
@@ -674,7 +4645,73 @@ pub enum ErrorCode {
   #[msg("Wrong token mint")]
   WrongTokenMint,
   #[msg("Whirlpool number downcast")]
-  WhirlpoolNumberDownCastError
+  WhirlpoolNumberDownCastError,
+  #[msg("Provided wrong lockbox program account")]
+  WrongLockboxProgram,
+  #[msg("Unsupported state layout version")]
+  UnsupportedVersion,
+  #[msg("Signer is not the program upgrade authority")]
+  WrongAdmin,
+  #[msg("Bridged token supply is not zero")]
+  BridgedSupplyNotZero,
+  #[msg("Deposit liquidity is below the minimum deposit")]
+  DepositBelowMinimum,
+  #[msg("Deposit liquidity exceeds the per-transaction limit")]
+  DepositExceedsLimit,
+  #[msg("Total liquidity cap exceeded")]
+  TotalLiquidityCapExceeded,
+  #[msg("Maximum deposit is below the minimum deposit")]
+  WrongDepositLimits,
+  #[msg("User deposit cap exceeded")]
+  UserDepositCapExceeded,
+  #[msg("Signer is not allowlisted")]
+  NotAllowlisted,
+  #[msg("Withdrawal rate limit exceeded")]
+  WithdrawRateLimitExceeded,
+  #[msg("Withdraw request is still in its cooldown period")]
+  WithdrawRequestLocked,
+  #[msg("Lockbox is in the emergency mode")]
+  EmergencyModeEnabled,
+  #[msg("Lockbox is not in the emergency mode")]
+  EmergencyModeDisabled,
+  #[msg("Token bridge is not configured")]
+  BridgeNotConfigured,
+  #[msg("Wrong bridge account")]
+  WrongBridgeAccount,
+  #[msg("Lockbox is paused")]
+  LockboxPaused,
+  #[msg("Cross-chain governance is not configured")]
+  GovernanceNotConfigured,
+  #[msg("Wrong posted VAA")]
+  WrongPostedVaa,
+  #[msg("Wrong governance emitter")]
+  WrongGovernanceEmitter,
+  #[msg("Wrong governance action")]
+  WrongGovernanceAction,
+  #[msg("Wrong bridged token mint authority")]
+  WrongMintAuthority,
+  #[msg("Bridged token mint has a freeze authority")]
+  FreezeAuthoritySet,
+  #[msg("Wrong bridged token decimals")]
+  WrongMintDecimals,
+  #[msg("Wrong position tick range")]
+  WrongTickRange,
+  #[msg("Rebalancing is not configured")]
+  RebalanceNotConfigured,
+  #[msg("Wrong rebalance keeper")]
+  WrongRebalanceKeeper,
+  #[msg("Rebalance is too frequent")]
+  RebalanceTooFrequent,
+  #[msg("Wrong rebalance parameters")]
+  WrongRebalanceParams,
+  #[msg("Position is in range")]
+  PositionInRange,
+  #[msg("Wrong fee collector accounts")]
+  WrongFeeCollectors,
+  #[msg("Withdraw request cooldown period has passed")]
+  WithdrawRequestUnlocked,
+  #[msg("Token account balance is not zero")]
+  TokenBalanceNotZero
 }
 
 
@@ -692,6 +4729,23 @@ pub struct DepositEvent {
     pub total_liquidity: u64
 }
 
+#[event]
+pub struct DepositPositionEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Liquidity position
+    #[index]
+    pub position: Pubkey,
+    // Absorbed user liquidity position
+    #[index]
+    pub user_position: Pubkey,
+    // Deposit liquidity amount
+    pub deposit_liquidity: u64,
+    // Total position liquidity
+    pub total_liquidity: u64
+}
+
 #[event]
 pub struct WithdrawEvent {
     // Signer (user)
@@ -709,3 +4763,337 @@ pub struct WithdrawEvent {
     // Total position liquidity
     pub total_liquidity: u64
 }
+
+#[event]
+pub struct WithdrawToPositionEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Liquidity position
+    #[index]
+    pub position: Pubkey,
+    // New signer liquidity position
+    #[index]
+    pub new_position: Pubkey,
+    // Withdraw liquidity amount
+    pub withdraw_liquidity: u64,
+    // Total position liquidity
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct RebalanceEvent {
+    // Signer (keeper)
+    #[index]
+    pub signer: Pubkey,
+    // Rebalanced liquidity position
+    #[index]
+    pub position: Pubkey,
+    // New liquidity position
+    #[index]
+    pub new_position: Pubkey,
+    // New position lower tick index
+    pub tick_lower_index: i32,
+    // New position upper tick index
+    pub tick_upper_index: i32,
+    // Rebalanced liquidity amount
+    pub liquidity: u64
+}
+
+#[event]
+pub struct MigratePositionEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Liquidity position
+    #[index]
+    pub position: Pubkey,
+    // Migrated lockbox v1 position
+    #[index]
+    pub v1_position: Pubkey,
+    // Migrated liquidity amount
+    pub migrated_liquidity: u64,
+    // Total position liquidity
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct SwapBridgedTokensEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // User v1 bridged token ATA
+    pub v1_bridged_token_account: Pubkey,
+    // User bridged token ATA
+    pub bridged_token_account: Pubkey,
+    // Swapped amount
+    pub amount: u64
+}
+
+#[event]
+pub struct MigrateStateEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+    // Migrated lockbox account
+    #[index]
+    pub lockbox: Pubkey,
+    // State layout version
+    pub version: u8
+}
+
+#[event]
+pub struct DecommissionEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+    // Closed lockbox account
+    #[index]
+    pub lockbox: Pubkey,
+    // Closed liquidity position
+    pub position: Pubkey,
+    // Number of closed lockbox-owned token accounts
+    pub num_token_accounts: u32
+}
+
+#[event]
+pub struct DepositLimitsEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Maximum total liquidity
+    pub max_total_liquidity: u64,
+    // Maximum liquidity per deposit
+    pub max_deposit_per_tx: u64,
+    // Minimum liquidity per deposit
+    pub min_deposit: u64
+}
+
+#[event]
+pub struct UserDepositCapEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Maximum cumulative liquidity deposited per user
+    pub max_user_deposit: u64
+}
+
+#[event]
+pub struct AllowlistRootEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Allowlist Merkle root
+    pub allowlist_root: [u8; 32]
+}
+
+#[event]
+pub struct WithdrawRateLimitEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Window length in slots
+    pub withdraw_window_slots: u64,
+    // Maximum liquidity withdrawn per window
+    pub max_withdraw_per_window: u64
+}
+
+#[event]
+pub struct RequestWithdrawEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Withdraw request account
+    #[index]
+    pub withdraw_request: Pubkey,
+    // Requested liquidity amount
+    pub amount: u64,
+    // Slot from which the withdrawal can be executed
+    pub unlock_slot: u64
+}
+
+#[event]
+pub struct ExecuteWithdrawEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Withdraw request account
+    #[index]
+    pub withdraw_request: Pubkey,
+    // User ATA token A
+    pub token_owner_account_a: Pubkey,
+    // User ATA token B
+    pub token_owner_account_b: Pubkey,
+    // Withdraw liquidity amount
+    pub withdraw_liquidity: u64,
+    // Total position liquidity
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct CancelWithdrawEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+    // Withdraw request requester
+    #[index]
+    pub requester: Pubkey,
+    // Withdraw request account
+    pub withdraw_request: Pubkey,
+    // Liquidity amount of bridged tokens minted back
+    pub amount: u64
+}
+
+#[event]
+pub struct WithdrawCooldownEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Queued withdrawal cooldown in slots
+    pub withdraw_cooldown_slots: u64
+}
+
+#[event]
+pub struct RebalanceParamsEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Rebalance keeper
+    pub rebalance_keeper: Pubkey,
+    // Minimum number of slots between rebalances
+    pub rebalance_interval_slots: u64,
+    // Maximum rebalance swap move of the whirlpool price in basis points
+    pub max_rebalance_slippage_bps: u16
+}
+
+#[event]
+pub struct EmergencyExitEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+    // Lockbox emergency token account A
+    pub lockbox_token_account_a: Pubkey,
+    // Lockbox emergency token account B
+    pub lockbox_token_account_b: Pubkey,
+    // Emergency token account A balance
+    pub amount_a: u64,
+    // Emergency token account B balance
+    pub amount_b: u64,
+    // Total liquidity backed by the emergency token accounts
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct EmergencyRedeemEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // User ATA token A
+    pub token_owner_account_a: Pubkey,
+    // User ATA token B
+    pub token_owner_account_b: Pubkey,
+    // Redeemed bridged token amount
+    pub amount: u64,
+    // Token A amount received
+    pub amount_a: u64,
+    // Token B amount received
+    pub amount_b: u64,
+    // Remaining total liquidity
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct DepositAndBridgeEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Liquidity position
+    #[index]
+    pub position: Pubkey,
+    // Deposit liquidity amount
+    pub deposit_liquidity: u64,
+    // Total position liquidity
+    pub total_liquidity: u64,
+    // Wormhole target chain id
+    pub target_chain: u16,
+    // Recipient address on the target chain
+    pub target_address: [u8; 32],
+    // Wormhole message account
+    pub wormhole_message: Pubkey
+}
+
+#[event]
+pub struct BridgeProgramsEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Wormhole token bridge program
+    pub token_bridge: Pubkey,
+    // Wormhole core bridge program
+    pub core_bridge: Pubkey
+}
+
+#[event]
+pub struct CompleteAndWithdrawEvent {
+    // Signer (user)
+    #[index]
+    pub signer: Pubkey,
+    // Liquidity position
+    #[index]
+    pub position: Pubkey,
+    // Posted VAA of the completed transfer
+    pub posted_vaa: Pubkey,
+    // User ATA token A
+    pub token_owner_account_a: Pubkey,
+    // User ATA token B
+    pub token_owner_account_b: Pubkey,
+    // Withdraw liquidity amount
+    pub withdraw_liquidity: u64,
+    // Total position liquidity
+    pub total_liquidity: u64
+}
+
+#[event]
+pub struct GovernanceEmitterEvent {
+    // Admin (program upgrade authority)
+    #[index]
+    pub admin: Pubkey,
+
+    // Wormhole chain id of the governance emitter
+    pub governance_chain: u16,
+    // Governance emitter address
+    pub governance_emitter: [u8; 32]
+}
+
+#[event]
+pub struct ExecuteGovernanceEvent {
+    // Posted VAA of the governance message
+    #[index]
+    pub posted_vaa: Pubkey,
+    // Governance message sequence
+    pub sequence: u64
+}
+
+#[event]
+pub struct PublishReservesEvent {
+    // Signer
+    #[index]
+    pub signer: Pubkey,
+    // Wormhole message account
+    pub wormhole_message: Pubkey,
+    // Total liquidity
+    pub total_liquidity: u64,
+    // Whirlpool position liquidity
+    pub position_liquidity: u128,
+    // Bridged token supply
+    pub bridged_token_supply: u64,
+    // Slot of the reserves snapshot
+    pub slot: u64
+}
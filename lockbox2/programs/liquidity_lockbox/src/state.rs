@@ -1,9 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use crate::ErrorCode;
 
 #[account]
 pub struct LiquidityLockbox {
   // Lockbox bump
   pub bump: [u8; 1],
+  // State layout version, right after the bump such that it can be read before the account is deserialized
+  pub version: u8,
   // Bridged token mint address
   pub bridged_token_mint: Pubkey,
   // Fee collector ATA for token A
@@ -15,11 +19,65 @@ pub struct LiquidityLockbox {
   // PDA position ATA
   pub pda_position_account: Pubkey,
   // Total liquidity
-  pub total_liquidity: u64
+  pub total_liquidity: u64,
+  // Maximum total liquidity, zero if not capped
+  pub max_total_liquidity: u64,
+  // Maximum liquidity per deposit, zero if not capped
+  pub max_deposit_per_tx: u64,
+  // Minimum liquidity per deposit
+  pub min_deposit: u64,
+  // Maximum cumulative liquidity deposited per user, zero if not capped
+  pub max_user_deposit: u64,
+  // Depositor allowlist Merkle root, zero if the allowlist mode is disabled
+  pub allowlist_root: [u8; 32],
+  // Withdrawal rate limit window length in slots, zero if withdrawals are not rate limited
+  pub withdraw_window_slots: u64,
+  // Maximum liquidity withdrawn per window
+  pub max_withdraw_per_window: u64,
+  // Start slot of the current withdrawal window
+  pub withdraw_window_start: u64,
+  // Liquidity withdrawn in the current window
+  pub withdrawn_in_window: u64,
+  // Queued withdrawal cooldown in slots
+  pub withdraw_cooldown_slots: u64,
+  // Emergency mode flag, set when the position liquidity is moved into the emergency token accounts
+  pub emergency_mode: bool,
+  // Emergency token account A backing the total liquidity in the emergency mode
+  pub emergency_token_account_a: Pubkey,
+  // Emergency token account B backing the total liquidity in the emergency mode
+  pub emergency_token_account_b: Pubkey,
+  // Wormhole token bridge program, default if bridging is not configured
+  pub token_bridge: Pubkey,
+  // Wormhole core bridge program
+  pub core_bridge: Pubkey,
+  // Paused flag, set by the governance to halt deposits and withdrawals
+  pub paused: bool,
+  // Wormhole chain id of the governance emitter
+  pub governance_chain: u16,
+  // Governance emitter address, zero if the cross-chain governance is disabled
+  pub governance_emitter: [u8; 32],
+  // Rebalance keeper, default if rebalancing is disabled
+  pub rebalance_keeper: Pubkey,
+  // Minimum number of slots between rebalances
+  pub rebalance_interval_slots: u64,
+  // Maximum rebalance swap move of the whirlpool price in basis points
+  pub max_rebalance_slippage_bps: u16,
+  // Slot of the last rebalance
+  pub last_rebalance_slot: u64,
+  // Lockbox whirlpool
+  pub whirlpool: Pubkey,
+  // Liquidity reserved for requested queued withdrawals
+  pub pending_withdraw_liquidity: u64
 }
 
 impl LiquidityLockbox {
-  pub const LEN: usize = 8 + 1 + 32 * 5 + 8;
+  pub const LEN: usize = 8 + 1 + 1 + 32 * 5 + 8 + 8 * 4 + 32 + 8 * 5 + 1 + 32 * 4 + 1 + 2 + 32 + 32 + 8 + 2 + 8 + 32 + 8;
+  // Length of the account created before the state layout versioning
+  pub const LEN_V0: usize = 8 + 1 + 32 * 5 + 8;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+  // Offset of the version field in the account data
+  pub const VERSION_OFFSET: usize = 8 + 1;
 
   pub fn seeds(&self) -> [&[u8]; 2] {
     [
@@ -35,7 +93,8 @@ impl LiquidityLockbox {
     fee_collector_token_owner_account_a: Pubkey,
     fee_collector_token_owner_account_b: Pubkey,
     position: Pubkey,
-    pda_position_account: Pubkey
+    pda_position_account: Pubkey,
+    whirlpool: Pubkey
   ) -> Result<()> {
     self.bridged_token_mint = bridged_token_mint;
     self.fee_collector_token_owner_account_a = fee_collector_token_owner_account_a;
@@ -43,7 +102,434 @@ impl LiquidityLockbox {
     self.position = position;
     self.pda_position_account = pda_position_account;
     self.total_liquidity = 0;
+    self.max_total_liquidity = 0;
+    self.max_deposit_per_tx = 0;
+    self.min_deposit = 0;
+    self.max_user_deposit = 0;
+    self.allowlist_root = [0u8; 32];
+    self.withdraw_window_slots = 0;
+    self.max_withdraw_per_window = 0;
+    self.withdraw_window_start = 0;
+    self.withdrawn_in_window = 0;
+    self.withdraw_cooldown_slots = 0;
+    self.emergency_mode = false;
+    self.emergency_token_account_a = Pubkey::default();
+    self.emergency_token_account_b = Pubkey::default();
+    self.token_bridge = Pubkey::default();
+    self.core_bridge = Pubkey::default();
+    self.paused = false;
+    self.governance_chain = 0;
+    self.governance_emitter = [0u8; 32];
+    self.rebalance_keeper = Pubkey::default();
+    self.rebalance_interval_slots = 0;
+    self.max_rebalance_slippage_bps = 0;
+    self.last_rebalance_slot = 0;
+    self.whirlpool = whirlpool;
+    self.pending_withdraw_liquidity = 0;
+    self.bump = [bump];
+    self.version = Self::VERSION;
+
+    Ok(())
+  }
+
+  pub fn check_version(&self) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    Ok(())
+  }
+
+  /// Checks the deposit liquidity against the configured deposit limits.
+  pub fn check_deposit_limits(&self, liquidity: u64) -> Result<()> {
+    if liquidity < self.min_deposit {
+      return Err(ErrorCode::DepositBelowMinimum.into());
+    }
+
+    if self.max_deposit_per_tx > 0 && liquidity > self.max_deposit_per_tx {
+      return Err(ErrorCode::DepositExceedsLimit.into());
+    }
+
+    if self.max_total_liquidity > 0 {
+      match self.total_liquidity.checked_add(liquidity) {
+        Some(new_liquidity) if new_liquidity <= self.max_total_liquidity => {},
+        _ => return Err(ErrorCode::TotalLiquidityCapExceeded.into()),
+      }
+    }
+
+    Ok(())
+  }
+
+  pub fn set_deposit_limits(
+    &mut self,
+    max_total_liquidity: u64,
+    max_deposit_per_tx: u64,
+    min_deposit: u64
+  ) -> Result<()> {
+    // The per-transaction limit cannot be below the minimum deposit
+    if max_deposit_per_tx > 0 && max_deposit_per_tx < min_deposit {
+      return Err(ErrorCode::WrongDepositLimits.into());
+    }
+
+    self.max_total_liquidity = max_total_liquidity;
+    self.max_deposit_per_tx = max_deposit_per_tx;
+    self.min_deposit = min_deposit;
+
+    Ok(())
+  }
+
+  /// Checks the user against the allowlist Merkle root, if the allowlist mode is enabled.
+  /// Leaves are keccak hashes of user addresses, and node pairs are hashed in the sorted order.
+  pub fn check_allowlist(&self, user: &Pubkey, proof: &[[u8; 32]]) -> Result<()> {
+    // Zero root disables the allowlist mode
+    if self.allowlist_root == [0u8; 32] {
+      return Ok(());
+    }
+
+    let mut node = keccak::hash(user.as_ref()).to_bytes();
+    for sibling in proof {
+      node = if node <= *sibling {
+        keccak::hashv(&[&node, sibling]).to_bytes()
+      } else {
+        keccak::hashv(&[sibling, &node]).to_bytes()
+      };
+    }
+
+    if node != self.allowlist_root {
+      return Err(ErrorCode::NotAllowlisted.into());
+    }
+
+    Ok(())
+  }
+
+  /// Records the withdrawn liquidity against the withdrawal rate limit of the current window.
+  pub fn record_withdraw(&mut self, liquidity: u64, slot: u64) -> Result<()> {
+    // Zero window length disables the withdrawal rate limit
+    if self.withdraw_window_slots == 0 {
+      return Ok(());
+    }
+
+    // Start a new window when the current one has elapsed
+    if slot >= self.withdraw_window_start.saturating_add(self.withdraw_window_slots) {
+      self.withdraw_window_start = slot;
+      self.withdrawn_in_window = 0;
+    }
+
+    let withdrawn_in_window = self.withdrawn_in_window.checked_add(liquidity)
+      .ok_or(ErrorCode::LiquidityOverflow)?;
+    if withdrawn_in_window > self.max_withdraw_per_window {
+      return Err(ErrorCode::WithdrawRateLimitExceeded.into());
+    }
+    self.withdrawn_in_window = withdrawn_in_window;
+
+    Ok(())
+  }
+
+  /// Gets the liquidity not reserved for requested queued withdrawals.
+  pub fn available_liquidity(&self) -> u64 {
+    self.total_liquidity.saturating_sub(self.pending_withdraw_liquidity)
+  }
+
+  /// Reserves the liquidity for a requested queued withdrawal.
+  pub fn reserve_withdraw(&mut self, liquidity: u64) -> Result<()> {
+    if liquidity > self.available_liquidity() {
+      return Err(ErrorCode::AmountExceedsTotalLiquidity.into());
+    }
+    self.pending_withdraw_liquidity += liquidity;
+
+    Ok(())
+  }
+
+  /// Releases the liquidity reserved for an executed or cancelled queued withdrawal.
+  pub fn release_withdraw(&mut self, liquidity: u64) -> Result<()> {
+    self.pending_withdraw_liquidity = self.pending_withdraw_liquidity.checked_sub(liquidity)
+      .ok_or(ErrorCode::LiquidityUnderflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_withdraw_rate_limit(&mut self, withdraw_window_slots: u64, max_withdraw_per_window: u64) -> Result<()> {
+    self.withdraw_window_slots = withdraw_window_slots;
+    self.max_withdraw_per_window = max_withdraw_per_window;
+    // Restart the window such that the new limit applies from the next withdrawal
+    self.withdraw_window_start = 0;
+    self.withdrawn_in_window = 0;
+
+    Ok(())
+  }
+
+  /// Checks the rebalance keeper and the minimum interval since the last rebalance, and records the rebalance slot.
+  pub fn record_rebalance(&mut self, keeper: &Pubkey, slot: u64) -> Result<()> {
+    // Default keeper disables rebalancing
+    if self.rebalance_keeper == Pubkey::default() {
+      return Err(ErrorCode::RebalanceNotConfigured.into());
+    }
+
+    if *keeper != self.rebalance_keeper {
+      return Err(ErrorCode::WrongRebalanceKeeper.into());
+    }
+
+    if self.last_rebalance_slot > 0 && slot < self.last_rebalance_slot.saturating_add(self.rebalance_interval_slots) {
+      return Err(ErrorCode::RebalanceTooFrequent.into());
+    }
+    self.last_rebalance_slot = slot;
+
+    Ok(())
+  }
+
+  pub fn set_rebalance_params(
+    &mut self,
+    rebalance_keeper: Pubkey,
+    rebalance_interval_slots: u64,
+    max_rebalance_slippage_bps: u16
+  ) -> Result<()> {
+    // The slippage cannot exceed the whole price
+    if max_rebalance_slippage_bps > 10_000 {
+      return Err(ErrorCode::WrongRebalanceParams.into());
+    }
+
+    self.rebalance_keeper = rebalance_keeper;
+    self.rebalance_interval_slots = rebalance_interval_slots;
+    self.max_rebalance_slippage_bps = max_rebalance_slippage_bps;
+
+    Ok(())
+  }
+
+  /// Applies the governance action received from the governance emitter.
+  pub fn apply_governance_action(&mut self, action: &GovernanceAction) -> Result<()> {
+    match *action {
+      GovernanceAction::SetPaused { paused } => {
+        self.paused = paused;
+      },
+      GovernanceAction::SetDepositLimits { max_total_liquidity, max_deposit_per_tx, min_deposit } => {
+        self.set_deposit_limits(max_total_liquidity, max_deposit_per_tx, min_deposit)?;
+      },
+      GovernanceAction::SetUserDepositCap { max_user_deposit } => {
+        self.max_user_deposit = max_user_deposit;
+      },
+      GovernanceAction::SetWithdrawRateLimit { withdraw_window_slots, max_withdraw_per_window } => {
+        self.set_withdraw_rate_limit(withdraw_window_slots, max_withdraw_per_window)?;
+      },
+      GovernanceAction::SetFeeCollectors { fee_collector_token_owner_account_a, fee_collector_token_owner_account_b } => {
+        self.fee_collector_token_owner_account_a = fee_collector_token_owner_account_a;
+        self.fee_collector_token_owner_account_b = fee_collector_token_owner_account_b;
+      },
+    }
+
+    Ok(())
+  }
+}
+
+// Lockbox account layout before the state layout versioning
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LiquidityLockboxV0 {
+  pub bump: [u8; 1],
+  pub bridged_token_mint: Pubkey,
+  pub fee_collector_token_owner_account_a: Pubkey,
+  pub fee_collector_token_owner_account_b: Pubkey,
+  pub position: Pubkey,
+  pub pda_position_account: Pubkey,
+  pub total_liquidity: u64
+}
+
+impl From<LiquidityLockboxV0> for LiquidityLockbox {
+  fn from(lockbox: LiquidityLockboxV0) -> Self {
+    Self {
+      bump: lockbox.bump,
+      version: Self::VERSION,
+      bridged_token_mint: lockbox.bridged_token_mint,
+      fee_collector_token_owner_account_a: lockbox.fee_collector_token_owner_account_a,
+      fee_collector_token_owner_account_b: lockbox.fee_collector_token_owner_account_b,
+      position: lockbox.position,
+      pda_position_account: lockbox.pda_position_account,
+      total_liquidity: lockbox.total_liquidity,
+      max_total_liquidity: 0,
+      max_deposit_per_tx: 0,
+      min_deposit: 0,
+      max_user_deposit: 0,
+      allowlist_root: [0u8; 32],
+      withdraw_window_slots: 0,
+      max_withdraw_per_window: 0,
+      withdraw_window_start: 0,
+      withdrawn_in_window: 0,
+      withdraw_cooldown_slots: 0,
+      emergency_mode: false,
+      emergency_token_account_a: Pubkey::default(),
+      emergency_token_account_b: Pubkey::default(),
+      token_bridge: Pubkey::default(),
+      core_bridge: Pubkey::default(),
+      paused: false,
+      governance_chain: 0,
+      governance_emitter: [0u8; 32],
+      rebalance_keeper: Pubkey::default(),
+      rebalance_interval_slots: 0,
+      max_rebalance_slippage_bps: 0,
+      last_rebalance_slot: 0,
+      // The whirlpool is set from the lockbox position during the migration
+      whirlpool: Pubkey::default(),
+      pending_withdraw_liquidity: 0
+    }
+  }
+}
+
+#[account]
+pub struct UserState {
+  // User state bump
+  pub bump: [u8; 1],
+  // State layout version
+  pub version: u8,
+  // Lockbox the user state belongs to
+  pub lockbox: Pubkey,
+  // User address
+  pub user: Pubkey,
+  // Cumulative liquidity deposited by the user
+  pub total_deposited: u64,
+  // Cumulative liquidity withdrawn by the user
+  pub total_withdrawn: u64
+}
+
+impl UserState {
+  pub const LEN: usize = 8 + 1 + 1 + 32 + 32 + 8 + 8;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+
+  /// Initializes the user state on its creation, otherwise checks its layout version.
+  pub fn initialize_if_needed(&mut self, bump: u8, lockbox: Pubkey, user: Pubkey) -> Result<()> {
+    if self.version == 0 {
+      self.bump = [bump];
+      self.lockbox = lockbox;
+      self.user = user;
+      self.total_deposited = 0;
+      self.total_withdrawn = 0;
+      self.version = Self::VERSION;
+    }
+
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    Ok(())
+  }
+
+  /// Records the deposited liquidity, checking the user cumulative deposited liquidity against the user cap.
+  /// Withdrawals do not free up the cap since bridged tokens are fungible and can be acquired from other holders.
+  pub fn record_deposit(&mut self, liquidity: u64, max_user_deposit: u64) -> Result<()> {
+    let total_deposited = self.total_deposited.checked_add(liquidity)
+      .ok_or(ErrorCode::LiquidityOverflow)?;
+
+    if max_user_deposit > 0 && total_deposited > max_user_deposit {
+      return Err(ErrorCode::UserDepositCapExceeded.into());
+    }
+
+    self.total_deposited = total_deposited;
+
+    Ok(())
+  }
+
+  /// Records the withdrawn liquidity, which is tracked for accounting only and does not affect the user cap.
+  pub fn record_withdraw(&mut self, liquidity: u64) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    self.total_withdrawn = self.total_withdrawn.checked_add(liquidity)
+      .ok_or(ErrorCode::LiquidityOverflow)?;
+
+    Ok(())
+  }
+}
+
+#[account]
+pub struct WithdrawRequest {
+  // Withdraw request bump
+  pub bump: [u8; 1],
+  // State layout version
+  pub version: u8,
+  // Requester address
+  pub requester: Pubkey,
+  // Requested liquidity amount of burned bridged tokens
+  pub amount: u64,
+  // Request slot
+  pub request_slot: u64,
+  // Slot from which the withdrawal can be executed
+  pub unlock_slot: u64
+}
+
+impl WithdrawRequest {
+  pub const LEN: usize = 8 + 1 + 1 + 32 + 8 + 8 + 8;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+
+  pub fn initialize(
+    &mut self,
+    bump: u8,
+    requester: Pubkey,
+    amount: u64,
+    request_slot: u64,
+    cooldown_slots: u64
+  ) -> Result<()> {
+    self.bump = [bump];
+    self.requester = requester;
+    self.amount = amount;
+    self.request_slot = request_slot;
+    self.unlock_slot = request_slot.saturating_add(cooldown_slots);
+    self.version = Self::VERSION;
+
+    Ok(())
+  }
+
+  pub fn check_version(&self) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(ErrorCode::UnsupportedVersion.into());
+    }
+
+    Ok(())
+  }
+}
+
+// Governance actions, Borsh-encoded in the governance message payload after the target program address
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub enum GovernanceAction {
+  SetPaused {
+    paused: bool
+  },
+  SetDepositLimits {
+    max_total_liquidity: u64,
+    max_deposit_per_tx: u64,
+    min_deposit: u64
+  },
+  SetUserDepositCap {
+    max_user_deposit: u64
+  },
+  SetWithdrawRateLimit {
+    withdraw_window_slots: u64,
+    max_withdraw_per_window: u64
+  },
+  SetFeeCollectors {
+    fee_collector_token_owner_account_a: Pubkey,
+    fee_collector_token_owner_account_b: Pubkey
+  }
+}
+
+#[account]
+pub struct GovernanceSequence {
+  // Governance sequence bump
+  pub bump: [u8; 1],
+  // State layout version
+  pub version: u8,
+  // Consumed governance message sequence
+  pub sequence: u64
+}
+
+impl GovernanceSequence {
+  pub const LEN: usize = 8 + 1 + 1 + 8;
+  // Current state layout version, bumped on every layout change with new fields appended at the end
+  pub const VERSION: u8 = 1;
+
+  pub fn initialize(&mut self, bump: u8, sequence: u64) -> Result<()> {
     self.bump = [bump];
+    self.sequence = sequence;
+    self.version = Self::VERSION;
 
     Ok(())
   }
@@ -0,0 +1,192 @@
+//! Wormhole core and token bridge stand-in for the localnet tests.
+//! It implements only the instructions the lockbox calls, with the same instruction indexes and account orders,
+//! and skips any guardian signature verification: posted VAAs are created directly with the `CREATE_ACCOUNT` instruction.
+use solana_program::{
+  account_info::AccountInfo,
+  entrypoint::ProgramResult,
+  program::invoke_signed,
+  program_error::ProgramError,
+  pubkey::Pubkey,
+  rent::Rent,
+  system_instruction,
+  sysvar::Sysvar
+};
+
+solana_program::declare_id!("6HCxLt7gbbyCVc4rY9ERsBA69XaRyievEZ7CpAJ1MeJU");
+
+// Creates an account owned by the mock with the supplied data, such as the core bridge config or a posted VAA
+pub const CREATE_ACCOUNT: u8 = 0;
+// Core bridge post message instruction index
+pub const POST_MESSAGE: u8 = 1;
+// Token bridge complete native instruction index
+pub const COMPLETE_NATIVE: u8 = 2;
+// Token bridge transfer native instruction index
+pub const TRANSFER_NATIVE: u8 = 5;
+// Posted VAA payload offset
+pub const POSTED_VAA_PAYLOAD_OFFSET: usize = 91;
+// Transfer payload identifier
+pub const TRANSFER_PAYLOAD_ID: u8 = 1;
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  let (instruction, data) = data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+  match *instruction {
+    CREATE_ACCOUNT => create_account(program_id, accounts, data),
+    POST_MESSAGE => post_message(program_id, accounts, data),
+    COMPLETE_NATIVE => complete_native(program_id, accounts),
+    TRANSFER_NATIVE => transfer_native(program_id, accounts, data),
+    _ => Err(ProgramError::InvalidInstructionData)
+  }
+}
+
+// Accounts: payer, new account, system program
+fn create_account(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  if accounts.len() < 3 {
+    return Err(ProgramError::NotEnoughAccountKeys);
+  }
+
+  create_owned_account(program_id, &accounts[0], &accounts[1], &accounts[2], data, &[])
+}
+
+// Accounts: bridge, message, emitter, sequence, payer, fee collector, clock, rent, system program
+// The message account data is the "msg" magic, the emitter address and the payload
+fn post_message(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  if accounts.len() < 9 {
+    return Err(ProgramError::NotEnoughAccountKeys);
+  }
+
+  let emitter = &accounts[2];
+  if !emitter.is_signer {
+    return Err(ProgramError::MissingRequiredSignature);
+  }
+
+  // Skip the nonce and get the payload
+  let payload_len = u32::from_le_bytes(read_array(data, 4)?) as usize;
+  let payload = data.get(8..8 + payload_len).ok_or(ProgramError::InvalidInstructionData)?;
+
+  let mut message = b"msg".to_vec();
+  message.extend_from_slice(emitter.key.as_ref());
+  message.extend_from_slice(payload);
+  create_owned_account(program_id, &accounts[4], &accounts[1], &accounts[8], &message, &[])
+}
+
+// Accounts: payer, config, from, mint, custody, authority signer, custody signer, bridge, message, emitter, sequence,
+// fee collector, clock, rent, system program, core bridge program, token program
+// Moves the delegated tokens into the custody and posts the transfer payload:
+// payload id, amount (u64 BE), recipient address and recipient chain (u16 BE)
+fn transfer_native(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  if accounts.len() < 17 {
+    return Err(ProgramError::NotEnoughAccountKeys);
+  }
+
+  // Skip the nonce and get the amount, then skip the relayer fee and get the recipient
+  let amount = u64::from_le_bytes(read_array(data, 4)?);
+  let target_address: [u8; 32] = read_array(data, 20)?;
+  let target_chain = u16::from_le_bytes(read_array(data, 52)?);
+
+  let (authority_signer, bump) = Pubkey::find_program_address(&[b"authority_signer"], program_id);
+  if accounts[5].key != &authority_signer {
+    return Err(ProgramError::InvalidSeeds);
+  }
+
+  invoke_signed(
+    &spl_token::instruction::transfer(accounts[16].key, accounts[2].key, accounts[4].key, &authority_signer, &[], amount)?,
+    &[accounts[2].clone(), accounts[4].clone(), accounts[5].clone(), accounts[16].clone()],
+    &[&[b"authority_signer", &[bump]]]
+  )?;
+
+  let mut message = b"msg".to_vec();
+  message.extend_from_slice(accounts[9].key.as_ref());
+  message.push(TRANSFER_PAYLOAD_ID);
+  message.extend_from_slice(&amount.to_be_bytes());
+  message.extend_from_slice(&target_address);
+  message.extend_from_slice(&target_chain.to_be_bytes());
+  create_owned_account(program_id, &accounts[0], &accounts[8], &accounts[14], &message, &[])
+}
+
+// Accounts: payer, config, posted VAA, claim, endpoint, to, relayer fee, custody, mint, custody signer, rent,
+// system program, token program, core bridge program
+// Releases the custody tokens to the recipient of the posted VAA transfer payload, and creates the claim PDA
+// of the posted VAA such that it cannot be replayed
+fn complete_native(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+  if accounts.len() < 14 {
+    return Err(ProgramError::NotEnoughAccountKeys);
+  }
+
+  let posted_vaa = &accounts[2];
+  let (amount, to) = {
+    let data = posted_vaa.try_borrow_data()?;
+    if posted_vaa.owner != program_id || data.len() < POSTED_VAA_PAYLOAD_OFFSET + 4 || &data[0..3] != b"vaa" {
+      return Err(ProgramError::InvalidAccountData);
+    }
+
+    let payload = &data[POSTED_VAA_PAYLOAD_OFFSET + 4..];
+    if payload.first() != Some(&TRANSFER_PAYLOAD_ID) {
+      return Err(ProgramError::InvalidAccountData);
+    }
+    (u64::from_be_bytes(read_array(payload, 1)?), Pubkey::new_from_array(read_array(payload, 9)?))
+  };
+
+  if accounts[5].key != &to {
+    return Err(ProgramError::InvalidArgument);
+  }
+
+  // Claim the posted VAA
+  let (claim, claim_bump) = Pubkey::find_program_address(&[b"claim", posted_vaa.key.as_ref()], program_id);
+  if accounts[3].key != &claim {
+    return Err(ProgramError::InvalidSeeds);
+  }
+  create_owned_account(
+    program_id,
+    &accounts[0],
+    &accounts[3],
+    &accounts[11],
+    &[1],
+    &[&[b"claim", posted_vaa.key.as_ref(), &[claim_bump]]]
+  )?;
+
+  let (custody_signer, bump) = Pubkey::find_program_address(&[b"custody_signer"], program_id);
+  if accounts[9].key != &custody_signer {
+    return Err(ProgramError::InvalidSeeds);
+  }
+
+  invoke_signed(
+    &spl_token::instruction::transfer(accounts[12].key, accounts[7].key, accounts[5].key, &custody_signer, &[], amount)?,
+    &[accounts[7].clone(), accounts[5].clone(), accounts[9].clone(), accounts[12].clone()],
+    &[&[b"custody_signer", &[bump]]]
+  )
+}
+
+// Creates a rent exempt account owned by the program with the supplied data
+fn create_owned_account<'info>(
+  program_id: &Pubkey,
+  payer: &AccountInfo<'info>,
+  account: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+  data: &[u8],
+  signer_seeds: &[&[&[u8]]]
+) -> ProgramResult {
+  invoke_signed(
+    &system_instruction::create_account(
+      payer.key,
+      account.key,
+      Rent::get()?.minimum_balance(data.len()),
+      data.len() as u64,
+      program_id
+    ),
+    &[payer.clone(), account.clone(), system_program.clone()],
+    signer_seeds
+  )?;
+  account.try_borrow_mut_data()?.copy_from_slice(data);
+
+  Ok(())
+}
+
+// Reads a fixed size array at the offset
+fn read_array<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], ProgramError> {
+  data.get(offset..offset + N)
+    .and_then(|slice| slice.try_into().ok())
+    .ok_or(ProgramError::InvalidInstructionData)
+}
@@ -26,7 +26,7 @@ async function main() {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
 
-  const PROGRAM_ID = new anchor.web3.PublicKey("7ahQGWysExobjeZ91RTsNqTCN3kWyHGZ43ud2vB7VVoZ");
+  const PROGRAM_ID = new anchor.web3.PublicKey("1BoXeb8hobfLCHNsyCoG1jpEv41ez4w4eDrJ48N1jY3");
   const program = new Program(idl as anchor.Idl, PROGRAM_ID, anchor.getProvider()) as Program<LiquidityLockbox>;
 
   const orca = new anchor.web3.PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
@@ -262,7 +262,7 @@ async function main() {
 
     // Execute the correct deposit tx
     try {
-        signature = await program.methods.deposit(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB)
+        signature = await program.methods.deposit(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB, [])
           .accounts(
               {
                 position: position,
@@ -313,7 +313,7 @@ async function main() {
 
     // Execute the second correct deposit tx
     try {
-        signature = await program.methods.deposit(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB)
+        signature = await program.methods.deposit(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB, [])
           .accounts(
               {
                 position: position,
@@ -0,0 +1,96 @@
+import * as anchor from "@coral-xyz/anchor";
+import { keccak_256 } from "@noble/hashes/sha3";
+import expect from "expect";
+import { confirm, setupLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const { provider, program, userWallet, pdaProgram, adminAccounts, deposit } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  async function setAllowlistRoot(root: Buffer, signer = userWallet) {
+    const signature = await program.methods.setAllowlistRoot(Array.from(root))
+      .accounts(adminAccounts(signer))
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // Allowlist leaves are keccak hashes of user addresses, and node pairs are hashed in the sorted order
+  function leaf(user: anchor.web3.PublicKey) {
+    return Buffer.from(keccak_256(user.toBuffer()));
+  }
+
+  function hashPair(a: Buffer, b: Buffer) {
+    return Buffer.from(keccak_256(Buffer.compare(a, b) <= 0 ? Buffer.concat([a, b]) : Buffer.concat([b, a])));
+  }
+
+  const leafUser = leaf(userWallet.publicKey);
+  const leaves = [1, 2, 3].map(() => leaf(anchor.web3.Keypair.generate().publicKey));
+
+  // ############################## ADMIN ##############################
+  console.log("\nSetting the allowlist root");
+
+  // Try to set the allowlist root by someone other than the program upgrade authority
+  try {
+      await setAllowlistRoot(leaves[0], anchor.web3.Keypair.generate());
+      throw new Error("Set allowlist root must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongAdmin");
+  }
+
+  // ############################## NOT ALLOWLISTED ##############################
+  console.log("\nRejecting deposits of users out of the allowlist");
+
+  await setAllowlistRoot(leaves[0]);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(Buffer.from(lockboxStateData.allowlistRoot).toString("hex")).toEqual(leaves[0].toString("hex"));
+
+  // Try to deposit by a user out of the allowlist
+  try {
+      await deposit("10" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("NotAllowlisted");
+  }
+
+  // ############################## ALLOWLISTED ##############################
+  console.log("\nDepositing by an allowlisted user");
+
+  // Four users tree: the user proof is the sibling leaf and the other subtree node
+  const root = hashPair(hashPair(leafUser, leaves[0]), hashPair(leaves[1], leaves[2]));
+  const proof = [leaves[0], hashPair(leaves[1], leaves[2])];
+  await setAllowlistRoot(root);
+
+  // Try to deposit without a proof
+  try {
+      await deposit("10" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("NotAllowlisted");
+  }
+
+  // Try to deposit with a proof of another user
+  try {
+      await deposit("10" /* olas */, [leafUser, hashPair(leaves[1], leaves[2])].map((node) => Array.from(node)));
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("NotAllowlisted");
+  }
+
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  const liquidity = await deposit("10" /* olas */, proof.map((node) => Array.from(node)));
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.add(liquidity).toString());
+
+  // Zero root disables the allowlist mode
+  await setAllowlistRoot(Buffer.alloc(32));
+  await deposit("10" /* olas */);
+
+  console.log("Successfully tested the depositor allowlist");
+}
+
+main();
@@ -0,0 +1,243 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getOrCreateAssociatedTokenAccount, getAccount } from "@solana/spl-token";
+import expect from "expect";
+import {
+  MOCK_BRIDGE_ID, orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper, confirm, setupMockCoreBridge, setupLockbox
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test uses the mock bridge program of programs/mock_bridge as both the Wormhole core and token bridge
+async function main() {
+  const {
+    provider, program, userWallet, pdaProgram, adminAccounts, position, pdaPositionAccount, bridgedTokenMint, positionMint,
+    tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, quoteFor
+  } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  let signature = null;
+
+  // ############################## MOCK BRIDGE SETUP ##############################
+  console.log("\nSetting up the mock bridge");
+
+  // Mock bridge PDAs
+  const [authoritySigner] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("authority_signer", "utf-8")], MOCK_BRIDGE_ID);
+  const [custodySigner] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("custody_signer", "utf-8")], MOCK_BRIDGE_ID);
+  const [tokenBridgeEmitter] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("emitter", "utf-8")], MOCK_BRIDGE_ID);
+  const [tokenBridgeConfig] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("config", "utf-8")], MOCK_BRIDGE_ID);
+  const [tokenBridgeSequence] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("Sequence", "utf-8"), tokenBridgeEmitter.toBuffer()], MOCK_BRIDGE_ID
+  );
+
+  const messageFee = 5000;
+  const { wormholeBridge, wormholeFeeCollector } = await setupMockCoreBridge(provider, userWallet, messageFee);
+
+  // Token bridge custody of the bridged token, and the lockbox escrow bridged token account
+  const tokenBridgeCustody = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      custodySigner,
+      true
+  );
+  const pdaBridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      pdaProgram,
+      true
+  );
+
+  const depositAndBridgeAccounts = {
+    position,
+    positionMint,
+    pdaPositionAccount,
+    whirlpool,
+    tokenOwnerAccountA: tokenOwnerAccountA.address,
+    tokenOwnerAccountB: tokenOwnerAccountB.address,
+    tokenVaultA,
+    tokenVaultB,
+    tickArrayLower,
+    tickArrayUpper,
+    bridgedTokenMint,
+    pdaBridgedTokenAccount: pdaBridgedTokenAccount.address,
+    lockbox: pdaProgram,
+    whirlpoolProgram: orca,
+    tokenBridgeConfig,
+    tokenBridgeCustody: tokenBridgeCustody.address,
+    tokenBridgeAuthoritySigner: authoritySigner,
+    tokenBridgeCustodySigner: custodySigner,
+    tokenBridgeEmitter,
+    tokenBridgeSequence,
+    wormholeBridge: wormholeBridge.publicKey,
+    wormholeFeeCollector,
+    tokenBridgeProgram: MOCK_BRIDGE_ID,
+    coreBridgeProgram: MOCK_BRIDGE_ID
+  };
+
+  const quote = quoteFor("10" /* olas */);
+
+  const nonce = 7;
+  const targetChain = 2;
+  const targetAddress = Buffer.alloc(32);
+  targetAddress.write("000000000000000000000000d8da6bf26964af9d7eed9e03e53415d37aa96045", "hex");
+
+  // Try to deposit and bridge before the bridge programs are configured
+  let wormholeMessage = anchor.web3.Keypair.generate();
+  try {
+      signature = await program.methods.depositAndBridge(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB, [],
+        nonce, targetChain, Array.from(targetAddress))
+        .accounts({ ...depositAndBridgeAccounts, wormholeMessage: wormholeMessage.publicKey })
+        .signers([userWallet, wormholeMessage])
+        .rpc();
+      throw new Error("Deposit and bridge must fail");
+  } catch (error) {
+      expect(error.message).toContain("ConstraintAddress");
+  }
+
+  // Configure the mock bridge as both the token and core bridges
+  signature = await program.methods.setBridgePrograms(MOCK_BRIDGE_ID, MOCK_BRIDGE_ID)
+    .accounts(adminAccounts())
+    .rpc();
+  await confirm(provider, signature);
+
+  // ############################## DEPOSIT AND BRIDGE ##############################
+  console.log("\nDepositing liquidity and bridging the bridged tokens");
+
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidityBefore = lockboxStateData.totalLiquidity;
+  const supplyBefore = (await provider.connection.getTokenSupply(bridgedTokenMint)).value.amount;
+  const custodyBalanceBefore = (await getAccount(provider.connection, tokenBridgeCustody.address)).amount;
+  const feeCollectorBalanceBefore = await provider.connection.getBalance(wormholeFeeCollector);
+
+  signature = await program.methods.depositAndBridge(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB, [],
+    nonce, targetChain, Array.from(targetAddress))
+    .accounts({ ...depositAndBridgeAccounts, wormholeMessage: wormholeMessage.publicKey })
+    .signers([userWallet, wormholeMessage])
+    .rpc();
+  await confirm(provider, signature);
+
+  // The liquidity is added and the minted bridged tokens are locked in the token bridge custody
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidityBefore.add(quote.liquidityAmount).toString());
+  const supply = (await provider.connection.getTokenSupply(bridgedTokenMint)).value.amount;
+  expect((BigInt(supply) - BigInt(supplyBefore)).toString()).toEqual(quote.liquidityAmount.toString());
+  const custodyBalance = (await getAccount(provider.connection, tokenBridgeCustody.address)).amount;
+  expect((custodyBalance - custodyBalanceBefore).toString()).toEqual(quote.liquidityAmount.toString());
+  expect((await getAccount(provider.connection, pdaBridgedTokenAccount.address)).amount.toString()).toEqual("0");
+
+  // The core bridge message fee is paid
+  const feeCollectorBalance = await provider.connection.getBalance(wormholeFeeCollector);
+  expect(feeCollectorBalance - feeCollectorBalanceBefore).toEqual(messageFee);
+
+  // The token bridge message carries the amount and the recipient
+  const message = (await provider.connection.getAccountInfo(wormholeMessage.publicKey)).data;
+  expect(message.subarray(0, 3).toString()).toEqual("msg");
+  expect(new anchor.web3.PublicKey(message.subarray(3, 35)).toBase58()).toEqual(tokenBridgeEmitter.toBase58());
+  expect(message[35]).toEqual(1);
+  expect(message.readBigUInt64BE(36).toString()).toEqual(quote.liquidityAmount.toString());
+  expect(Buffer.compare(message.subarray(44, 76), targetAddress)).toEqual(0);
+  expect(message.readUInt16BE(76)).toEqual(targetChain);
+
+  console.log("Successfully tested deposit and bridge");
+
+  // ############################## COMPLETE AND WITHDRAW ##############################
+  console.log("\nCompleting a bridged token transfer and withdrawing liquidity");
+
+  // Post the VAA of the transfer back to the user bridged token account, skipping the guardian signatures
+  const withdrawAmount = quote.liquidityAmount.div(new anchor.BN(2));
+  const postedVaa = anchor.web3.Keypair.generate();
+  const vaaData = Buffer.alloc(95 + 43);
+  vaaData.write("vaa", 0);
+  vaaData.writeBigUInt64LE(BigInt(1), 49);
+  vaaData.writeUInt16LE(targetChain, 57);
+  vaaData.writeUInt32LE(43, 91);
+  vaaData[95] = 1;
+  vaaData.writeBigUInt64BE(BigInt(withdrawAmount.toString()), 96);
+  bridgedTokenAccount.address.toBuffer().copy(vaaData, 104);
+  vaaData.writeUInt16BE(1, 136);
+  const tx = new anchor.web3.Transaction().add(new anchor.web3.TransactionInstruction({
+    programId: MOCK_BRIDGE_ID,
+    keys: [
+      { pubkey: userWallet.publicKey, isSigner: true, isWritable: true },
+      { pubkey: postedVaa.publicKey, isSigner: true, isWritable: true },
+      { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false }
+    ],
+    data: Buffer.concat([Buffer.from([0]), vaaData])
+  }));
+  await provider.sendAndConfirm(tx, [postedVaa]);
+
+  const [tokenBridgeClaim] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("claim", "utf-8"), postedVaa.publicKey.toBuffer()], MOCK_BRIDGE_ID
+  );
+  const [tokenBridgeEndpoint] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("endpoint", "utf-8")], MOCK_BRIDGE_ID
+  );
+  const completeAndWithdrawAccounts = {
+    whirlpool,
+    bridgedTokenMint,
+    bridgedTokenAccount: bridgedTokenAccount.address,
+    position,
+    pdaPositionAccount,
+    positionMint,
+    tokenOwnerAccountA: tokenOwnerAccountA.address,
+    tokenOwnerAccountB: tokenOwnerAccountB.address,
+    feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+    feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+    tokenVaultA,
+    tokenVaultB,
+    tickArrayLower,
+    tickArrayUpper,
+    lockbox: pdaProgram,
+    whirlpoolProgram: orca,
+    relayerFeeTokenAccount: bridgedTokenAccount.address,
+    tokenBridgeConfig,
+    postedVaa: postedVaa.publicKey,
+    tokenBridgeClaim,
+    tokenBridgeEndpoint,
+    tokenBridgeCustody: tokenBridgeCustody.address,
+    tokenBridgeCustodySigner: custodySigner,
+    tokenBridgeProgram: MOCK_BRIDGE_ID,
+    coreBridgeProgram: MOCK_BRIDGE_ID
+  };
+
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  const balanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+
+  signature = await program.methods.completeAndWithdraw(new anchor.BN(0), new anchor.BN(0))
+    .accounts(completeAndWithdrawAccounts)
+    .signers([userWallet])
+    .rpc();
+  await confirm(provider, signature);
+
+  // The received bridged tokens are burned right away for the liquidity withdrawn to the user
+  expect((await getAccount(provider.connection, bridgedTokenAccount.address)).amount.toString())
+    .toEqual(bridgedBalanceBefore.toString());
+  const custodyBalanceAfter = (await getAccount(provider.connection, tokenBridgeCustody.address)).amount;
+  expect((custodyBalance - custodyBalanceAfter).toString()).toEqual(withdrawAmount.toString());
+  const supplyAfter = (await provider.connection.getTokenSupply(bridgedTokenMint)).value.amount;
+  expect((BigInt(supply) - BigInt(supplyAfter)).toString()).toEqual(withdrawAmount.toString());
+  const lockboxStateDataAfter = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateDataAfter.totalLiquidity.toString())
+    .toEqual(lockboxStateData.totalLiquidity.sub(withdrawAmount).toString());
+  expect((await getAccount(provider.connection, tokenOwnerAccountA.address)).amount).toBeGreaterThan(balanceABefore);
+  expect((await getAccount(provider.connection, tokenOwnerAccountB.address)).amount).toBeGreaterThan(balanceBBefore);
+
+  // Try to replay the same VAA
+  try {
+      signature = await program.methods.completeAndWithdraw(new anchor.BN(0), new anchor.BN(0))
+        .accounts(completeAndWithdrawAccounts)
+        .signers([userWallet])
+        .rpc();
+      throw new Error("Complete and withdraw replay must fail");
+  } catch (error) {
+      expect(error.message).not.toContain("must fail");
+  }
+
+  console.log("Successfully tested complete and withdraw");
+}
+
+main();
@@ -0,0 +1,124 @@
+import * as anchor from "@coral-xyz/anchor";
+import { createMint, getOrCreateAssociatedTokenAccount, mintTo } from "@solana/spl-token";
+import expect from "expect";
+import { orca, confirm, setupLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test drains and decommissions the lockbox, and must be run on a freshly started validator.sh
+// right after the initialization script
+async function main() {
+  const {
+    provider, program, userWallet, pdaProgram, adminAccounts, position, pdaPositionAccount, bridgedTokenMint, positionMint,
+    bridgedTokenAccount, deposit, withdraw
+  } = await setupLockbox();
+
+  let signature = null;
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing liquidity");
+
+  await deposit("10" /* olas */);
+
+  // Lockbox-owned empty token account to be closed along with the lockbox
+  const pdaBridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      pdaProgram,
+      true
+  );
+
+  const decommissionAccounts = {
+    ...adminAccounts(),
+    position,
+    positionMint,
+    pdaPositionAccount,
+    bridgedTokenMint,
+    whirlpoolProgram: orca
+  };
+  const remainingAccounts = (tokenAccount: anchor.web3.PublicKey) => [
+    { pubkey: tokenAccount, isSigner: false, isWritable: true }
+  ];
+
+  // Try to decommission the lockbox with liquidity
+  try {
+      signature = await program.methods.decommission()
+        .accounts(decommissionAccounts)
+        .remainingAccounts(remainingAccounts(pdaBridgedTokenAccount.address))
+        .rpc();
+      throw new Error("Decommission must fail");
+  } catch (error) {
+      expect(error.message).toContain("LiquidityNotZero");
+  }
+
+  // ############################## DRAIN ##############################
+  console.log("\nWithdrawing the whole liquidity");
+
+  await withdraw((await program.account.liquidityLockbox.fetch(pdaProgram)).totalLiquidity);
+
+  const lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual("0");
+  expect((await provider.connection.getTokenSupply(bridgedTokenMint)).value.amount).toEqual("0");
+
+  // ############################## DECOMMISSION ##############################
+  console.log("\nDecommissioning the drained lockbox");
+
+  // Try to close a token account that is not owned by the lockbox
+  try {
+      signature = await program.methods.decommission()
+        .accounts(decommissionAccounts)
+        .remainingAccounts(remainingAccounts(bridgedTokenAccount.address))
+        .rpc();
+      throw new Error("Decommission must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongOwner");
+  }
+
+  // Try to close a lockbox-owned token account that still holds tokens
+  const otherMint = await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8);
+  const pdaOtherTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      otherMint,
+      pdaProgram,
+      true
+  );
+  await mintTo(provider.connection, userWallet, otherMint, pdaOtherTokenAccount.address, userWallet, 1);
+  try {
+      signature = await program.methods.decommission()
+        .accounts(decommissionAccounts)
+        .remainingAccounts(remainingAccounts(pdaOtherTokenAccount.address))
+        .rpc();
+      throw new Error("Decommission must fail");
+  } catch (error) {
+      expect(error.message).toContain("TokenBalanceNotZero");
+  }
+
+  const rentAccounts = [pdaProgram, position, pdaPositionAccount, pdaBridgedTokenAccount.address];
+  let rent = 0;
+  for (const account of rentAccounts) {
+    rent += (await provider.connection.getAccountInfo(account)).lamports;
+  }
+  const adminBalanceBefore = await provider.connection.getBalance(userWallet.publicKey);
+
+  signature = await program.methods.decommission()
+    .accounts(decommissionAccounts)
+    .remainingAccounts(remainingAccounts(pdaBridgedTokenAccount.address))
+    .rpc();
+  await confirm(provider, signature);
+
+  // The lockbox, its position and token accounts are closed, and their rent is reclaimed to the admin
+  for (const account of rentAccounts) {
+    expect(await provider.connection.getAccountInfo(account)).toBeNull();
+  }
+  const adminBalance = await provider.connection.getBalance(userWallet.publicKey);
+  expect(adminBalance).toBeGreaterThan(adminBalanceBefore);
+  expect(adminBalance).toBeLessThanOrEqual(adminBalanceBefore + rent);
+
+  console.log("Successfully tested lockbox decommission");
+}
+
+main();
@@ -0,0 +1,198 @@
+import * as idl_whirlpool from "../artifacts/whirlpool.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { createMint, getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+import { increaseLiquidityQuoteByInputTokenWithParams, PDAUtil, TickUtil } from "@orca-so/whirlpools-sdk";
+import { DecimalUtil, Percentage } from "@orca-so/common-sdk";
+import Decimal from "decimal.js";
+import expect from "expect";
+import {
+  orca, olas, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper, confirm, setupLockbox
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const {
+    provider, program, userWallet, client, whirlpoolClient, whirlpool_data, token_a, token_b, pdaProgram, position,
+    pdaPositionAccount, bridgedTokenMint, positionMint, lower_tick_index, upper_tick_index, tokenOwnerAccountA,
+    tokenOwnerAccountB, bridgedTokenAccount
+  } = await setupLockbox();
+  const program_whirlpool = new Program(idl_whirlpool as anchor.Idl, orca, anchor.getProvider());
+
+  // Token max amounts cover the liquidity rounding of the deposited position
+  const tokenMax = new anchor.BN(1000000);
+
+  async function depositPosition(userPosition: anchor.web3.PublicKey, userPositionTokenAccount: anchor.web3.PublicKey) {
+    const signature = await program.methods.depositPosition(tokenMax, tokenMax, [])
+      .accounts(
+          {
+            userPosition: userPosition,
+            userPositionTokenAccount: userPositionTokenAccount,
+            position: position,
+            positionMint: positionMint,
+            pdaPositionAccount: pdaPositionAccount,
+            whirlpool: whirlpool,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: tickArrayLower,
+            tickArrayUpper: tickArrayUpper,
+            bridgedTokenMint: bridgedTokenMint,
+            bridgedTokenAccount: bridgedTokenAccount.address,
+            lockbox: pdaProgram,
+            whirlpoolProgram: orca
+          }
+      )
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // Opens a user position with the provided amount of OLAS over the provided tick range of the lockbox whirlpool
+  async function openPosition(olasAmount: string, tickLowerIndex: number, tickUpperIndex: number) {
+    const quote = increaseLiquidityQuoteByInputTokenWithParams({
+      tokenMintA: token_a.mint,
+      tokenMintB: token_b.mint,
+      sqrtPrice: whirlpool_data.sqrtPrice,
+      tickCurrentIndex: whirlpool_data.tickCurrentIndex,
+      tickLowerIndex: tickLowerIndex,
+      tickUpperIndex: tickUpperIndex,
+      inputTokenMint: olas,
+      inputTokenAmount: DecimalUtil.toBN(new Decimal(olasAmount), 8),
+      slippageTolerance: Percentage.fromFraction(10, 1000),
+    });
+    const openPositionTx = await whirlpoolClient.openPosition(tickLowerIndex, tickUpperIndex, quote);
+    await confirm(provider, await openPositionTx.tx.buildAndExecute());
+
+    const userPosition = PDAUtil.getPosition(orca, openPositionTx.positionMint).publicKey;
+    const userPositionTokenAccount = await getAssociatedTokenAddress(openPositionTx.positionMint, userWallet.publicKey);
+    return { userPosition, userPositionTokenAccount };
+  }
+
+  // ############################## WRONG TICKS ##############################
+  console.log("\nRejecting a position with a tick range other than the lockbox one");
+
+  const tickSpacing = whirlpool_data.tickSpacing;
+  const narrowPosition = await openPosition("10" /* olas */, lower_tick_index + tickSpacing, upper_tick_index - tickSpacing);
+  try {
+      await depositPosition(narrowPosition.userPosition, narrowPosition.userPositionTokenAccount);
+      throw new Error("Deposit position must fail");
+  } catch (error) {
+      expect(error.message).toContain("OutOfRange");
+  }
+
+  // ############################## WRONG HEADER ##############################
+  console.log("\nRejecting a whirlpool account other than a position");
+
+  // The tick array is owned by the whirlpool program, but is not a position
+  try {
+      await depositPosition(tickArrayLower, narrowPosition.userPositionTokenAccount);
+      throw new Error("Deposit position must fail");
+  } catch (error) {
+      expect(error.message).toContain("AccountDiscriminatorMismatch");
+  }
+
+  // ############################## WRONG WHIRLPOOL ##############################
+  console.log("\nRejecting a position of another whirlpool");
+
+  // Create a whirlpool config with a fee tier of the lockbox whirlpool tick spacing
+  const configKeypair = anchor.web3.Keypair.generate();
+  let signature = await program_whirlpool.methods.initializeConfig(
+    userWallet.publicKey, userWallet.publicKey, userWallet.publicKey, 300)
+    .accounts({ config: configKeypair.publicKey, funder: userWallet.publicKey })
+    .signers([configKeypair])
+    .rpc();
+  await confirm(provider, signature);
+
+  const feeTier = PDAUtil.getFeeTier(orca, configKeypair.publicKey, tickSpacing).publicKey;
+  signature = await program_whirlpool.methods.initializeFeeTier(tickSpacing, 3000)
+    .accounts({ config: configKeypair.publicKey, feeTier, funder: userWallet.publicKey, feeAuthority: userWallet.publicKey })
+    .rpc();
+  await confirm(provider, signature);
+
+  // Create another whirlpool of new token mints sorted as required by the whirlpool program
+  const mints = [
+    await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8),
+    await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8)
+  ].sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()));
+  const otherWhirlpoolPda = PDAUtil.getWhirlpool(orca, configKeypair.publicKey, mints[0], mints[1], tickSpacing);
+  const otherTokenVaultA = anchor.web3.Keypair.generate();
+  const otherTokenVaultB = anchor.web3.Keypair.generate();
+  signature = await program_whirlpool.methods.initializePool(
+    { whirlpoolBump: otherWhirlpoolPda.bump }, tickSpacing, new anchor.BN(1).shln(64))
+    .accounts(
+      {
+        whirlpoolsConfig: configKeypair.publicKey,
+        tokenMintA: mints[0],
+        tokenMintB: mints[1],
+        funder: userWallet.publicKey,
+        whirlpool: otherWhirlpoolPda.publicKey,
+        tokenVaultA: otherTokenVaultA.publicKey,
+        tokenVaultB: otherTokenVaultB.publicKey,
+        feeTier
+      }
+    )
+    .signers([otherTokenVaultA, otherTokenVaultB])
+    .rpc();
+  await confirm(provider, signature);
+
+  // Open a position of the other whirlpool with the lockbox tick range
+  const otherPositionMintKeypair = anchor.web3.Keypair.generate();
+  const otherPositionPda = PDAUtil.getPosition(orca, otherPositionMintKeypair.publicKey);
+  const otherPositionTokenAccount = await getAssociatedTokenAddress(otherPositionMintKeypair.publicKey, userWallet.publicKey);
+  const [otherLowerTickIndex, otherUpperTickIndex] = TickUtil.getFullRangeTickIndex(tickSpacing);
+  signature = await program_whirlpool.methods.openPosition(
+    { positionBump: otherPositionPda.bump }, otherLowerTickIndex, otherUpperTickIndex)
+    .accounts(
+      {
+        funder: userWallet.publicKey,
+        owner: userWallet.publicKey,
+        position: otherPositionPda.publicKey,
+        positionMint: otherPositionMintKeypair.publicKey,
+        positionTokenAccount: otherPositionTokenAccount,
+        whirlpool: otherWhirlpoolPda.publicKey
+      }
+    )
+    .signers([otherPositionMintKeypair])
+    .rpc();
+  await confirm(provider, signature);
+
+  try {
+      await depositPosition(otherPositionPda.publicKey, otherPositionTokenAccount);
+      throw new Error("Deposit position must fail");
+  } catch (error) {
+      expect(error.message).toContain("ConstraintHasOne");
+  }
+
+  // ############################## DEPOSIT POSITION ##############################
+  console.log("\nDepositing a full range position");
+
+  const { userPosition, userPositionTokenAccount } = await openPosition("10" /* olas */, lower_tick_index, upper_tick_index);
+  const userPositionLiquidity = (await client.getPosition(userPosition, true)).getData().liquidity;
+
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  const lockboxPositionLiquidity = (await client.getPosition(position, true)).getData().liquidity;
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+
+  await depositPosition(userPosition, userPositionTokenAccount);
+
+  // Bridged tokens are minted in the amount of the deposited position liquidity
+  const bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalance - bridgedBalanceBefore).toString()).toEqual(userPositionLiquidity.toString());
+
+  // The user position liquidity is moved to the lockbox position
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.add(userPositionLiquidity).toString());
+  expect((await client.getPosition(position, true)).getData().liquidity.toString())
+    .toEqual(lockboxPositionLiquidity.add(userPositionLiquidity).toString());
+  expect((await client.getPosition(userPosition, true)).getData().liquidity.toString()).toEqual("0");
+
+  console.log("Successfully tested the deposit of existing positions");
+}
+
+main();
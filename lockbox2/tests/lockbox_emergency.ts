@@ -0,0 +1,183 @@
+import * as anchor from "@coral-xyz/anchor";
+import {
+  getOrCreateAssociatedTokenAccount, getAccount, TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
+import expect from "expect";
+import {
+  orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper, confirm, setupLockbox
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const {
+    provider, program, userWallet, client, token_a, token_b, pdaProgram, adminAccounts, position, pdaPositionAccount,
+    bridgedTokenMint, tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, deposit
+  } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  // Get the lockbox token ATAs receiving the position tokens in the emergency mode
+  const lockboxTokenAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      pdaProgram,
+      true
+  );
+  const lockboxTokenAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      pdaProgram,
+      true
+  );
+
+  async function emergencyExit(signer = userWallet) {
+    const signature = await program.methods.emergencyExit(new anchor.BN(0), new anchor.BN(0))
+      .accounts(
+          {
+            ...adminAccounts(signer),
+            whirlpool: whirlpool,
+            position: position,
+            pdaPositionAccount: pdaPositionAccount,
+            lockboxTokenAccountA: lockboxTokenAccountA.address,
+            lockboxTokenAccountB: lockboxTokenAccountB.address,
+            feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+            feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: tickArrayLower,
+            tickArrayUpper: tickArrayUpper,
+            whirlpoolProgram: orca,
+            tokenProgram: TOKEN_PROGRAM_ID
+          }
+      )
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  async function emergencyRedeem(amount: anchor.BN, tokenAccountA = lockboxTokenAccountA.address) {
+    const signature = await program.methods.emergencyRedeem(amount)
+      .accounts(
+          {
+            bridgedTokenMint: bridgedTokenMint,
+            bridgedTokenAccount: bridgedTokenAccount.address,
+            lockboxTokenAccountA: tokenAccountA,
+            lockboxTokenAccountB: lockboxTokenAccountB.address,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            lockbox: pdaProgram,
+            tokenProgram: TOKEN_PROGRAM_ID
+          }
+      )
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing liquidity");
+
+  const liquidity = await deposit("10" /* olas */);
+
+  // Try to redeem while the emergency mode is disabled
+  try {
+      await emergencyRedeem(liquidity);
+      throw new Error("Emergency redeem must fail");
+  } catch (error) {
+      expect(error.message).toContain("EmergencyModeDisabled");
+  }
+
+  // ############################## EMERGENCY EXIT ##############################
+  console.log("\nEnabling the emergency mode");
+
+  // Try to enable the emergency mode by someone other than the program upgrade authority
+  try {
+      await emergencyExit(anchor.web3.Keypair.generate());
+      throw new Error("Emergency exit must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongAdmin");
+  }
+
+  const lockboxBalanceABefore = (await getAccount(provider.connection, lockboxTokenAccountA.address)).amount;
+  const lockboxBalanceBBefore = (await getAccount(provider.connection, lockboxTokenAccountB.address)).amount;
+  await emergencyExit();
+
+  // The whole position liquidity is moved into the lockbox token accounts, and the total liquidity is unchanged
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  expect(lockboxStateData.emergencyMode).toEqual(true);
+  expect(lockboxStateData.emergencyTokenAccountA.toBase58()).toEqual(lockboxTokenAccountA.address.toBase58());
+  expect(lockboxStateData.emergencyTokenAccountB.toBase58()).toEqual(lockboxTokenAccountB.address.toBase58());
+  expect((await client.getPosition(position, true)).getData().liquidity.toString()).toEqual("0");
+  let lockboxBalanceA = (await getAccount(provider.connection, lockboxTokenAccountA.address)).amount;
+  let lockboxBalanceB = (await getAccount(provider.connection, lockboxTokenAccountB.address)).amount;
+  expect(lockboxBalanceA).toBeGreaterThan(lockboxBalanceABefore);
+  expect(lockboxBalanceB).toBeGreaterThan(lockboxBalanceBBefore);
+
+  // Try to enable the emergency mode twice
+  try {
+      await emergencyExit();
+      throw new Error("Emergency exit must fail");
+  } catch (error) {
+      expect(error.message).toContain("EmergencyModeEnabled");
+  }
+
+  // Try to deposit in the emergency mode
+  try {
+      await deposit("10" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("EmergencyModeEnabled");
+  }
+
+  // ############################## EMERGENCY REDEEM ##############################
+  console.log("\nRedeeming bridged tokens pro-rata");
+
+  // Try to redeem more than the total liquidity
+  try {
+      await emergencyRedeem(totalLiquidity.add(new anchor.BN(1)));
+      throw new Error("Emergency redeem must fail");
+  } catch (error) {
+      expect(error.message).toContain("AmountExceedsPositionLiquidity");
+  }
+
+  // Try to redeem from token accounts other than the emergency ones
+  try {
+      await emergencyRedeem(liquidity, tokenOwnerAccountA.address);
+      throw new Error("Emergency redeem must fail");
+  } catch (error) {
+      expect(error.message).toContain("ConstraintAddress");
+  }
+
+  const redeemAmount = liquidity.div(new anchor.BN(2));
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  const balanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  await emergencyRedeem(redeemAmount);
+
+  // The signer gets the pro-rata share of the lockbox token accounts rounded down
+  const amount = BigInt(redeemAmount.toString());
+  const total = BigInt(totalLiquidity.toString());
+  const balanceA = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceB = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  expect(balanceA - balanceABefore).toEqual(lockboxBalanceA * amount / total);
+  expect(balanceB - balanceBBefore).toEqual(lockboxBalanceB * amount / total);
+  const bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalanceBefore - bridgedBalance).toString()).toEqual(redeemAmount.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.sub(redeemAmount).toString());
+
+  // The lockbox token accounts are decreased by the redeemed tokens only
+  lockboxBalanceA -= balanceA - balanceABefore;
+  lockboxBalanceB -= balanceB - balanceBBefore;
+  expect((await getAccount(provider.connection, lockboxTokenAccountA.address)).amount).toEqual(lockboxBalanceA);
+  expect((await getAccount(provider.connection, lockboxTokenAccountB.address)).amount).toEqual(lockboxBalanceB);
+
+  console.log("Successfully tested the emergency mode");
+}
+
+main();
@@ -0,0 +1,212 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getOrCreateAssociatedTokenAccount } from "@solana/spl-token";
+import expect from "expect";
+import { PROGRAM_ID, MOCK_BRIDGE_ID, orca, whirlpool, confirm, setupProgram } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test uses the mock bridge program of programs/mock_bridge as the Wormhole core bridge
+async function main() {
+  const { provider, program, userWallet, token_a, token_b, pdaProgram, adminAccounts } = await setupProgram();
+
+  // Get the lockbox state data, the lockbox must be initialized with tests/lockbox_init.ts
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const feeCollectorA = lockboxStateData.feeCollectorTokenOwnerAccountA;
+  const feeCollectorB = lockboxStateData.feeCollectorTokenOwnerAccountB;
+
+  let signature = null;
+
+  // ############################## GOVERNANCE SETUP ##############################
+  console.log("\nConfiguring the cross-chain governance");
+
+  const governanceChain = 2;
+  const governanceEmitter = Buffer.alloc(32);
+  governanceEmitter.write("0000000000000000000000003c1e3a4cf2b2c2e0bc8b1a5ffc5e1e0dbc1a4c12", "hex");
+
+  signature = await program.methods.setBridgePrograms(MOCK_BRIDGE_ID, MOCK_BRIDGE_ID)
+    .accounts(adminAccounts())
+    .rpc();
+  await confirm(provider, signature);
+
+  signature = await program.methods.setGovernanceEmitter(governanceChain, Array.from(governanceEmitter))
+    .accounts(adminAccounts())
+    .rpc();
+  await confirm(provider, signature);
+
+  // Creates the posted VAA account owned by the mock core bridge, skipping the guardian signatures
+  async function postVaa(sequence: number, payload: Buffer, emitter = governanceEmitter, magic = "vaa") {
+    const postedVaa = anchor.web3.Keypair.generate();
+    const data = Buffer.alloc(95 + payload.length);
+    data.write(magic, 0);
+    data.writeBigUInt64LE(BigInt(sequence), 49);
+    data.writeUInt16LE(governanceChain, 57);
+    emitter.copy(data, 59);
+    data.writeUInt32LE(payload.length, 91);
+    payload.copy(data, 95);
+
+    const tx = new anchor.web3.Transaction().add(new anchor.web3.TransactionInstruction({
+      programId: MOCK_BRIDGE_ID,
+      keys: [
+        { pubkey: userWallet.publicKey, isSigner: true, isWritable: true },
+        { pubkey: postedVaa.publicKey, isSigner: true, isWritable: true },
+        { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false }
+      ],
+      data: Buffer.concat([Buffer.from([0]), data])
+    }));
+    await provider.sendAndConfirm(tx, [postedVaa]);
+
+    return postedVaa.publicKey;
+  }
+
+  // Governance action payload targeting the program, with the Borsh-encoded action
+  function actionPayload(action: Buffer, target = PROGRAM_ID) {
+    return Buffer.concat([target.toBuffer(), action]);
+  }
+
+  function setPausedAction(paused: boolean) {
+    return Buffer.from([0, paused ? 1 : 0]);
+  }
+
+  function setFeeCollectorsAction(a: anchor.web3.PublicKey, b: anchor.web3.PublicKey) {
+    return Buffer.concat([Buffer.from([4]), a.toBuffer(), b.toBuffer()]);
+  }
+
+  async function executeGovernance(sequence: number, postedVaa: anchor.web3.PublicKey, remainingAccounts = []) {
+    const sequenceBuffer = Buffer.alloc(8);
+    sequenceBuffer.writeBigUInt64BE(BigInt(sequence));
+    const chainBuffer = Buffer.alloc(2);
+    chainBuffer.writeUInt16BE(governanceChain);
+    const [governanceSequence] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from("governance_sequence", "utf-8"), chainBuffer, governanceEmitter, sequenceBuffer], program.programId
+    );
+
+    const signature = await program.methods.executeGovernance(new anchor.BN(sequence))
+      .accounts({ postedVaa, lockbox: pdaProgram, whirlpool, governanceSequence })
+      .remainingAccounts(remainingAccounts)
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## PAUSE AND UNPAUSE ##############################
+  console.log("\nPausing and unpausing via governance");
+
+  const pauseVaa = await postVaa(1, actionPayload(setPausedAction(true)));
+  await executeGovernance(1, pauseVaa);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.paused).toEqual(true);
+
+  const unpauseVaa = await postVaa(2, actionPayload(setPausedAction(false)));
+  await executeGovernance(2, unpauseVaa);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.paused).toEqual(false);
+
+  // Try to replay the pause message
+  try {
+      await executeGovernance(1, pauseVaa);
+      throw new Error("Governance replay must fail");
+  } catch (error) {
+      expect(error.message).not.toContain("must fail");
+  }
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.paused).toEqual(false);
+
+  // Try to execute a message with a different sequence than the requested one
+  try {
+      await executeGovernance(4, await postVaa(3, actionPayload(setPausedAction(true))));
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPostedVaa");
+  }
+
+  // ############################## WRONG MESSAGES ##############################
+  console.log("\nRejecting wrong governance messages");
+
+  // Try to execute a message from another emitter
+  try {
+      await executeGovernance(5, await postVaa(5, actionPayload(setPausedAction(true)), Buffer.alloc(32, 1)));
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongGovernanceEmitter");
+  }
+
+  // Try to execute a message targeting another program
+  try {
+      await executeGovernance(6, await postVaa(6, actionPayload(setPausedAction(true), orca)));
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongGovernanceAction");
+  }
+
+  // Try to execute an unverified message published via the core bridge
+  try {
+      await executeGovernance(7, await postVaa(7, actionPayload(setPausedAction(true)), governanceEmitter, "msg"));
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPostedVaa");
+  }
+
+  // Try to execute a message from an account not owned by the core bridge
+  try {
+      await executeGovernance(8, userWallet.publicKey);
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongPostedVaa");
+  }
+
+  // ############################## FEE COLLECTORS ##############################
+  console.log("\nChanging fee collectors via governance");
+
+  const newCollector = anchor.web3.Keypair.generate().publicKey;
+  const newFeeCollectorA = (await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      newCollector
+  )).address;
+  const newFeeCollectorB = (await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      newCollector
+  )).address;
+  const remainingAccounts = (a: anchor.web3.PublicKey, b: anchor.web3.PublicKey) => [
+    { pubkey: a, isSigner: false, isWritable: false },
+    { pubkey: b, isSigner: false, isWritable: false }
+  ];
+
+  // Try to change fee collectors without supplying their accounts
+  try {
+      await executeGovernance(9, await postVaa(9, actionPayload(setFeeCollectorsAction(newFeeCollectorA, newFeeCollectorB))));
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongFeeCollectors");
+  }
+
+  // Try to set fee collectors with swapped token mints
+  try {
+      await executeGovernance(10, await postVaa(10, actionPayload(setFeeCollectorsAction(newFeeCollectorB, newFeeCollectorA))),
+        remainingAccounts(newFeeCollectorB, newFeeCollectorA));
+      throw new Error("Governance must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongTokenMint");
+  }
+
+  await executeGovernance(11, await postVaa(11, actionPayload(setFeeCollectorsAction(newFeeCollectorA, newFeeCollectorB))),
+    remainingAccounts(newFeeCollectorA, newFeeCollectorB));
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.feeCollectorTokenOwnerAccountA.toBase58()).toEqual(newFeeCollectorA.toBase58());
+  expect(lockboxStateData.feeCollectorTokenOwnerAccountB.toBase58()).toEqual(newFeeCollectorB.toBase58());
+
+  // Restore the fee collectors
+  await executeGovernance(12, await postVaa(12, actionPayload(setFeeCollectorsAction(feeCollectorA, feeCollectorB))),
+    remainingAccounts(feeCollectorA, feeCollectorB));
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.feeCollectorTokenOwnerAccountA.toBase58()).toEqual(feeCollectorA.toBase58());
+  expect(lockboxStateData.feeCollectorTokenOwnerAccountB.toBase58()).toEqual(feeCollectorB.toBase58());
+
+  console.log("Successfully tested cross-chain governance");
+}
+
+main();
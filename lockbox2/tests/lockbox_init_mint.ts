@@ -0,0 +1,139 @@
+import * as idl_whirlpool from "../artifacts/whirlpool.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  createMint, mintTo, setAuthority, AuthorityType, getOrCreateAssociatedTokenAccount, getAssociatedTokenAddress
+} from "@solana/spl-token";
+import { PDAUtil, TickUtil } from "@orca-so/whirlpools-sdk";
+import expect from "expect";
+import { orca, whirlpool, confirm, setupProgram } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test initializes the lockbox, and must be run on a freshly started validator.sh without the initialization script
+async function main() {
+  const { provider, program, userWallet, whirlpool_data, token_a, token_b, pdaProgram } = await setupProgram();
+  const program_whirlpool = new Program(idl_whirlpool as anchor.Idl, orca, anchor.getProvider());
+
+  // Get the user token ATAs used as fee collectors
+  const feeCollectorTokenOwnerAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      userWallet.publicKey
+  );
+  const feeCollectorTokenOwnerAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      userWallet.publicKey
+  );
+
+  // Create the full range lockbox position
+  const [lower_tick_index, upper_tick_index] = TickUtil.getFullRangeTickIndex(whirlpool_data.tickSpacing);
+  const positionMintKeypair = anchor.web3.Keypair.generate();
+  const positionMint = positionMintKeypair.publicKey;
+  const positionPda = PDAUtil.getPosition(orca, positionMint);
+  const position = positionPda.publicKey;
+  const pdaPositionAccount = await getAssociatedTokenAddress(positionMint, pdaProgram, true);
+
+  let signature = await program_whirlpool.methods.openPosition(
+    { positionBump: positionPda.bump },
+    lower_tick_index,
+    upper_tick_index)
+    .accounts(
+      {
+        funder: userWallet.publicKey,
+        owner: pdaProgram,
+        position: position,
+        positionMint: positionMint,
+        positionTokenAccount: pdaPositionAccount,
+        whirlpool
+      }
+    )
+    .signers([positionMintKeypair])
+    .rpc();
+  await confirm(provider, signature);
+
+  async function initialize(bridgedTokenMint: anchor.web3.PublicKey) {
+    const signature = await program.methods
+      .initialize()
+      .accounts(
+        {
+          bridgedTokenMint: bridgedTokenMint,
+          feeCollectorTokenOwnerAccountA: feeCollectorTokenOwnerAccountA.address,
+          feeCollectorTokenOwnerAccountB: feeCollectorTokenOwnerAccountB.address,
+          position: position,
+          positionMint: positionMint,
+          pdaPositionAccount,
+          whirlpool
+        }
+      )
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## WRONG MINTS ##############################
+  console.log("\nRejecting misconfigured bridged token mints");
+
+  // Try to initialize with a mint that has a freeze authority
+  let bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, userWallet.publicKey, 8);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("FreezeAuthoritySet");
+  }
+
+  // Try to initialize with a mint of wrong decimals
+  bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, null, 6);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongMintDecimals");
+  }
+
+  // Try to initialize with a mint that already has a supply
+  bridgedTokenMint = await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8);
+  const userBridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      userWallet.publicKey
+  );
+  await mintTo(provider.connection, userWallet, bridgedTokenMint, userBridgedTokenAccount.address, userWallet, 1);
+  await setAuthority(provider.connection, userWallet, bridgedTokenMint, userWallet, AuthorityType.MintTokens, pdaProgram);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("BridgedSupplyNotZero");
+  }
+
+  // Try to initialize with a mint that has no mint authority, which is rejected without a panic
+  bridgedTokenMint = await createMint(provider.connection, userWallet, userWallet.publicKey, null, 8);
+  await setAuthority(provider.connection, userWallet, bridgedTokenMint, userWallet, AuthorityType.MintTokens, null);
+  try {
+      await initialize(bridgedTokenMint);
+      throw new Error("Initialize must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongMintAuthority");
+  }
+
+  // ############################## INITIALIZE ##############################
+  console.log("\nInitializing with a valid bridged token mint");
+
+  bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, null, 8);
+  await initialize(bridgedTokenMint);
+
+  const lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.bridgedTokenMint.toBase58()).toEqual(bridgedTokenMint.toBase58());
+  expect(lockboxStateData.position.toBase58()).toEqual(position.toBase58());
+
+  console.log("Successfully tested the bridged token mint validation");
+}
+
+main();
@@ -0,0 +1,304 @@
+import * as idl_v1 from "../../lockbox/target/idl/liquidity_lockbox.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { createMint, getOrCreateAssociatedTokenAccount, getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+import { PDAUtil } from "@orca-so/whirlpools-sdk";
+import expect from "expect";
+import {
+  orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper, confirm, setupLockbox
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const {
+    provider, program, userWallet, ctx, client, whirlpoolClient, token_a, token_b, pdaProgram, position, pdaPositionAccount,
+    bridgedTokenMint, positionMint, lower_tick_index, upper_tick_index, tokenOwnerAccountA, tokenOwnerAccountB,
+    bridgedTokenAccount, quoteFor, adminAccounts
+  } = await setupLockbox();
+
+  const PROGRAM_ID_V1 = new anchor.web3.PublicKey("7ahQGWysExobjeZ91RTsNqTCN3kWyHGZ43ud2vB7VVoZ");
+  const program_v1 = new Program(idl_v1 as anchor.Idl, PROGRAM_ID_V1, anchor.getProvider());
+
+  // Find a PDA account for the lockbox v1 program
+  const [pdaProgramV1] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("liquidity_lockbox", "utf-8")], program_v1.programId);
+  console.log("Lockbox v1 PDA:", pdaProgramV1.toBase58());
+  const [pdaPositionIndexV1] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("position_index", "utf-8")], program_v1.programId);
+
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidityBefore = lockboxStateData.totalLiquidity;
+
+  // Get the lockbox token ATAs
+  const lockboxTokenAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      pdaProgram,
+      true
+  );
+  const lockboxTokenAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      pdaProgram,
+      true
+  );
+  const pdaBridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      pdaProgram,
+      true
+  );
+
+  async function setDepositLimits(maxTotalLiquidity: anchor.BN) {
+    const signature = await program.methods.setDepositLimits(maxTotalLiquidity, new anchor.BN(0), new anchor.BN(0))
+      .accounts(adminAccounts())
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  let signature = null;
+
+  // ############################## LOCKBOX V1 ##############################
+  console.log("\nInitializing the lockbox v1 and depositing a position");
+
+  // Create the v1 bridged token mint with the v1 pda mint authority
+  const v1BridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgramV1, null, 8);
+  console.log("V1 bridged token mint:", v1BridgedTokenMint.toBase58());
+
+  // Initialize the lockbox v1 state
+  try {
+      signature = await program_v1.methods
+        .initialize()
+        .accounts(
+          {
+            bridgedTokenMint: v1BridgedTokenMint,
+            feeCollectorTokenOwnerAccountA: tokenOwnerAccountA.address,
+            feeCollectorTokenOwnerAccountB: tokenOwnerAccountB.address,
+            whirlpool
+          }
+        )
+        .rpc();
+  } catch (error) {
+      if (error instanceof Error && "message" in error) {
+          console.error("Program Error:", error);
+          console.error("Error Message:", error.message);
+      } else {
+          console.error("Transaction Error:", error);
+      }
+  }
+  await confirm(provider, signature);
+
+  // Open a position with the lockbox tick range
+  const quote = quoteFor("10" /* olas */);
+  const open_position_tx = await whirlpoolClient.openPosition(lower_tick_index, upper_tick_index, quote);
+  signature = await open_position_tx.tx.buildAndExecute();
+  const v1PositionMint = open_position_tx.positionMint;
+  console.log("V1 position NFT:", v1PositionMint.toBase58());
+  const latest_blockhash = await ctx.connection.getLatestBlockhash();
+  await ctx.connection.confirmTransaction({signature, ...latest_blockhash}, "confirmed");
+
+  const v1Position = PDAUtil.getPosition(orca, v1PositionMint).publicKey;
+  const v1PositionLiquidity = (await client.getPosition(v1Position)).getData().liquidity;
+  const positionTokenAccount = await getAssociatedTokenAddress(v1PositionMint, userWallet.publicKey);
+
+  // ATA of the lockbox v1 PDA to store the position NFT
+  const v1PdaPositionAccount = await getAssociatedTokenAddress(v1PositionMint, pdaProgramV1, true);
+
+  // Find a PDA account for the lockbox v1 position
+  let lockboxV1StateData = await program_v1.account.liquidityLockbox.fetch(pdaProgramV1);
+  const v1PositionId = lockboxV1StateData.numPositions as number;
+  const bytesNum = Buffer.allocUnsafe(4);
+  bytesNum.writeInt32BE(v1PositionId);
+  const [v1PdaLockboxPosition] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("lockbox_position", "utf-8"), bytesNum], program_v1.programId);
+  const [v1UserState] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("user_state", "utf-8"), pdaProgramV1.toBuffer(), userWallet.publicKey.toBuffer()], program_v1.programId);
+
+  const v1BridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      v1BridgedTokenMint,
+      userWallet.publicKey
+  );
+
+  // Deposit the position into the lockbox v1 in exchange of v1 bridged tokens
+  try {
+      signature = await program_v1.methods.deposit(v1PositionId, [])
+        .accounts(
+            {
+              lockbox: pdaProgramV1,
+              positionTokenAccount,
+              pdaPositionAccount: v1PdaPositionAccount,
+              positionMint: v1PositionMint,
+              pdaLockboxPosition: v1PdaLockboxPosition,
+              bridgedTokenAccount: v1BridgedTokenAccount.address,
+              bridgedTokenMint: v1BridgedTokenMint,
+              position: v1Position,
+              userState: v1UserState,
+              positionIndex: pdaPositionIndexV1
+            }
+        )
+        .signers([userWallet])
+        .rpc();
+  } catch (error) {
+      if (error instanceof Error && "message" in error) {
+          console.error("Program Error:", error);
+          console.error("Error Message:", error.message);
+      } else {
+          console.error("Transaction Error:", error);
+      }
+  }
+  await confirm(provider, signature);
+
+  let v1Balance = (await getAccount(provider.connection, v1BridgedTokenAccount.address)).amount;
+  expect(v1Balance.toString()).toEqual(v1PositionLiquidity.toString());
+
+  // ############################## MIGRATE POSITION ##############################
+  console.log("\nMigrating the lockbox v1 position");
+
+  // ATA of the lockbox PDA to receive the v1 position NFT
+  const pdaV1PositionAccount = await getAssociatedTokenAddress(v1PositionMint, pdaProgram, true);
+  const v1PositionState = await program_v1.account.lockboxPosition.fetch(v1PdaLockboxPosition);
+
+  // Rounding cover of the increased liquidity
+  const tokenMaxA = new anchor.BN(1000);
+  const tokenMaxB = new anchor.BN(1000);
+
+  async function migratePosition() {
+    const signature = await program.methods.migratePosition(v1PositionId, tokenMaxA, tokenMaxB)
+      .accounts(
+          {
+            position,
+            positionMint,
+            pdaPositionAccount,
+            v1Position,
+            v1PositionMint,
+            pdaV1PositionAccount,
+            v1PdaPositionAccount,
+            v1PdaLockboxPosition,
+            v1Depositor: v1PositionState.depositor,
+            lockboxV1: pdaProgramV1,
+            v1PositionIndex: pdaPositionIndexV1,
+            lockboxV1Program: program_v1.programId,
+            whirlpool,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            lockboxTokenAccountA: lockboxTokenAccountA.address,
+            lockboxTokenAccountB: lockboxTokenAccountB.address,
+            feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+            feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+            tokenVaultA,
+            tokenVaultB,
+            tickArrayLower,
+            tickArrayUpper,
+            bridgedTokenMint,
+            pdaBridgedTokenAccount: pdaBridgedTokenAccount.address,
+            lockbox: pdaProgram,
+            whirlpoolProgram: orca
+          }
+      )
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // Try to migrate the position above the total liquidity cap
+  const zero = new anchor.BN(0);
+  await setDepositLimits(totalLiquidityBefore.add(new anchor.BN(v1PositionLiquidity.toString())).subn(1));
+  try {
+      await migratePosition();
+      throw new Error("Migrate position must fail");
+  } catch (error) {
+      expect(error.message).toContain("TotalLiquidityCapExceeded");
+  }
+  await setDepositLimits(zero);
+
+  const balanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  await migratePosition();
+
+  // The v1 position is closed and its liquidity is moved to the lockbox position
+  expect(await provider.connection.getAccountInfo(v1Position)).toBeNull();
+  expect(await provider.connection.getAccountInfo(v1PdaLockboxPosition)).toBeNull();
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidityBefore.add(v1PositionLiquidity).toString());
+  lockboxV1StateData = await program_v1.account.liquidityLockbox.fetch(pdaProgramV1);
+  expect(lockboxV1StateData.totalLiquidity.toString()).toEqual("0");
+
+  // Bridged tokens for the v1 swap are minted to the lockbox
+  let pdaBridgedBalance = (await getAccount(provider.connection, pdaBridgedTokenAccount.address)).amount;
+  expect(pdaBridgedBalance.toString()).toEqual(v1PositionLiquidity.toString());
+
+  // The signer pays at most the rounding cover, and the cover is not kept by the lockbox
+  const balanceA = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceB = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  expect(balanceABefore - balanceA <= BigInt(tokenMaxA.toString())).toBe(true);
+  expect(balanceBBefore - balanceB <= BigInt(tokenMaxB.toString())).toBe(true);
+  const lockboxBalanceA = (await getAccount(provider.connection, lockboxTokenAccountA.address)).amount;
+  const lockboxBalanceB = (await getAccount(provider.connection, lockboxTokenAccountB.address)).amount;
+  expect(lockboxBalanceA.toString()).toEqual("0");
+  expect(lockboxBalanceB.toString()).toEqual("0");
+
+  // ############################## SWAP BRIDGED TOKENS ##############################
+  console.log("\nSwapping v1 bridged tokens");
+
+  // Try to swap more than the migrated liquidity
+  try {
+      signature = await program.methods.swapBridgedTokens(new anchor.BN(v1PositionLiquidity.toString()).addn(1))
+        .accounts(
+            {
+              v1BridgedTokenMint,
+              v1BridgedTokenAccount: v1BridgedTokenAccount.address,
+              bridgedTokenAccount: bridgedTokenAccount.address,
+              pdaBridgedTokenAccount: pdaBridgedTokenAccount.address,
+              lockboxV1: pdaProgramV1,
+              lockbox: pdaProgram
+            }
+        )
+        .signers([userWallet])
+        .rpc();
+      throw new Error("Swap over the migrated liquidity must fail");
+  } catch (error) {
+      expect(error.message).not.toEqual("Swap over the migrated liquidity must fail");
+  }
+
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  try {
+      signature = await program.methods.swapBridgedTokens(new anchor.BN(v1PositionLiquidity.toString()))
+        .accounts(
+            {
+              v1BridgedTokenMint,
+              v1BridgedTokenAccount: v1BridgedTokenAccount.address,
+              bridgedTokenAccount: bridgedTokenAccount.address,
+              pdaBridgedTokenAccount: pdaBridgedTokenAccount.address,
+              lockboxV1: pdaProgramV1,
+              lockbox: pdaProgram
+            }
+        )
+        .signers([userWallet])
+        .rpc();
+  } catch (error) {
+      if (error instanceof Error && "message" in error) {
+          console.error("Program Error:", error);
+          console.error("Error Message:", error.message);
+      } else {
+          console.error("Transaction Error:", error);
+      }
+  }
+  await confirm(provider, signature);
+
+  // The v1 bridged tokens are burned in exchange of the lockbox bridged tokens
+  v1Balance = (await getAccount(provider.connection, v1BridgedTokenAccount.address)).amount;
+  expect(v1Balance.toString()).toEqual("0");
+  const bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalance - bridgedBalanceBefore).toString()).toEqual(v1PositionLiquidity.toString());
+  pdaBridgedBalance = (await getAccount(provider.connection, pdaBridgedTokenAccount.address)).amount;
+  expect(pdaBridgedBalance.toString()).toEqual("0");
+
+  console.log("Successfully migrated the lockbox v1 position and swapped bridged tokens");
+}
+
+main();
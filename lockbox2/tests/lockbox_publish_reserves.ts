@@ -0,0 +1,125 @@
+import * as anchor from "@coral-xyz/anchor";
+import expect from "expect";
+import { MOCK_BRIDGE_ID, orca, confirm, setupMockCoreBridge, setupProgram } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test uses the mock bridge program of programs/mock_bridge as the Wormhole core bridge
+async function main() {
+  const { provider, program, userWallet, client, pdaProgram, adminAccounts } = await setupProgram();
+
+  // Get the lockbox state data, the lockbox must be initialized with tests/lockbox_init.ts
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const position = lockboxStateData.position;
+  const bridgedTokenMint = lockboxStateData.bridgedTokenMint;
+
+  let signature = null;
+
+  // ############################## MOCK BRIDGE SETUP ##############################
+  console.log("\nSetting up the mock bridge");
+
+  // Lockbox emitter PDA and its core bridge sequence
+  const [wormholeEmitter] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("emitter", "utf-8")], program.programId);
+  const [wormholeSequence] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("Sequence", "utf-8"), wormholeEmitter.toBuffer()], MOCK_BRIDGE_ID
+  );
+
+  const messageFee = 5000;
+  const { wormholeBridge, wormholeFeeCollector } = await setupMockCoreBridge(provider, userWallet, messageFee);
+
+  // Reserves are published permissionlessly, and the signer pays the message fee and the message account rent
+  const publisher = anchor.web3.Keypair.generate();
+  signature = await provider.connection.requestAirdrop(publisher.publicKey, 1000000000);
+  await confirm(provider, signature);
+
+  async function publishReserves(wormholeMessage: anchor.web3.Keypair, coreBridgeProgram = MOCK_BRIDGE_ID,
+    bridge = wormholeBridge.publicKey) {
+    const signature = await program.methods.publishReserves(0)
+      .accounts(
+          {
+            signer: publisher.publicKey,
+            position: position,
+            bridgedTokenMint: bridgedTokenMint,
+            lockbox: pdaProgram,
+            wormholeEmitter: wormholeEmitter,
+            wormholeSequence: wormholeSequence,
+            wormholeBridge: bridge,
+            wormholeFeeCollector: wormholeFeeCollector,
+            wormholeMessage: wormholeMessage.publicKey,
+            coreBridgeProgram: coreBridgeProgram
+          }
+      )
+      .signers([publisher, wormholeMessage])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // Try to publish reserves before the core bridge is configured, if the lockbox is freshly initialized
+  if (lockboxStateData.coreBridge.equals(anchor.web3.PublicKey.default)) {
+      try {
+          await publishReserves(anchor.web3.Keypair.generate(), anchor.web3.SystemProgram.programId);
+          throw new Error("Publish reserves must fail");
+      } catch (error) {
+          expect(error.message).toContain("BridgeNotConfigured");
+      }
+  }
+
+  // Configure the mock bridge as both the token and core bridges
+  signature = await program.methods.setBridgePrograms(MOCK_BRIDGE_ID, MOCK_BRIDGE_ID)
+    .accounts(adminAccounts())
+    .rpc();
+  await confirm(provider, signature);
+
+  // ############################## WRONG ACCOUNTS ##############################
+  console.log("\nRejecting wrong core bridge accounts");
+
+  // Try to publish reserves via a program other than the configured core bridge
+  try {
+      await publishReserves(anchor.web3.Keypair.generate(), orca);
+      throw new Error("Publish reserves must fail");
+  } catch (error) {
+      expect(error.message).toContain("ConstraintAddress");
+  }
+
+  // Try to publish reserves with a core bridge config too short to hold the message fee
+  try {
+      await publishReserves(anchor.web3.Keypair.generate(), MOCK_BRIDGE_ID, publisher.publicKey);
+      throw new Error("Publish reserves must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongBridgeAccount");
+  }
+
+  // ############################## PUBLISH RESERVES ##############################
+  console.log("\nPublishing the lockbox reserves");
+
+  const feeCollectorBalanceBefore = await provider.connection.getBalance(wormholeFeeCollector);
+  const slotBefore = await provider.connection.getSlot();
+  const wormholeMessage = anchor.web3.Keypair.generate();
+  await publishReserves(wormholeMessage);
+
+  // The core bridge message fee is paid
+  const feeCollectorBalance = await provider.connection.getBalance(wormholeFeeCollector);
+  expect(feeCollectorBalance - feeCollectorBalanceBefore).toEqual(messageFee);
+
+  // The message is emitted by the lockbox emitter and carries the reserves snapshot
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const positionLiquidity = (await client.getPosition(position, true)).getData().liquidity;
+  const supply = (await provider.connection.getTokenSupply(bridgedTokenMint)).value.amount;
+  const message = (await provider.connection.getAccountInfo(wormholeMessage.publicKey)).data;
+  expect(message.subarray(0, 3).toString()).toEqual("msg");
+  expect(new anchor.web3.PublicKey(message.subarray(3, 35)).toBase58()).toEqual(wormholeEmitter.toBase58());
+  expect(message.length).toEqual(35 + 1 + 8 + 16 + 8 + 8);
+  expect(message[35]).toEqual(1);
+  expect(message.readBigUInt64BE(36).toString()).toEqual(lockboxStateData.totalLiquidity.toString());
+  const liquidityHigh = message.readBigUInt64BE(44);
+  const liquidityLow = message.readBigUInt64BE(52);
+  expect(((liquidityHigh << BigInt(64)) + liquidityLow).toString()).toEqual(positionLiquidity.toString());
+  expect(message.readBigUInt64BE(60).toString()).toEqual(supply);
+  expect(Number(message.readBigUInt64BE(68))).toBeGreaterThanOrEqual(slotBefore);
+
+  console.log("Successfully tested publishing reserves");
+}
+
+main();
@@ -0,0 +1,187 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getAccount } from "@solana/spl-token";
+import expect from "expect";
+import {
+  orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper, confirm, setupLockbox
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const {
+    provider, program, userWallet, pdaProgram, adminAccounts, position, pdaPositionAccount, bridgedTokenMint, positionMint,
+    tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, deposit, withdrawAccounts
+  } = await setupLockbox();
+  const [withdrawRequest] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("withdraw_request", "utf-8"), pdaProgram.toBuffer(), userWallet.publicKey.toBuffer()], program.programId
+  );
+
+  let signature = null;
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing liquidity");
+
+  const liquidity = await deposit("10" /* olas */);
+
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  const requestAmount = liquidity.div(new anchor.BN(2));
+
+  // Set the withdrawal cooldown
+  const cooldownSlots = 20;
+  signature = await program.methods.setWithdrawCooldown(new anchor.BN(cooldownSlots))
+    .accounts(adminAccounts())
+    .rpc();
+  await confirm(provider, signature);
+
+  const requestAccounts = {
+    bridgedTokenMint,
+    bridgedTokenAccount: bridgedTokenAccount.address,
+    withdrawRequest,
+    lockbox: pdaProgram
+  };
+  const cancelAccounts = {
+    ...adminAccounts(),
+    withdrawRequest,
+    requester: userWallet.publicKey,
+    bridgedTokenMint,
+    bridgedTokenAccount: bridgedTokenAccount.address
+  };
+  const executeAccounts = {
+    whirlpool,
+    withdrawRequest,
+    position,
+    pdaPositionAccount,
+    positionMint,
+    tokenOwnerAccountA: tokenOwnerAccountA.address,
+    tokenOwnerAccountB: tokenOwnerAccountB.address,
+    feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+    feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+    tokenVaultA,
+    tokenVaultB,
+    tickArrayLower,
+    tickArrayUpper,
+    lockbox: pdaProgram,
+    whirlpoolProgram: orca
+  };
+
+  // Waits until the slot is reached
+  async function waitForSlot(slot: number) {
+    while (await provider.connection.getSlot() < slot) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+    }
+  }
+
+  // ############################## REQUEST AND CANCEL ##############################
+  console.log("\nRequesting and cancelling a queued withdrawal");
+
+  // Try to request more than the total liquidity
+  try {
+      signature = await program.methods.requestWithdraw(totalLiquidity.addn(1))
+        .accounts(requestAccounts)
+        .signers([userWallet])
+        .rpc();
+      throw new Error("Request must fail");
+  } catch (error) {
+      expect(error.message).toContain("AmountExceedsTotalLiquidity");
+  }
+
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  signature = await program.methods.requestWithdraw(requestAmount)
+    .accounts(requestAccounts)
+    .signers([userWallet])
+    .rpc();
+  await confirm(provider, signature);
+
+  // The bridged tokens are burned and the liquidity is reserved
+  let bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalanceBefore - bridgedBalance).toString()).toEqual(requestAmount.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.pendingWithdrawLiquidity.toString()).toEqual(requestAmount.toString());
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.toString());
+
+  // Try to withdraw directly the liquidity reserved for the queued withdrawal
+  try {
+      signature = await program.methods.withdraw(totalLiquidity, new anchor.BN(0), new anchor.BN(0))
+        .accounts(await withdrawAccounts())
+        .signers([userWallet])
+        .rpc();
+      throw new Error("Withdraw must fail");
+  } catch (error) {
+      expect(error.message).toContain("AmountExceedsTotalLiquidity");
+  }
+
+  // Try to execute the withdrawal before the cooldown period passes
+  try {
+      signature = await program.methods.executeWithdraw(new anchor.BN(0), new anchor.BN(0))
+        .accounts(executeAccounts)
+        .signers([userWallet])
+        .rpc();
+      throw new Error("Execute must fail");
+  } catch (error) {
+      expect(error.message).toContain("WithdrawRequestLocked");
+  }
+
+  // Cancel the withdrawal during the cooldown period
+  signature = await program.methods.cancelWithdraw()
+    .accounts(cancelAccounts)
+    .rpc();
+  await confirm(provider, signature);
+
+  // The bridged tokens are minted back and the reserved liquidity is released
+  bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect(bridgedBalance.toString()).toEqual(bridgedBalanceBefore.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.pendingWithdrawLiquidity.toString()).toEqual("0");
+  expect(await provider.connection.getAccountInfo(withdrawRequest)).toBeNull();
+
+  // ############################## REQUEST AND EXECUTE ##############################
+  console.log("\nRequesting and executing a queued withdrawal");
+
+  signature = await program.methods.requestWithdraw(requestAmount)
+    .accounts(requestAccounts)
+    .signers([userWallet])
+    .rpc();
+  await confirm(provider, signature);
+
+  // Wait for the cooldown period to pass
+  const withdrawRequestData = await program.account.withdrawRequest.fetch(withdrawRequest);
+  await waitForSlot(withdrawRequestData.unlockSlot.toNumber());
+
+  // Try to cancel the withdrawal after the cooldown period
+  try {
+      signature = await program.methods.cancelWithdraw()
+        .accounts(cancelAccounts)
+        .rpc();
+      throw new Error("Cancel must fail");
+  } catch (error) {
+      expect(error.message).toContain("WithdrawRequestUnlocked");
+  }
+
+  const balanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  signature = await program.methods.executeWithdraw(new anchor.BN(0), new anchor.BN(0))
+    .accounts(executeAccounts)
+    .signers([userWallet])
+    .rpc();
+  await confirm(provider, signature);
+
+  // The requested liquidity is withdrawn and the reservation is released
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.pendingWithdrawLiquidity.toString()).toEqual("0");
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.sub(requestAmount).toString());
+  expect((await getAccount(provider.connection, tokenOwnerAccountA.address)).amount).toBeGreaterThan(balanceABefore);
+  expect((await getAccount(provider.connection, tokenOwnerAccountB.address)).amount).toBeGreaterThan(balanceBBefore);
+  expect(await provider.connection.getAccountInfo(withdrawRequest)).toBeNull();
+
+  // Reset the withdrawal cooldown
+  await program.methods.setWithdrawCooldown(new anchor.BN(0))
+    .accounts(adminAccounts())
+    .rpc();
+
+  console.log("Successfully tested queued withdrawals");
+}
+
+main();
@@ -0,0 +1,96 @@
+import * as anchor from "@coral-xyz/anchor";
+import expect from "expect";
+import { confirm, setupLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const { provider, program, userWallet, pdaProgram, adminAccounts, deposit, withdraw } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  async function setWithdrawRateLimit(withdrawWindowSlots: number, maxWithdrawPerWindow: anchor.BN, signer = userWallet) {
+    const signature = await program.methods.setWithdrawRateLimit(new anchor.BN(withdrawWindowSlots), maxWithdrawPerWindow)
+      .accounts(adminAccounts(signer))
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing liquidity");
+
+  const liquidity = await deposit("10" /* olas */);
+  const quarter = liquidity.div(new anchor.BN(4));
+  const one = new anchor.BN(1);
+
+  // ############################## ADMIN ##############################
+  console.log("\nSetting the withdrawal rate limit");
+
+  // Try to set the withdrawal rate limit by someone other than the program upgrade authority
+  try {
+      await setWithdrawRateLimit(1000000, quarter, anchor.web3.Keypair.generate());
+      throw new Error("Set withdraw rate limit must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongAdmin");
+  }
+
+  // ############################## RATE LIMIT ##############################
+  console.log("\nWithdrawing up to the window limit");
+
+  // The window is long enough not to elapse during the test
+  await setWithdrawRateLimit(1000000, quarter.mul(new anchor.BN(2)));
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.withdrawWindowSlots.toString()).toEqual("1000000");
+  expect(lockboxStateData.maxWithdrawPerWindow.toString()).toEqual(quarter.mul(new anchor.BN(2)).toString());
+
+  await withdraw(quarter);
+  await withdraw(quarter);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.withdrawnInWindow.toString()).toEqual(quarter.mul(new anchor.BN(2)).toString());
+
+  // Try to withdraw above the window limit
+  try {
+      await withdraw(one);
+      throw new Error("Withdraw must fail");
+  } catch (error) {
+      expect(error.message).toContain("WithdrawRateLimitExceeded");
+  }
+
+  // ############################## WINDOW ELAPSED ##############################
+  console.log("\nWithdrawing again once the window has elapsed");
+
+  // Setting the rate limit restarts the window
+  const windowSlots = 10;
+  await setWithdrawRateLimit(windowSlots, quarter);
+  await withdraw(quarter);
+
+  // Try to withdraw above the window limit
+  try {
+      await withdraw(one);
+      throw new Error("Withdraw must fail");
+  } catch (error) {
+      expect(error.message).toContain("WithdrawRateLimitExceeded");
+  }
+
+  // Wait for the window to elapse
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const windowEnd = lockboxStateData.withdrawWindowStart.toNumber() + windowSlots;
+  while (await provider.connection.getSlot() <= windowEnd) {
+      await new Promise((resolve) => setTimeout(resolve, 400));
+  }
+
+  await withdraw(one);
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.withdrawnInWindow.toString()).toEqual("1");
+  expect(lockboxStateData.withdrawWindowStart.toNumber()).toBeGreaterThanOrEqual(windowEnd);
+
+  // Zero window length disables the withdrawal rate limit
+  await setWithdrawRateLimit(0, new anchor.BN(0));
+  await withdraw(quarter);
+
+  console.log("Successfully tested the withdrawal rate limit");
+}
+
+main();
@@ -0,0 +1,245 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getOrCreateAssociatedTokenAccount, getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+import { PDAUtil, SwapUtils } from "@orca-so/whirlpools-sdk";
+import { DecimalUtil } from "@orca-so/common-sdk";
+import Decimal from "decimal.js";
+import expect from "expect";
+import { orca, whirlpool, tokenVaultA, tokenVaultB, confirm, setupRangeLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test initializes the lockbox with a narrow tick range, and must be run on a freshly started validator.sh
+async function main() {
+  // Narrow price range of two tick spacings around the current tick, the user wallet is also the rebalance keeper
+  const {
+    provider, program, userWallet, client, whirlpoolClient, token_a, token_b, pdaProgram, adminAccounts, tickSpacing,
+    lower_tick_index, tokenOwnerAccountA, tokenOwnerAccountB, deposit, movePriceBelowRange
+  } = await setupRangeLockbox(1);
+  const oracle = PDAUtil.getOracle(orca, whirlpool).publicKey;
+
+  // Get the lockbox token ATAs
+  const lockboxTokenAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      pdaProgram,
+      true
+  );
+  const lockboxTokenAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      pdaProgram,
+      true
+  );
+
+  let signature = null;
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing liquidity into the narrow range position");
+
+  const quote = await deposit("10" /* olas */);
+
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  expect(totalLiquidity.toString()).toEqual(quote.liquidityAmount.toString());
+
+  // Gets the rebalance accounts for the current whirlpool state and a new position mint
+  async function getRebalanceAccounts(newPositionMint: anchor.web3.PublicKey, aToB: boolean) {
+    await whirlpoolClient.refreshData();
+    const data = whirlpoolClient.getData();
+    const state = await program.account.liquidityLockbox.fetch(pdaProgram);
+    const currentPosition = (await client.getPosition(state.position)).getData();
+
+    // New position range of the same width centered on the current tick
+    const width = currentPosition.tickUpperIndex - currentPosition.tickLowerIndex;
+    const newLower = Math.floor(data.tickCurrentIndex / tickSpacing) * tickSpacing -
+      Math.floor(width / tickSpacing / 2) * tickSpacing;
+    const newUpper = newLower + width;
+    const swapTickArrays = SwapUtils.getTickArrayPublicKeys(data.tickCurrentIndex, tickSpacing, aToB, orca, whirlpool);
+
+    return {
+      whirlpool,
+      position: state.position,
+      pdaPositionAccount: state.pdaPositionAccount,
+      positionMint: currentPosition.positionMint,
+      newPosition: PDAUtil.getPosition(orca, newPositionMint).publicKey,
+      newPositionMint,
+      newPositionTokenAccount: await getAssociatedTokenAddress(newPositionMint, pdaProgram, true),
+      tokenOwnerAccountA: tokenOwnerAccountA.address,
+      tokenOwnerAccountB: tokenOwnerAccountB.address,
+      lockboxTokenAccountA: lockboxTokenAccountA.address,
+      lockboxTokenAccountB: lockboxTokenAccountB.address,
+      feeCollectorTokenOwnerAccountA: state.feeCollectorTokenOwnerAccountA,
+      feeCollectorTokenOwnerAccountB: state.feeCollectorTokenOwnerAccountB,
+      tokenVaultA,
+      tokenVaultB,
+      tickArrayLower: PDAUtil.getTickArrayFromTickIndex(currentPosition.tickLowerIndex, tickSpacing, whirlpool, orca).publicKey,
+      tickArrayUpper: PDAUtil.getTickArrayFromTickIndex(currentPosition.tickUpperIndex, tickSpacing, whirlpool, orca).publicKey,
+      newTickArrayLower: PDAUtil.getTickArrayFromTickIndex(newLower, tickSpacing, whirlpool, orca).publicKey,
+      newTickArrayUpper: PDAUtil.getTickArrayFromTickIndex(newUpper, tickSpacing, whirlpool, orca).publicKey,
+      tickArray0: swapTickArrays[0],
+      tickArray1: swapTickArrays[1],
+      tickArray2: swapTickArrays[2],
+      oracle,
+      lockbox: pdaProgram,
+      whirlpoolProgram: orca
+    };
+  }
+
+  // ############################## REBALANCE PARAMS ##############################
+  console.log("\nSetting rebalance params");
+
+  // Try to rebalance before the keeper is set
+  let newPositionMintKeypair = anchor.web3.Keypair.generate();
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(0), new anchor.BN(0), true, new anchor.BN(0), new anchor.BN(0))
+        .accounts(await getRebalanceAccounts(newPositionMintKeypair.publicKey, true))
+        .signers([userWallet, newPositionMintKeypair])
+        .rpc();
+      throw new Error("Rebalance must fail");
+  } catch (error) {
+      expect(error.message).toContain("RebalanceNotConfigured");
+  }
+
+  // Try to set the slippage over the whole price
+  try {
+      signature = await program.methods.setRebalanceParams(userWallet.publicKey, new anchor.BN(10), 10001)
+        .accounts(adminAccounts())
+        .rpc();
+      throw new Error("Setting rebalance params must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongRebalanceParams");
+  }
+
+  // Set the rebalance keeper, the rebalance interval and 1% of max price slippage
+  signature = await program.methods.setRebalanceParams(userWallet.publicKey, new anchor.BN(10), 100)
+    .accounts(adminAccounts())
+    .rpc();
+  await confirm(provider, signature);
+
+  // Try to rebalance by another keeper
+  const anotherKeeper = anchor.web3.Keypair.generate();
+  await provider.connection.confirmTransaction(await provider.connection.requestAirdrop(anotherKeeper.publicKey, 1000000000));
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(0), new anchor.BN(0), true, new anchor.BN(0), new anchor.BN(0))
+        .accounts({...(await getRebalanceAccounts(newPositionMintKeypair.publicKey, true)), signer: anotherKeeper.publicKey})
+        .signers([anotherKeeper, newPositionMintKeypair])
+        .rpc();
+      throw new Error("Rebalance must fail");
+  } catch (error) {
+      expect(error.message).not.toEqual("Rebalance must fail");
+  }
+
+  // Try to rebalance while the position is in range
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(0), new anchor.BN(0), true, new anchor.BN(0), new anchor.BN(0))
+        .accounts(await getRebalanceAccounts(newPositionMintKeypair.publicKey, true))
+        .signers([userWallet, newPositionMintKeypair])
+        .rpc();
+      throw new Error("Rebalance must fail");
+  } catch (error) {
+      expect(error.message).toContain("PositionInRange");
+  }
+
+  // ############################## PRICE MOVE ##############################
+  console.log("\nMoving the price below the lockbox position range");
+
+  await movePriceBelowRange();
+  expect(whirlpoolClient.getData().tickCurrentIndex).toBeLessThan(lower_tick_index);
+
+  // The new position tick arrays must be initialized
+  const newTickArraysTx = await whirlpoolClient.initTickArrayForTicks([
+    whirlpoolClient.getData().tickCurrentIndex - tickSpacing, whirlpoolClient.getData().tickCurrentIndex + tickSpacing
+  ]);
+  if (newTickArraysTx) {
+    await newTickArraysTx.buildAndExecute();
+  }
+
+  // ############################## REBALANCE ##############################
+  console.log("\nRebalancing the lockbox position");
+
+  // Try to swap with the min amount out that cannot be reached
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(1000), new anchor.BN("18446744073709551615"), true,
+        new anchor.BN(0), new anchor.BN(0))
+        .accounts(await getRebalanceAccounts(newPositionMintKeypair.publicKey, true))
+        .signers([userWallet, newPositionMintKeypair])
+        .rpc();
+      throw new Error("Rebalance must fail");
+  } catch (error) {
+      expect(error.message).not.toEqual("Rebalance must fail");
+  }
+
+  // Try to rebalance without the keeper cover, as the closed position is only in token A
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(0), new anchor.BN(0), true, new anchor.BN(0), new anchor.BN(0))
+        .accounts(await getRebalanceAccounts(newPositionMintKeypair.publicKey, true))
+        .signers([userWallet, newPositionMintKeypair])
+        .rpc();
+      throw new Error("Rebalance must fail");
+  } catch (error) {
+      expect(error.message).not.toEqual("Rebalance must fail");
+  }
+
+  // Rebalance with the keeper cover of token B
+  const tokenMaxB = DecimalUtil.toBN(new Decimal("20" /* olas */), 8);
+  const keeperBalanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const keeperBalanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  const oldPosition = lockboxStateData.position;
+  const rebalanceAccounts = await getRebalanceAccounts(newPositionMintKeypair.publicKey, true);
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(0), new anchor.BN(0), true, new anchor.BN(0), tokenMaxB)
+        .accounts(rebalanceAccounts)
+        .signers([userWallet, newPositionMintKeypair])
+        .rpc();
+  } catch (error) {
+      if (error instanceof Error && "message" in error) {
+          console.error("Program Error:", error);
+          console.error("Error Message:", error.message);
+      } else {
+          console.error("Transaction Error:", error);
+      }
+  }
+  await confirm(provider, signature);
+
+  // The new position is in range and keeps the lockbox liquidity, and the old one is closed
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.position.toBase58()).toEqual(rebalanceAccounts.newPosition.toBase58());
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.toString());
+  const newPositionData = (await client.getPosition(rebalanceAccounts.newPosition)).getData();
+  expect(newPositionData.liquidity.toString()).toEqual(totalLiquidity.toString());
+  expect(newPositionData.tickLowerIndex).toBeLessThanOrEqual(whirlpoolClient.getData().tickCurrentIndex);
+  expect(newPositionData.tickUpperIndex).toBeGreaterThan(whirlpoolClient.getData().tickCurrentIndex);
+  expect(await provider.connection.getAccountInfo(oldPosition)).toBeNull();
+
+  // The keeper only covers the missing token B, and the token A surplus is kept in the lockbox as the reserve
+  const keeperBalanceA = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const keeperBalanceB = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  expect(keeperBalanceBBefore - keeperBalanceB).toBeGreaterThan(BigInt(0));
+  expect(keeperBalanceBBefore - keeperBalanceB).toBeLessThanOrEqual(BigInt(tokenMaxB.toString()));
+  const lockboxBalanceA = (await getAccount(provider.connection, lockboxTokenAccountA.address)).amount;
+  const lockboxBalanceB = (await getAccount(provider.connection, lockboxTokenAccountB.address)).amount;
+  expect(lockboxBalanceA).toBeGreaterThan(BigInt(0));
+  expect(lockboxBalanceB).toEqual(BigInt(0));
+  // Token A received by the keeper as the fee collector is only the collected fee part, not the surplus
+  expect(keeperBalanceA - keeperBalanceABefore).toBeLessThan(lockboxBalanceA);
+
+  // Try to rebalance again before the rebalance interval passes
+  newPositionMintKeypair = anchor.web3.Keypair.generate();
+  try {
+      signature = await program.methods.rebalance(new anchor.BN(0), new anchor.BN(0), true, new anchor.BN(0), new anchor.BN(0))
+        .accounts(await getRebalanceAccounts(newPositionMintKeypair.publicKey, true))
+        .signers([userWallet, newPositionMintKeypair])
+        .rpc();
+      throw new Error("Rebalance must fail");
+  } catch (error) {
+      expect(error.message).toContain("RebalanceTooFrequent");
+  }
+
+  console.log("Successfully rebalanced the lockbox position");
+}
+
+main();
@@ -0,0 +1,64 @@
+import { getAccount } from "@solana/spl-token";
+import expect from "expect";
+import { whirlpool, setupRangeLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+// The test initializes the lockbox with a concentrated tick range, and must be run on a freshly started validator.sh
+async function main() {
+  // ############################## INITIALIZE ##############################
+  console.log("\nInitializing the lockbox with the concentrated range position");
+
+  // Concentrated price range of four tick spacings around the current tick
+  // Note that the whirlpool program itself rejects positions with ticks not aligned with the tick spacing or out of
+  // the tick bounds, such that the lockbox WrongTickRange check cannot be reached with a valid whirlpool position
+  const {
+    provider, program, client, whirlpoolClient, pdaProgram, lower_tick_index, upper_tick_index, position,
+    bridgedTokenAccount, deposit, movePriceBelowRange
+  } = await setupRangeLockbox(2);
+
+  // The lockbox position is the one of the concentrated range
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.position.toBase58()).toEqual(position.toBase58());
+  expect(lockboxStateData.whirlpool.toBase58()).toEqual(whirlpool.toBase58());
+  let positionData = (await client.getPosition(position, true)).getData();
+  expect(positionData.tickLowerIndex).toEqual(lower_tick_index);
+  expect(positionData.tickUpperIndex).toEqual(upper_tick_index);
+
+  // ############################## DEPOSIT IN RANGE ##############################
+  console.log("\nDepositing liquidity into the concentrated range position");
+
+  const quote = await deposit("10" /* olas */);
+
+  // The liquidity is added to the lockbox position and the bridged tokens are minted for it
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(quote.liquidityAmount.toString());
+  positionData = (await client.getPosition(position, true)).getData();
+  expect(positionData.liquidity.toString()).toEqual(quote.liquidityAmount.toString());
+  const bridgedTokenBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect(bridgedTokenBalance.toString()).toEqual(quote.liquidityAmount.toString());
+
+  // ############################## DEPOSIT OUT OF RANGE ##############################
+  console.log("\nMoving the price below the lockbox position range");
+
+  await movePriceBelowRange();
+  expect(whirlpoolClient.getData().tickCurrentIndex).toBeLessThan(lower_tick_index);
+
+  // Try to deposit when the current tick is out of the lockbox position range
+  try {
+      await deposit("10" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("OutOfRange");
+  }
+
+  // The lockbox state is not changed
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(quote.liquidityAmount.toString());
+
+  console.log("Successfully tested the concentrated range lockbox");
+}
+
+main();
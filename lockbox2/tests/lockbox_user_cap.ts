@@ -0,0 +1,92 @@
+import * as anchor from "@coral-xyz/anchor";
+import expect from "expect";
+import { confirm, setupLockbox } from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const { provider, program, userWallet, pdaProgram, adminAccounts, quoteFor, deposit, withdraw } = await setupLockbox();
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+
+  async function setUserDepositCap(maxUserDeposit: anchor.BN, signer = userWallet) {
+    const signature = await program.methods.setUserDepositCap(maxUserDeposit)
+      .accounts(adminAccounts(signer))
+      .signers([signer])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  // Find the user state PDA tracking the cumulative user deposits
+  const [pdaUserState] = await anchor.web3.PublicKey.findProgramAddress(
+    [Buffer.from("user_state", "utf-8"), pdaProgram.toBuffer(), userWallet.publicKey.toBuffer()], program.programId
+  );
+
+  // Gets the cumulative user deposits, the user state is only created on the first deposit
+  async function getTotalDeposited() {
+    const userStateData = await program.account.userState.fetchNullable(pdaUserState);
+    return userStateData == null ? new anchor.BN(0) : userStateData.totalDeposited;
+  }
+
+  const liquidity = quoteFor("10" /* olas */).liquidityAmount;
+
+  // ############################## ADMIN ##############################
+  console.log("\nSetting the user deposit cap");
+
+  // Try to set the user deposit cap by someone other than the program upgrade authority
+  try {
+      await setUserDepositCap(liquidity, anchor.web3.Keypair.generate());
+      throw new Error("Set user deposit cap must fail");
+  } catch (error) {
+      expect(error.message).toContain("WrongAdmin");
+  }
+
+  // ############################## USER DEPOSIT CAP ##############################
+  console.log("\nDepositing up to the user deposit cap");
+
+  // The cap leaves room for one deposit and a half on top of the previous user deposits
+  let totalDeposited = await getTotalDeposited();
+  await setUserDepositCap(totalDeposited.add(liquidity).add(liquidity.div(new anchor.BN(2))));
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.maxUserDeposit.toString())
+    .toEqual(totalDeposited.add(liquidity).add(liquidity.div(new anchor.BN(2))).toString());
+
+  await deposit("10" /* olas */);
+  const userStateData = await program.account.userState.fetch(pdaUserState);
+  expect(userStateData.user.toBase58()).toEqual(userWallet.publicKey.toBase58());
+  expect(userStateData.lockbox.toBase58()).toEqual(pdaProgram.toBase58());
+  expect(userStateData.totalDeposited.toString()).toEqual(totalDeposited.add(liquidity).toString());
+
+  // Try to deposit above the user deposit cap
+  try {
+      await deposit("10" /* olas */);
+      throw new Error("Deposit must fail");
+  } catch (error) {
+      expect(error.message).toContain("UserDepositCapExceeded");
+  }
+
+  // A deposit within the remaining cap is accepted
+  const smallLiquidity = await deposit("1" /* olas */);
+  totalDeposited = await getTotalDeposited();
+  expect(totalDeposited.toString()).toEqual(userStateData.totalDeposited.add(smallLiquidity).toString());
+
+  // Zero cap disables the user deposit cap, and deposits are still tracked
+  await setUserDepositCap(new anchor.BN(0));
+  await deposit("10" /* olas */);
+  expect((await getTotalDeposited()).toString()).toEqual(totalDeposited.add(liquidity).toString());
+
+  // ############################## WITHDRAW ##############################
+  console.log("\nRecording withdrawals in the user state");
+
+  // Withdrawals are recorded in the existing user state and do not free up the cap
+  const totalWithdrawn = (await program.account.userState.fetch(pdaUserState)).totalWithdrawn;
+  await withdraw(liquidity);
+  const userStateDataAfter = await program.account.userState.fetch(pdaUserState);
+  expect(userStateDataAfter.totalWithdrawn.toString()).toEqual(totalWithdrawn.add(liquidity).toString());
+  expect(userStateDataAfter.totalDeposited.toString()).toEqual(totalDeposited.add(liquidity).toString());
+
+  console.log("Successfully tested the user deposit cap");
+}
+
+main();
@@ -0,0 +1,132 @@
+import * as anchor from "@coral-xyz/anchor";
+import { getAccount, getAssociatedTokenAddress } from "@solana/spl-token";
+import { PDAUtil } from "@orca-so/whirlpools-sdk";
+import expect from "expect";
+import {
+  orca, whirlpool, tokenVaultA, tokenVaultB, tickArrayLower, tickArrayUpper, confirm, setupLockbox
+} from "./setup";
+
+// UNIX/Linux/Mac
+// bash$ export ANCHOR_PROVIDER_URL=http://127.0.0.1:8899
+// bash$ export ANCHOR_WALLET=artifacts/id.json
+
+async function main() {
+  const {
+    provider, program, userWallet, client, pdaProgram, position, pdaPositionAccount, bridgedTokenMint, positionMint,
+    tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, deposit
+  } = await setupLockbox();
+
+  let signature = null;
+
+  // ############################## DEPOSIT ##############################
+  console.log("\nDepositing liquidity");
+
+  const liquidity = await deposit("10" /* olas */);
+
+  let lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const totalLiquidity = lockboxStateData.totalLiquidity;
+  const withdrawAmount = liquidity.div(new anchor.BN(2));
+
+  // ############################## WITHDRAW TO POSITION ##############################
+  console.log("\nWithdrawing liquidity into a new position");
+
+  const newPositionMint = anchor.web3.Keypair.generate();
+  const newPosition = PDAUtil.getPosition(orca, newPositionMint.publicKey).publicKey;
+  const newPositionTokenAccount = await getAssociatedTokenAddress(newPositionMint.publicKey, userWallet.publicKey);
+
+  const bridgedBalanceBefore = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  const balanceABefore = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceBBefore = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+
+  // Token max amounts cover the rounding between the withdrawn and the required token amounts, and are mostly unused
+  const tokenMax = new anchor.BN(1000000);
+  signature = await program.methods.withdrawToPosition(withdrawAmount, tokenMax, tokenMax)
+    .accounts(
+        {
+          whirlpool: whirlpool,
+          bridgedTokenMint: bridgedTokenMint,
+          bridgedTokenAccount: bridgedTokenAccount.address,
+          position: position,
+          pdaPositionAccount: pdaPositionAccount,
+          positionMint: positionMint,
+          newPosition: newPosition,
+          newPositionMint: newPositionMint.publicKey,
+          newPositionTokenAccount: newPositionTokenAccount,
+          tokenOwnerAccountA: tokenOwnerAccountA.address,
+          tokenOwnerAccountB: tokenOwnerAccountB.address,
+          feeCollectorTokenOwnerAccountA: lockboxStateData.feeCollectorTokenOwnerAccountA,
+          feeCollectorTokenOwnerAccountB: lockboxStateData.feeCollectorTokenOwnerAccountB,
+          tokenVaultA: tokenVaultA,
+          tokenVaultB: tokenVaultB,
+          tickArrayLower: tickArrayLower,
+          tickArrayUpper: tickArrayUpper,
+          lockbox: pdaProgram,
+          whirlpoolProgram: orca
+        }
+    )
+    .signers([userWallet, newPositionMint])
+    .rpc();
+  await confirm(provider, signature);
+
+  // The bridged tokens are burned and the lockbox liquidity is decreased
+  let bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect((bridgedBalanceBefore - bridgedBalance).toString()).toEqual(withdrawAmount.toString());
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.sub(withdrawAmount).toString());
+
+  // The signer owns the new position with the withdrawn liquidity over the lockbox position tick range
+  const lockboxPositionData = (await client.getPosition(position)).getData();
+  const newPositionData = (await client.getPosition(newPosition, true)).getData();
+  expect(newPositionData.liquidity.toString()).toEqual(withdrawAmount.toString());
+  expect(newPositionData.tickLowerIndex).toEqual(lockboxPositionData.tickLowerIndex);
+  expect(newPositionData.tickUpperIndex).toEqual(lockboxPositionData.tickUpperIndex);
+  expect((await getAccount(provider.connection, newPositionTokenAccount)).amount.toString()).toEqual("1");
+
+  // Only the rounding is taken from the signer tokens, and the rest of the token max amounts is left to the signer
+  const balanceA = (await getAccount(provider.connection, tokenOwnerAccountA.address)).amount;
+  const balanceB = (await getAccount(provider.connection, tokenOwnerAccountB.address)).amount;
+  expect(balanceABefore - balanceA).toBeGreaterThanOrEqual(BigInt(0));
+  expect(balanceABefore - balanceA).toBeLessThanOrEqual(BigInt(10));
+  expect(balanceBBefore - balanceB).toBeGreaterThanOrEqual(BigInt(0));
+  expect(balanceBBefore - balanceB).toBeLessThanOrEqual(BigInt(10));
+
+  // ############################## DEPOSIT POSITION ##############################
+  console.log("\nDepositing the new position back into the lockbox");
+
+  signature = await program.methods.depositPosition(tokenMax, tokenMax, [])
+    .accounts(
+        {
+          userPosition: newPosition,
+          userPositionTokenAccount: newPositionTokenAccount,
+          position: position,
+          positionMint: positionMint,
+          pdaPositionAccount: pdaPositionAccount,
+          whirlpool: whirlpool,
+          tokenOwnerAccountA: tokenOwnerAccountA.address,
+          tokenOwnerAccountB: tokenOwnerAccountB.address,
+          tokenVaultA: tokenVaultA,
+          tokenVaultB: tokenVaultB,
+          tickArrayLower: tickArrayLower,
+          tickArrayUpper: tickArrayUpper,
+          bridgedTokenMint: bridgedTokenMint,
+          bridgedTokenAccount: bridgedTokenAccount.address,
+          lockbox: pdaProgram,
+          whirlpoolProgram: orca
+        }
+    )
+    .signers([userWallet])
+    .rpc();
+  await confirm(provider, signature);
+
+  // The round trip restores the lockbox liquidity and the bridged token balance
+  lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  expect(lockboxStateData.totalLiquidity.toString()).toEqual(totalLiquidity.toString());
+  bridgedBalance = (await getAccount(provider.connection, bridgedTokenAccount.address)).amount;
+  expect(bridgedBalance.toString()).toEqual(bridgedBalanceBefore.toString());
+  const drainedPositionData = (await client.getPosition(newPosition, true)).getData();
+  expect(drainedPositionData.liquidity.toString()).toEqual("0");
+
+  console.log("Successfully tested withdraw to position");
+}
+
+main();
@@ -0,0 +1,377 @@
+import * as idl from "../target/idl/liquidity_lockbox.json";
+import * as idl_whirlpool from "../artifacts/whirlpool.json";
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { LiquidityLockbox } from "../target/types/liquidity_lockbox";
+import {
+  createMint, getOrCreateAssociatedTokenAccount, syncNative, getAssociatedTokenAddress, TOKEN_PROGRAM_ID
+} from "@solana/spl-token";
+import {
+  WhirlpoolContext, buildWhirlpoolClient, increaseLiquidityQuoteByInputTokenWithParams, swapQuoteByInputToken,
+  PDAUtil, TickUtil
+} from "@orca-so/whirlpools-sdk";
+import { DecimalUtil, Percentage } from "@orca-so/common-sdk";
+import Decimal from "decimal.js";
+
+// Shared localnet setup of the lockbox test scripts, the accounts are the ones loaded by validator.sh
+
+export const PROGRAM_ID = new anchor.web3.PublicKey("1BoXeb8hobfLCHNsyCoG1jpEv41ez4w4eDrJ48N1jY3");
+export const MOCK_BRIDGE_ID = new anchor.web3.PublicKey("6HCxLt7gbbyCVc4rY9ERsBA69XaRyievEZ7CpAJ1MeJU");
+export const BPF_LOADER_UPGRADEABLE = new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
+export const orca = new anchor.web3.PublicKey("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+export const whirlpool = new anchor.web3.PublicKey("5dMKUYJDsjZkAD3wiV3ViQkuq9pSmWQ5eAzcQLtDnUT3");
+export const sol = new anchor.web3.PublicKey("So11111111111111111111111111111111111111112");
+export const olas = new anchor.web3.PublicKey("Ez3nzG9ofodYCvEmw73XhQ87LWNYVRM2s7diB5tBZPyM");
+export const tokenVaultA = new anchor.web3.PublicKey("CLA8hU8SkdCZ9cJVLMfZQfcgAsywZ9txBJ6qrRAqthLx");
+export const tokenVaultB = new anchor.web3.PublicKey("6E8pzDK8uwpENc49kp5xo5EGydYjtamPSmUKXxum4ybb");
+export const tickArrayLower = new anchor.web3.PublicKey("3oJAqTKTCdGvLS9zpoBquWvMjwthu9Np67Qp4W8AT843");
+export const tickArrayUpper = new anchor.web3.PublicKey("J3eMJUQWLmSsG5VnXVFHCGwakpKmzi4jkNvi3vbCZQ3o");
+
+// Waits for the transaction to be confirmed
+export async function confirm(provider: anchor.AnchorProvider, signature: string) {
+  await provider.connection.confirmTransaction({
+      signature: signature,
+      ...(await provider.connection.getLatestBlockhash()),
+  });
+}
+
+// Creates the mock core bridge config with the message fee and a funded core bridge fee collector
+export async function setupMockCoreBridge(provider: anchor.AnchorProvider, payer: anchor.web3.Keypair, messageFee: number) {
+  // Core bridge config with the message fee stored after the guardian set index and last lamports
+  const wormholeBridge = anchor.web3.Keypair.generate();
+  const bridgeData = Buffer.alloc(24);
+  bridgeData.writeBigUInt64LE(BigInt(messageFee), 16);
+  const tx = new anchor.web3.Transaction().add(new anchor.web3.TransactionInstruction({
+    programId: MOCK_BRIDGE_ID,
+    keys: [
+      { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+      { pubkey: wormholeBridge.publicKey, isSigner: true, isWritable: true },
+      { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false }
+    ],
+    data: Buffer.concat([Buffer.from([0]), bridgeData])
+  }));
+  await provider.sendAndConfirm(tx, [wormholeBridge]);
+
+  // Core bridge fee collector
+  const wormholeFeeCollector = anchor.web3.Keypair.generate().publicKey;
+  await confirm(provider, await provider.connection.requestAirdrop(wormholeFeeCollector, 1000000000));
+
+  return { wormholeBridge, wormholeFeeCollector };
+}
+
+// Gets the program, the whirlpool and the lockbox PDA, the lockbox does not need to be initialized
+export async function setupProgram() {
+  // Configure the client to use the local cluster.
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = new Program(idl as anchor.Idl, PROGRAM_ID, anchor.getProvider()) as Program<LiquidityLockbox>;
+  const [programData] = await anchor.web3.PublicKey.findProgramAddress([PROGRAM_ID.toBuffer()], BPF_LOADER_UPGRADEABLE);
+
+  // User wallet is the provider payer and the program upgrade authority
+  const userWallet = provider.wallet["payer"];
+  console.log("User wallet:", userWallet.publicKey.toBase58());
+
+  const ctx = WhirlpoolContext.withProvider(provider, orca);
+  const client = buildWhirlpoolClient(ctx);
+  const whirlpoolClient = await client.getPool(whirlpool);
+  const whirlpool_data = whirlpoolClient.getData();
+  const token_a = whirlpoolClient.getTokenAInfo();
+  const token_b = whirlpoolClient.getTokenBInfo();
+
+  // Find a PDA account for the program
+  const [pdaProgram] = await anchor.web3.PublicKey.findProgramAddress([Buffer.from("liquidity_lockbox", "utf-8")], program.programId);
+
+  // Accounts of the admin instructions gated by the program upgrade authority
+  function adminAccounts(signer = userWallet) {
+    return { signer: signer.publicKey, program: PROGRAM_ID, programData, lockbox: pdaProgram };
+  }
+
+  return {
+    provider, program, programData, userWallet, ctx, client, whirlpoolClient, whirlpool_data, token_a, token_b, pdaProgram,
+    adminAccounts
+  };
+}
+
+// Gets the initialized lockbox along with the user token accounts and the deposit and withdraw helpers,
+// the lockbox must be initialized with tests/lockbox_init.ts
+export async function setupLockbox() {
+  const env = await setupProgram();
+  const { provider, program, userWallet, client, whirlpool_data, token_a, token_b, pdaProgram } = env;
+
+  // Get the lockbox state data
+  const lockboxStateData = await program.account.liquidityLockbox.fetch(pdaProgram);
+  const position = lockboxStateData.position;
+  const pdaPositionAccount = lockboxStateData.pdaPositionAccount;
+  const bridgedTokenMint = lockboxStateData.bridgedTokenMint;
+  const positionData = (await client.getPosition(position)).getData();
+  const positionMint = positionData.positionMint;
+  const [lower_tick_index, upper_tick_index] = TickUtil.getFullRangeTickIndex(whirlpool_data.tickSpacing);
+
+  // Get the user token ATAs
+  const tokenOwnerAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      userWallet.publicKey
+  );
+  await provider.connection.requestAirdrop(tokenOwnerAccountA.address, 100000000000);
+  await syncNative(provider.connection, userWallet, tokenOwnerAccountA.address);
+  const tokenOwnerAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      userWallet.publicKey
+  );
+  const bridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      userWallet.publicKey
+  );
+
+  // Gets the deposit quote for the provided amount of OLAS over the lockbox position range
+  function quoteFor(olasAmount: string) {
+    return increaseLiquidityQuoteByInputTokenWithParams({
+      tokenMintA: token_a.mint,
+      tokenMintB: token_b.mint,
+      sqrtPrice: whirlpool_data.sqrtPrice,
+      tickCurrentIndex: whirlpool_data.tickCurrentIndex,
+      tickLowerIndex: lower_tick_index,
+      tickUpperIndex: upper_tick_index,
+      inputTokenMint: olas,
+      inputTokenAmount: DecimalUtil.toBN(new Decimal(olasAmount), 8),
+      slippageTolerance: Percentage.fromFraction(10, 1000),
+    });
+  }
+
+  // Accounts of the deposit into the lockbox position
+  function depositAccounts() {
+    return {
+      position: position,
+      positionMint: positionMint,
+      pdaPositionAccount: pdaPositionAccount,
+      whirlpool: whirlpool,
+      tokenOwnerAccountA: tokenOwnerAccountA.address,
+      tokenOwnerAccountB: tokenOwnerAccountB.address,
+      tokenVaultA: tokenVaultA,
+      tokenVaultB: tokenVaultB,
+      tickArrayLower: tickArrayLower,
+      tickArrayUpper: tickArrayUpper,
+      bridgedTokenAccount: bridgedTokenAccount.address,
+      bridgedTokenMint: bridgedTokenMint,
+      lockbox: pdaProgram,
+      whirlpoolProgram: orca
+    };
+  }
+
+  // Accounts of the withdraw from the lockbox position, fee collectors are taken from the current lockbox state
+  async function withdrawAccounts() {
+    const state = await program.account.liquidityLockbox.fetch(pdaProgram);
+    return {
+      lockbox: pdaProgram,
+      whirlpoolProgram: orca,
+      whirlpool: whirlpool,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      position: position,
+      positionMint: positionMint,
+      bridgedTokenAccount: bridgedTokenAccount.address,
+      bridgedTokenMint: bridgedTokenMint,
+      pdaPositionAccount: pdaPositionAccount,
+      tokenOwnerAccountA: tokenOwnerAccountA.address,
+      tokenOwnerAccountB: tokenOwnerAccountB.address,
+      feeCollectorTokenOwnerAccountA: state.feeCollectorTokenOwnerAccountA,
+      feeCollectorTokenOwnerAccountB: state.feeCollectorTokenOwnerAccountB,
+      tokenVaultA: tokenVaultA,
+      tokenVaultB: tokenVaultB,
+      tickArrayLower: tickArrayLower,
+      tickArrayUpper: tickArrayUpper
+    };
+  }
+
+  // Deposits the provided amount of OLAS and returns the minted liquidity
+  async function deposit(olasAmount: string, proof = []) {
+    const quote = quoteFor(olasAmount);
+    const signature = await program.methods.deposit(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB, proof)
+      .accounts(depositAccounts())
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+
+    return quote.liquidityAmount;
+  }
+
+  // Withdraws the provided amount of liquidity with no token minimums
+  async function withdraw(amount: anchor.BN) {
+    const signature = await program.methods.withdraw(amount, new anchor.BN(0), new anchor.BN(0))
+      .accounts(await withdrawAccounts())
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+  }
+
+  return {
+    ...env, position, pdaPositionAccount, bridgedTokenMint, positionMint, lower_tick_index, upper_tick_index,
+    tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, quoteFor, depositAccounts, withdrawAccounts, deposit, withdraw
+  };
+}
+
+// Initializes the lockbox with a new position of the provided number of tick spacings on each side of the current tick,
+// the lockbox must not be initialized yet, i.e. the validator.sh must be freshly started
+export async function setupRangeLockbox(numTickSpacings: number) {
+  const env = await setupProgram();
+  const { provider, program, userWallet, whirlpoolClient, whirlpool_data, token_a, token_b, pdaProgram } = env;
+  const program_whirlpool = new Program(idl_whirlpool as anchor.Idl, orca, anchor.getProvider());
+
+  const tickSpacing = whirlpool_data.tickSpacing;
+  const alignedTickIndex = Math.floor(whirlpool_data.tickCurrentIndex / tickSpacing) * tickSpacing;
+  const lower_tick_index = alignedTickIndex - numTickSpacings * tickSpacing;
+  const upper_tick_index = alignedTickIndex + numTickSpacings * tickSpacing;
+  console.log("lower & upper tick_index:", lower_tick_index, upper_tick_index);
+
+  // Initialize tick arrays of the range and the ones below for moving the price out of the range
+  const ticksInArray = 88 * tickSpacing;
+  const initTickArraysTx = await whirlpoolClient.initTickArrayForTicks([
+    lower_tick_index, upper_tick_index, lower_tick_index - ticksInArray, lower_tick_index - 2 * ticksInArray
+  ]);
+  if (initTickArraysTx) {
+    await initTickArraysTx.buildAndExecute();
+  }
+  const rangeTickArrayLower = PDAUtil.getTickArrayFromTickIndex(lower_tick_index, tickSpacing, whirlpool, orca).publicKey;
+  const rangeTickArrayUpper = PDAUtil.getTickArrayFromTickIndex(upper_tick_index, tickSpacing, whirlpool, orca).publicKey;
+
+  // Create new bridged token mint with the pda mint authority
+  const bridgedTokenMint = await createMint(provider.connection, userWallet, pdaProgram, null, 8);
+
+  // Get the user token ATAs, also used as fee collectors
+  const tokenOwnerAccountA = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_a.mint,
+      userWallet.publicKey
+  );
+  const tokenOwnerAccountB = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      token_b.mint,
+      userWallet.publicKey
+  );
+
+  // Simulate SOL transfer and the sync of native SOL
+  await provider.connection.requestAirdrop(tokenOwnerAccountA.address, 1000000000000);
+  await syncNative(provider.connection, userWallet, tokenOwnerAccountA.address);
+
+  const bridgedTokenAccount = await getOrCreateAssociatedTokenAccount(
+      provider.connection,
+      userWallet,
+      bridgedTokenMint,
+      userWallet.publicKey
+  );
+
+  // Create the lockbox position of the range
+  const positionMintKeypair = anchor.web3.Keypair.generate();
+  const positionMint = positionMintKeypair.publicKey;
+  const positionPda = PDAUtil.getPosition(orca, positionMint);
+  const position = positionPda.publicKey;
+  const pdaPositionAccount = await getAssociatedTokenAddress(positionMint, pdaProgram, true);
+
+  let signature = await program_whirlpool.methods.openPosition(
+    positionPda.bump,
+    lower_tick_index,
+    upper_tick_index)
+    .accounts(
+      {
+        funder: userWallet.publicKey,
+        owner: pdaProgram,
+        position: position,
+        positionMint: positionMint,
+        positionTokenAccount: pdaPositionAccount,
+        whirlpool
+      }
+    )
+    .signers([positionMintKeypair])
+    .rpc();
+  await confirm(provider, signature);
+
+  // Initialize the LiquidityLockbox state
+  signature = await program.methods
+    .initialize()
+    .accounts(
+      {
+        bridgedTokenMint: bridgedTokenMint,
+        feeCollectorTokenOwnerAccountA: tokenOwnerAccountA.address,
+        feeCollectorTokenOwnerAccountB: tokenOwnerAccountB.address,
+        position: position,
+        positionMint: positionMint,
+        pdaPositionAccount,
+        whirlpool
+      }
+    )
+    .rpc();
+  await confirm(provider, signature);
+
+  // Deposits liquidity for the OLAS amount at the current whirlpool state and returns the deposit quote
+  async function deposit(olasAmount: string) {
+    await whirlpoolClient.refreshData();
+    const data = whirlpoolClient.getData();
+    const quote = increaseLiquidityQuoteByInputTokenWithParams({
+      tokenMintA: token_a.mint,
+      tokenMintB: token_b.mint,
+      sqrtPrice: data.sqrtPrice,
+      tickCurrentIndex: data.tickCurrentIndex,
+      tickLowerIndex: lower_tick_index,
+      tickUpperIndex: upper_tick_index,
+      inputTokenMint: olas,
+      inputTokenAmount: DecimalUtil.toBN(new Decimal(olasAmount), 8),
+      slippageTolerance: Percentage.fromFraction(10, 1000),
+    });
+
+    const signature = await program.methods.deposit(quote.liquidityAmount, quote.tokenMaxA, quote.tokenMaxB, [])
+      .accounts(
+          {
+            position: position,
+            positionMint: positionMint,
+            pdaPositionAccount: pdaPositionAccount,
+            whirlpool: whirlpool,
+            tokenOwnerAccountA: tokenOwnerAccountA.address,
+            tokenOwnerAccountB: tokenOwnerAccountB.address,
+            tokenVaultA: tokenVaultA,
+            tokenVaultB: tokenVaultB,
+            tickArrayLower: rangeTickArrayLower,
+            tickArrayUpper: rangeTickArrayUpper,
+            bridgedTokenAccount: bridgedTokenAccount.address,
+            bridgedTokenMint: bridgedTokenMint,
+            lockbox: pdaProgram,
+            whirlpoolProgram: orca
+          }
+      )
+      .signers([userWallet])
+      .rpc();
+    await confirm(provider, signature);
+
+    return quote;
+  }
+
+  // Swaps SOL for OLAS until the current tick goes below the lockbox position range
+  async function movePriceBelowRange() {
+    for (let i = 0; i < 20 && whirlpoolClient.getData().tickCurrentIndex >= lower_tick_index; i++) {
+      const swapQuote = await swapQuoteByInputToken(
+        whirlpoolClient,
+        sol,
+        DecimalUtil.toBN(new Decimal("10" /* sol */), 9),
+        Percentage.fromFraction(10, 100),
+        orca,
+        env.ctx.fetcher,
+        true
+      );
+      await (await whirlpoolClient.swap(swapQuote)).buildAndExecute();
+      await whirlpoolClient.refreshData();
+    }
+  }
+
+  return {
+    ...env, tickSpacing, lower_tick_index, upper_tick_index, tickArrayLower: rangeTickArrayLower,
+    tickArrayUpper: rangeTickArrayUpper, bridgedTokenMint, position, positionMint, pdaPositionAccount,
+    tokenOwnerAccountA, tokenOwnerAccountB, bridgedTokenAccount, deposit, movePriceBelowRange
+  };
+}